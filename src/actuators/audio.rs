@@ -18,6 +18,18 @@ pub struct SetMuteParams {
     pub muted: bool,
 }
 
+// === Helper Functions ===
+
+/// Current system volume (percent), for the undo journal
+pub fn current_volume() -> u8 {
+    cpvc::get_system_volume()
+}
+
+/// Current system mute state, for the undo journal
+pub fn current_mute() -> bool {
+    cpvc::get_mute()
+}
+
 // === Tool Functions ===
 
 pub async fn get_volume() -> Result<CallToolResult, McpError> {
@@ -37,7 +49,7 @@ pub async fn set_volume(params: SetVolumeParams) -> Result<CallToolResult, McpEr
             volume
         ))]))
     } else {
-        Ok(CallToolResult::success(vec![Content::text(
+        Ok(CallToolResult::error(vec![Content::text(
             "Failed to set volume",
         )]))
     }
@@ -61,7 +73,7 @@ pub async fn set_mute(params: SetMuteParams) -> Result<CallToolResult, McpError>
             },
         )]))
     } else {
-        Ok(CallToolResult::success(vec![Content::text(
+        Ok(CallToolResult::error(vec![Content::text(
             "Failed to change mute status",
         )]))
     }
@@ -5,7 +5,7 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use std::process::Command;
 
-use crate::shared::internal_error;
+use crate::shared::{internal_error, CommandExt};
 
 // === Parameter Types ===
 
@@ -49,7 +49,7 @@ pub async fn set_reminder(params: SetReminderParams) -> Result<CallToolResult, M
 
     cmd.arg(&params.input);
 
-    match cmd.output() {
+    match cmd.traced_output() {
         Ok(output) => {
             if output.status.success() {
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -70,7 +70,7 @@ pub async fn set_reminder(params: SetReminderParams) -> Result<CallToolResult, M
 }
 
 pub async fn list_reminders() -> Result<CallToolResult, McpError> {
-    match Command::new("breakrs").arg("list").output() {
+    match Command::new("breakrs").arg("list").traced_output() {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             if stdout.trim().is_empty() {
@@ -91,7 +91,7 @@ pub async fn remove_reminder(params: RemoveReminderParams) -> Result<CallToolRes
     match Command::new("breakrs")
         .arg("remove")
         .arg(&params.id)
-        .output()
+        .traced_output()
     {
         Ok(output) => {
             if output.status.success() {
@@ -112,7 +112,7 @@ pub async fn remove_reminder(params: RemoveReminderParams) -> Result<CallToolRes
 }
 
 pub async fn clear_reminders() -> Result<CallToolResult, McpError> {
-    match Command::new("breakrs").arg("clear").output() {
+    match Command::new("breakrs").arg("clear").traced_output() {
         Ok(output) => {
             if output.status.success() {
                 Ok(CallToolResult::success(vec![Content::text(
@@ -131,7 +131,7 @@ pub async fn clear_reminders() -> Result<CallToolResult, McpError> {
 }
 
 pub async fn daemon_status() -> Result<CallToolResult, McpError> {
-    match Command::new("breakrs").arg("status").output() {
+    match Command::new("breakrs").arg("status").traced_output() {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             Ok(CallToolResult::success(vec![Content::text(
@@ -143,7 +143,7 @@ pub async fn daemon_status() -> Result<CallToolResult, McpError> {
 }
 
 pub async fn get_history() -> Result<CallToolResult, McpError> {
-    match Command::new("breakrs").arg("history").output() {
+    match Command::new("breakrs").arg("history").traced_output() {
         Ok(output) => {
             let stdout = String::from_utf8_lossy(&output.stdout);
             if stdout.trim().is_empty() {
@@ -31,6 +31,19 @@ pub struct CameraIndexParams {
     pub index: Option<u32>,
 }
 
+/// Check whether at least one camera is present
+pub async fn probe() -> Result<String, String> {
+    let cameras = tokio::task::spawn_blocking(|| query(nokhwa::utils::ApiBackend::Auto))
+        .await
+        .map_err(|e| format!("Task error: {}", e))?
+        .map_err(|e| format!("Failed to query cameras: {}", e))?;
+
+    if cameras.is_empty() {
+        return Err("No cameras found".to_string());
+    }
+    Ok(format!("{} camera(s)", cameras.len()))
+}
+
 pub async fn list_cameras() -> Result<CallToolResult, McpError> {
     let cameras = query(nokhwa::utils::ApiBackend::Auto)
         .map_err(|e| internal_error(format!("Failed to query cameras: {}", e)))?;
@@ -14,6 +14,15 @@ pub struct WriteClipboardParams {
     pub text: String,
 }
 
+// === Helper Functions ===
+
+/// Current clipboard text (`None` when empty or not text), for the undo journal
+pub fn current_text() -> Result<Option<String>, String> {
+    let mut clipboard =
+        Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    Ok(clipboard.get_text().ok().filter(|text| !text.is_empty()))
+}
+
 // === Tool Functions ===
 
 pub async fn read_clipboard() -> Result<CallToolResult, McpError> {
@@ -8,5 +8,6 @@ pub mod microphone;
 pub mod ollama;
 pub mod open;
 pub mod printers;
+pub mod process;
 pub mod screenshot;
 pub mod trash;
@@ -4,7 +4,13 @@ use rmcp::{model::*, ErrorData as McpError};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-const DEFAULT_HOST: &str = "http://localhost:11434";
+use crate::egress::Egress;
+use crate::shared::RequestBuilderExt;
+
+pub const DEFAULT_HOST: &str = "http://localhost:11434";
+
+/// Where the Ollama daemon downloads models from on `pull_model`
+const REGISTRY_HOST: &str = "registry.ollama.ai";
 
 // === Parameter Types ===
 
@@ -70,6 +76,11 @@ struct ShowResponse {
     license: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct VersionResponse {
+    version: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct PullResponse {
     status: Option<String>,
@@ -91,18 +102,36 @@ fn format_size(bytes: u64) -> String {
     }
 }
 
-async fn get_client() -> Result<reqwest::Client, McpError> {
-    Ok(reqwest::Client::new())
+/// Check whether the Ollama API answers on the default host
+pub async fn probe() -> Result<String, String> {
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/version", DEFAULT_HOST);
+    let response = client
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(2))
+        .traced_send()
+        .await
+        .map_err(|e| format!("Ollama not reachable at {}: {}", DEFAULT_HOST, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Ollama at {} returned status {}", DEFAULT_HOST, response.status()));
+    }
+
+    let version: VersionResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Ollama version: {}", e))?;
+    Ok(format!("ollama {} at {}", version.version, DEFAULT_HOST))
 }
 
 // === Tool Functions ===
 
-pub async fn list_models(params: HostParams) -> Result<CallToolResult, McpError> {
-    let client = get_client().await?;
+pub async fn list_models(egress: &Egress, params: HostParams) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
     let url = format!("{}/api/tags", host);
 
-    let response = match client.get(&url).send().await {
+    let response = match client.get(&url).traced_send().await {
         Ok(r) => r,
         Err(e) => {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -145,12 +174,12 @@ pub async fn list_models(params: HostParams) -> Result<CallToolResult, McpError>
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn list_running(params: HostParams) -> Result<CallToolResult, McpError> {
-    let client = get_client().await?;
+pub async fn list_running(egress: &Egress, params: HostParams) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
     let url = format!("{}/api/ps", host);
 
-    let response = match client.get(&url).send().await {
+    let response = match client.get(&url).traced_send().await {
         Ok(r) => r,
         Err(e) => {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -197,9 +226,9 @@ pub async fn list_running(params: HostParams) -> Result<CallToolResult, McpError
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn show_model(params: ModelParams) -> Result<CallToolResult, McpError> {
-    let client = get_client().await?;
+pub async fn show_model(egress: &Egress, params: ModelParams) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
     let url = format!("{}/api/show", host);
 
     let request = ModelRequest {
@@ -207,7 +236,7 @@ pub async fn show_model(params: ModelParams) -> Result<CallToolResult, McpError>
         stream: None,
     };
 
-    let response = match client.post(&url).json(&request).send().await {
+    let response = match client.post(&url).json(&request).traced_send().await {
         Ok(r) => r,
         Err(e) => {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -253,9 +282,10 @@ pub async fn show_model(params: ModelParams) -> Result<CallToolResult, McpError>
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn pull_model(params: ModelParams) -> Result<CallToolResult, McpError> {
-    let client = get_client().await?;
+pub async fn pull_model(egress: &Egress, params: ModelParams) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
+    egress.check_host(REGISTRY_HOST, 443).await?;
     let url = format!("{}/api/pull", host);
 
     let request = ModelRequest {
@@ -263,7 +293,7 @@ pub async fn pull_model(params: ModelParams) -> Result<CallToolResult, McpError>
         stream: Some(false),
     };
 
-    let response = match client.post(&url).json(&request).send().await {
+    let response = match client.post(&url).json(&request).traced_send().await {
         Ok(r) => r,
         Err(e) => {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -298,9 +328,12 @@ pub async fn pull_model(params: ModelParams) -> Result<CallToolResult, McpError>
     ))]))
 }
 
-pub async fn delete_model(params: ModelParams) -> Result<CallToolResult, McpError> {
-    let client = get_client().await?;
+pub async fn delete_model(
+    egress: &Egress,
+    params: ModelParams,
+) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
     let url = format!("{}/api/delete", host);
 
     let request = ModelRequest {
@@ -308,7 +341,7 @@ pub async fn delete_model(params: ModelParams) -> Result<CallToolResult, McpErro
         stream: None,
     };
 
-    let response = match client.delete(&url).json(&request).send().await {
+    let response = match client.delete(&url).json(&request).traced_send().await {
         Ok(r) => r,
         Err(e) => {
             return Ok(CallToolResult::success(vec![Content::text(format!(
@@ -359,29 +392,16 @@ pub struct LoadedModel {
 
 // === Composite Function ===
 
-pub async fn get_ollama_status(params: HostParams) -> Result<CallToolResult, McpError> {
-    let client = match get_client().await {
-        Ok(c) => c,
-        Err(_) => {
-            let status = OllamaStatus {
-                online: false,
-                host: get_host(params.host.as_deref()),
-                installed_models: vec![],
-                running_models: vec![],
-                installed_count: 0,
-                running_count: 0,
-            };
-            return Ok(CallToolResult::success(vec![Content::text(
-                serde_json::to_string_pretty(&status).unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e)),
-            )]));
-        }
-    };
-
+pub async fn get_ollama_status(
+    egress: &Egress,
+    params: HostParams,
+) -> Result<CallToolResult, McpError> {
     let host = get_host(params.host.as_deref());
+    let client = egress.client(&host).await?;
 
     // Check if Ollama is reachable and get installed models
     let tags_url = format!("{}/api/tags", host);
-    let installed_models = match client.get(&tags_url).send().await {
+    let installed_models = match client.get(&tags_url).traced_send().await {
         Ok(response) if response.status().is_success() => {
             match response.json::<TagsResponse>().await {
                 Ok(tags) => tags.models.unwrap_or_default()
@@ -412,7 +432,7 @@ pub async fn get_ollama_status(params: HostParams) -> Result<CallToolResult, Mcp
 
     // Get running models
     let ps_url = format!("{}/api/ps", host);
-    let running_models: Vec<LoadedModel> = match client.get(&ps_url).send().await {
+    let running_models: Vec<LoadedModel> = match client.get(&ps_url).traced_send().await {
         Ok(response) if response.status().is_success() => {
             match response.json::<PsResponse>().await {
                 Ok(ps) => ps.models.unwrap_or_default()
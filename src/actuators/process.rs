@@ -0,0 +1,331 @@
+//! Process signalling actuators
+//!
+//! Before a signal is sent the target is looked up fresh and checked: PID 1, the
+//! server itself and its parent, processes of other users and protected names are
+//! refused, and the process name must match what the caller expects so a reused PID
+//! is never hit by mistake.
+
+use crate::config::SignalConfig;
+use crate::shared::{internal_error, policy_error};
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use sysinfo::{
+    Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, Signal, System, UpdateKind,
+};
+
+/// Never signalled, on top of `[signals] protected`
+const BUILTIN_PROTECTED: &[&str] = &[
+    "init",
+    "systemd",
+    "dbus-daemon",
+    "dbus-broker",
+    "sshd",
+    "Xorg",
+    "Xwayland",
+    "gnome-shell",
+    "kwin_wayland",
+    "i3",
+    "sway",
+];
+
+const DEFAULT_GRACE_SECS: u64 = 5;
+/// The call holds the request open for the whole grace period
+const MAX_GRACE_SECS: u64 = 60;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SignalProcessParams {
+    #[schemars(description = "Process ID to signal")]
+    pub pid: u32,
+    #[schemars(
+        description = "Name the process is expected to have (as shown by find_process); the call is refused if it differs"
+    )]
+    pub expected_name: String,
+    #[schemars(
+        description = "Signal: TERM, KILL, INT, HUP, QUIT, USR1, USR2, STOP or CONT (default TERM)"
+    )]
+    #[serde(default)]
+    pub signal: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TerminateParams {
+    #[schemars(description = "Process ID to terminate")]
+    pub pid: u32,
+    #[schemars(
+        description = "Name the process is expected to have (as shown by find_process); the call is refused if it differs"
+    )]
+    pub expected_name: String,
+    #[schemars(
+        description = "Seconds to wait after TERM before sending KILL (default 5, at most 60)"
+    )]
+    #[serde(default)]
+    pub grace_secs: Option<u64>,
+}
+
+// === Helper Functions ===
+
+fn grace_period(grace_secs: Option<u64>) -> Result<Duration, McpError> {
+    match grace_secs.unwrap_or(DEFAULT_GRACE_SECS) {
+        secs if secs > MAX_GRACE_SECS => Err(McpError::invalid_params(
+            format!("grace_secs must be at most {}", MAX_GRACE_SECS),
+            None,
+        )),
+        secs => Ok(Duration::from_secs(secs)),
+    }
+}
+
+fn parse_signal(name: &str) -> Option<Signal> {
+    let name = name.trim().to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    match name {
+        "TERM" => Some(Signal::Term),
+        "KILL" => Some(Signal::Kill),
+        "INT" => Some(Signal::Interrupt),
+        "HUP" => Some(Signal::Hangup),
+        "QUIT" => Some(Signal::Quit),
+        "USR1" => Some(Signal::User1),
+        "USR2" => Some(Signal::User2),
+        "STOP" => Some(Signal::Stop),
+        "CONT" => Some(Signal::Continue),
+        _ => None,
+    }
+}
+
+fn refused(pid: u32, reason: &str, msg: String) -> McpError {
+    policy_error(
+        msg,
+        serde_json::json!({
+            "policy": "process_signal",
+            "reason": reason,
+            "pid": pid,
+        }),
+    )
+}
+
+/// Refresh just the processes we need to look at
+fn refresh(sys: &mut System, pids: &[Pid]) {
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(pids),
+        true,
+        ProcessRefreshKind::nothing()
+            .with_user(UpdateKind::Always)
+            .with_exe(UpdateKind::OnlyIfNotSet),
+    );
+}
+
+/// Whether `pid` may be signalled by this caller. Returns the process start time, which
+/// tells the original process apart from a later one reusing its PID.
+fn check(
+    config: &SignalConfig,
+    sys: &mut System,
+    pid: u32,
+    expected_name: &str,
+) -> Result<u64, McpError> {
+    if pid <= 1 {
+        return Err(refused(
+            pid,
+            "init",
+            format!("Refusing to signal PID {}", pid),
+        ));
+    }
+
+    let own = sysinfo::get_current_pid()
+        .map_err(|e| internal_error(format!("Unknown own PID: {}", e)))?;
+    let target = Pid::from_u32(pid);
+    refresh(sys, &[own, target]);
+
+    let me = sys.process(own);
+    if target == own || me.and_then(|p| p.parent()) == Some(target) {
+        return Err(refused(
+            pid,
+            "self",
+            format!(
+                "Refusing to signal PID {}: it is this server or its client",
+                pid
+            ),
+        ));
+    }
+
+    let proc = sys
+        .process(target)
+        .ok_or_else(|| internal_error(format!("Process {} not found", pid)))?;
+    let name = proc.name().to_string_lossy().to_string();
+    let exe_name = proc
+        .exe()
+        .and_then(|exe| exe.file_name())
+        .map(|n| n.to_string_lossy().to_string());
+
+    // Linux truncates names to 15 bytes, so the executable name counts as well
+    if name != expected_name && exe_name.as_deref() != Some(expected_name) {
+        return Err(policy_error(
+            format!(
+                "PID {} is '{}', not '{}' (it may have exited and the PID been reused)",
+                pid, name, expected_name
+            ),
+            serde_json::json!({
+                "policy": "process_signal",
+                "reason": "name_mismatch",
+                "pid": pid,
+                "actual_name": name,
+            }),
+        ));
+    }
+
+    let protected = BUILTIN_PROTECTED
+        .iter()
+        .copied()
+        .chain(config.protected.iter().map(String::as_str))
+        .find(|p| *p == name || exe_name.as_deref() == Some(*p));
+    if let Some(protected) = protected {
+        return Err(refused(
+            pid,
+            "protected",
+            format!(
+                "Refusing to signal PID {}: '{}' is protected",
+                pid, protected
+            ),
+        ));
+    }
+
+    let owner = proc.user_id();
+    if owner.is_none() || owner != me.and_then(|p| p.user_id()) {
+        return Err(refused(
+            pid,
+            "other_user",
+            format!("Refusing to signal PID {}: it belongs to another user", pid),
+        ));
+    }
+
+    Ok(proc.start_time())
+}
+
+fn send(sys: &System, pid: u32, signal: Signal) -> Result<(), McpError> {
+    let proc = sys
+        .process(Pid::from_u32(pid))
+        .ok_or_else(|| internal_error(format!("Process {} not found", pid)))?;
+    match proc.kill_with(signal) {
+        Some(true) => Ok(()),
+        Some(false) => Err(internal_error(format!(
+            "Failed to send {:?} to PID {}",
+            signal, pid
+        ))),
+        None => Err(internal_error(format!(
+            "Signal {:?} is not supported on this platform",
+            signal
+        ))),
+    }
+}
+
+/// Whether the process that started at `start_time` is gone (exited, reaped or a zombie)
+fn exited(sys: &mut System, pid: u32, start_time: u64) -> bool {
+    let target = Pid::from_u32(pid);
+    refresh(sys, &[target]);
+    match sys.process(target) {
+        Some(proc) => proc.start_time() != start_time || proc.status() == ProcessStatus::Zombie,
+        None => true,
+    }
+}
+
+// === Tool Functions ===
+
+pub async fn signal_process(
+    config: &SignalConfig,
+    params: SignalProcessParams,
+) -> Result<CallToolResult, McpError> {
+    let name = params.signal.as_deref().unwrap_or("TERM");
+    let signal = parse_signal(name)
+        .ok_or_else(|| McpError::invalid_params(format!("Unknown signal '{}'", name), None))?;
+
+    let mut sys = System::new();
+    check(config, &mut sys, params.pid, &params.expected_name)?;
+    send(&sys, params.pid, signal)?;
+
+    Ok(CallToolResult::success(vec![Content::text(format!(
+        "Sent {:?} to PID {} ({})",
+        signal, params.pid, params.expected_name
+    ))]))
+}
+
+pub async fn terminate_gracefully(
+    config: &SignalConfig,
+    params: TerminateParams,
+) -> Result<CallToolResult, McpError> {
+    let grace = grace_period(params.grace_secs)?;
+
+    let mut sys = System::new();
+    let start_time = check(config, &mut sys, params.pid, &params.expected_name)?;
+    send(&sys, params.pid, Signal::Term)?;
+
+    let sent = Instant::now();
+    while sent.elapsed() < grace {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if exited(&mut sys, params.pid, start_time) {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "PID {} ({}) exited after TERM in {:.1}s",
+                params.pid,
+                params.expected_name,
+                sent.elapsed().as_secs_f32()
+            ))]));
+        }
+    }
+
+    // Still the same process, so KILL can't hit a newcomer that reused the PID
+    if exited(&mut sys, params.pid, start_time) {
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "PID {} ({}) exited after TERM",
+            params.pid, params.expected_name
+        ))]));
+    }
+    send(&sys, params.pid, Signal::Kill)?;
+
+    tokio::time::sleep(POLL_INTERVAL).await;
+    let outcome = if exited(&mut sys, params.pid, start_time) {
+        "was killed"
+    } else {
+        "was sent KILL but is still listed (it may be in uninterruptible sleep)"
+    };
+    Ok(CallToolResult::success(vec![Content::text(format!(
+        "PID {} ({}) ignored TERM for {}s and {}",
+        params.pid,
+        params.expected_name,
+        grace.as_secs(),
+        outcome
+    ))]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("TERM"), Some(Signal::Term));
+        assert_eq!(parse_signal("sigkill"), Some(Signal::Kill));
+        assert_eq!(parse_signal(" Hup "), Some(Signal::Hangup));
+        assert_eq!(parse_signal("SEGV"), None);
+    }
+
+    #[test]
+    fn test_grace_period() {
+        assert_eq!(grace_period(None).unwrap(), Duration::from_secs(5));
+        assert_eq!(grace_period(Some(60)).unwrap(), Duration::from_secs(60));
+        assert!(grace_period(Some(61)).is_err());
+        assert!(grace_period(Some(u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_refuses_init_and_self() {
+        let config = SignalConfig::default();
+        let mut sys = System::new();
+        assert!(check(&config, &mut sys, 1, "systemd").is_err());
+
+        let own = std::process::id();
+        let err = check(&config, &mut sys, own, "anything").unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], "self");
+    }
+}
@@ -25,13 +25,75 @@ pub struct RestoreParams {
     pub name: String,
 }
 
+// === Helper Functions ===
+
+/// Restore the most recently trashed item for each original path
+pub async fn restore_paths(paths: &[PathBuf]) -> Result<CallToolResult, McpError> {
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    {
+        let items = match trash::os_limited::list() {
+            Ok(items) => items,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to list trash: {}",
+                    e
+                ))]))
+            }
+        };
+
+        let mut matches = Vec::new();
+        let mut missing = Vec::new();
+        for path in paths {
+            let latest = items
+                .iter()
+                .filter(|item| &item.original_path() == path)
+                .max_by_key(|item| item.time_deleted);
+            match latest {
+                Some(item) => matches.push(item.clone()),
+                None => missing.push(path.display().to_string()),
+            }
+        }
+
+        if matches.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Not in trash anymore: {}",
+                missing.join(", ")
+            ))]));
+        }
+
+        let count = matches.len();
+        match trash::os_limited::restore_all(matches) {
+            Ok(()) if missing.is_empty() => Ok(CallToolResult::success(vec![Content::text(
+                format!("Restored {} item(s)", count),
+            )])),
+            Ok(()) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Restored {} item(s); not in trash anymore: {}",
+                count,
+                missing.join(", ")
+            ))])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to restore: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        let _ = paths;
+        Ok(CallToolResult::error(vec![Content::text(
+            "Restoring from trash is not supported on this platform (Linux/Windows only)",
+        )]))
+    }
+}
+
 // === Tool Functions ===
 
 pub async fn trash_file(params: TrashFileParams) -> Result<CallToolResult, McpError> {
     let path = PathBuf::from(&params.path);
 
     if !path.exists() {
-        return Ok(CallToolResult::success(vec![Content::text(format!(
+        return Ok(CallToolResult::error(vec![Content::text(format!(
             "Path does not exist: {}",
             params.path
         ))]));
@@ -42,7 +104,7 @@ pub async fn trash_file(params: TrashFileParams) -> Result<CallToolResult, McpEr
             "Moved to trash: {}",
             params.path
         ))])),
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
             "Failed to trash: {}",
             e
         ))])),
@@ -64,7 +126,7 @@ pub async fn trash_files(params: TrashFilesParams) -> Result<CallToolResult, Mcp
     }
 
     if to_trash.is_empty() {
-        return Ok(CallToolResult::success(vec![Content::text(
+        return Ok(CallToolResult::error(vec![Content::text(
             "No valid paths to trash",
         )]));
     }
@@ -77,7 +139,7 @@ pub async fn trash_files(params: TrashFilesParams) -> Result<CallToolResult, Mcp
             }
             Ok(CallToolResult::success(vec![Content::text(msg)]))
         }
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
             "Failed to trash: {}",
             e
         ))])),
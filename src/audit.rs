@@ -0,0 +1,67 @@
+//! Audit trail
+//!
+//! Tool calls and policy decisions are emitted as structured tracing events on the
+//! `audit` target, so they can be filtered separately (e.g. `RUST_LOG=audit=info`).
+
+use crate::redact::Redaction;
+
+/// Record a completed tool call
+pub fn tool_call(tool: &str, success: bool) {
+    tracing::info!(target: "audit", tool, success, "tool call");
+}
+
+/// Record secrets removed from a tool's output
+pub fn redaction(tool: &str, redaction: &Redaction) {
+    tracing::warn!(
+        target: "audit",
+        tool,
+        detector = %redaction.detector,
+        count = redaction.count,
+        "redacted secret from tool output"
+    );
+}
+
+/// Record a sensitive capture (camera, microphone, screen) that was allowed, with
+/// how it ended: `ok`, `failed` or `cancelled`
+pub fn sensitive_capture(tool: &str, device: &str, outcome: &str) {
+    tracing::warn!(target: "audit", tool, device, outcome, "sensitive capture");
+}
+
+/// Record the outcome of a consent prompt
+pub fn consent(tool: &str, granted: bool, reason: &str) {
+    tracing::info!(target: "audit", tool, granted, reason, "capture consent");
+}
+
+/// Record a call refused because a quota was spent
+pub fn quota_exceeded(tool: &str, category: &str) {
+    tracing::warn!(target: "audit", tool, category, "quota exceeded");
+}
+
+/// Record a connection attempt to the split-mode helper
+pub fn helper_connection(uid: u32, allowed: bool) {
+    if allowed {
+        tracing::info!(target: "audit", uid, "helper connection accepted");
+    } else {
+        tracing::warn!(target: "audit", uid, "helper connection refused");
+    }
+}
+
+/// Record a call refused by a rate limit or daily quota
+pub fn rate_limited(tool: &str, limit: &str, reason: &str) {
+    tracing::warn!(target: "audit", tool, limit, reason, "rate limited");
+}
+
+/// Record a call refused by a time-of-day or presence policy rule
+pub fn condition_refused(tool: &str, rule: usize, reason: &str) {
+    tracing::warn!(target: "audit", tool, rule, reason, "refused by policy rule");
+}
+
+/// Record a connection refused by the `network_egress` setting
+pub fn egress_blocked(host: &str, mode: &str, reason: &str) {
+    tracing::warn!(target: "audit", host, mode, reason, "network egress blocked");
+}
+
+/// Record one step of a workflow
+pub fn workflow_step(workflow: &str, step: &str, tool: &str, status: &str) {
+    tracing::info!(target: "audit", workflow, step, tool, status, "workflow step");
+}
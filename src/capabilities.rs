@@ -0,0 +1,347 @@
+//! Backend capability probing
+//!
+//! Checks at startup which optional backends (i3, xdotool, BlueZ, ...) are reachable,
+//! so tools depending on a missing backend can be hidden or marked unavailable.
+
+use rmcp::{model::*, ErrorData as McpError};
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::shared::internal_error;
+
+/// How long a single backend probe may take before it counts as unavailable
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// An external service or binary that a group of tools depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    I3,
+    Xdotool,
+    Breakrs,
+    Bluez,
+    Pulseaudio,
+    Ollama,
+    Camera,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 7] = [
+        Backend::I3,
+        Backend::Xdotool,
+        Backend::Breakrs,
+        Backend::Bluez,
+        Backend::Pulseaudio,
+        Backend::Ollama,
+        Backend::Camera,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::I3 => "i3",
+            Backend::Xdotool => "xdotool",
+            Backend::Breakrs => "breakrs",
+            Backend::Bluez => "bluez",
+            Backend::Pulseaudio => "pulseaudio",
+            Backend::Ollama => "ollama",
+            Backend::Camera => "camera",
+        }
+    }
+
+    /// Tools that fail without this backend
+    pub fn tools(&self) -> &'static [&'static str] {
+        match self {
+            Backend::I3 => &[
+                "get_workspaces",
+                "get_tree",
+                "switch_workspace",
+                "focus_window",
+                "move_to_workspace",
+                "run_command",
+                "exec",
+                "kill",
+                "kill_window",
+                "fullscreen",
+                "get_outputs",
+                "get_marks",
+                "get_binding_modes",
+                "get_version",
+                "get_scratchpad",
+                "get_workspace_status",
+            ],
+            Backend::Xdotool => &[
+                "move_mouse",
+                "click",
+                "click_at",
+                "type_text",
+                "key_press",
+                "scroll",
+                "get_mouse_position",
+                "double_click",
+                "search_window",
+                "get_active_window",
+                "get_window_geometry",
+                "get_window_name",
+            ],
+            Backend::Breakrs => &[
+                "set_reminder",
+                "list_reminders",
+                "remove_reminder",
+                "clear_reminders",
+                "daemon_status",
+                "get_history",
+            ],
+            Backend::Bluez => &[
+                "list_adapters",
+                "get_adapter_info",
+                "discover_devices",
+                "list_known_devices",
+                "get_device_info",
+                "pair_device",
+                "remove_device",
+                "connect_device",
+                "disconnect_device",
+                "get_bluetooth_status",
+            ],
+            Backend::Pulseaudio => &[
+                "list_sinks",
+                "list_sources",
+                "list_sink_inputs",
+                "list_source_outputs",
+                "get_default_sink",
+                "get_default_source",
+                "set_default_sink",
+                "set_default_source",
+                "set_sink_input_volume",
+                "set_sink_input_mute",
+                "move_sink_input",
+                "get_audio_status",
+            ],
+            Backend::Ollama => &[
+                "list_models",
+                "list_running",
+                "show_model",
+                "pull_model",
+                "delete_model",
+                "get_ollama_status",
+            ],
+            Backend::Camera => &["capture_camera", "get_camera_info"],
+        }
+    }
+
+    /// Whether a failed probe means the tools cannot work at all. The Ollama probe
+    /// only tries the default host, while the tools can be pointed at any other, so
+    /// those are marked unavailable but never auto-disabled.
+    pub fn can_auto_disable(&self) -> bool {
+        *self != Backend::Ollama
+    }
+}
+
+/// Find the backend a tool depends on, if any
+pub fn backend_for(tool_name: &str) -> Option<Backend> {
+    Backend::ALL
+        .into_iter()
+        .find(|backend| backend.tools().contains(&tool_name))
+}
+
+/// Result of probing one backend
+#[derive(Debug, Clone, Serialize)]
+pub struct Probe {
+    pub backend: Backend,
+    pub available: bool,
+    pub detail: String,
+}
+
+/// Probe results for all backends
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Capabilities {
+    pub probes: Vec<Probe>,
+}
+
+impl Capabilities {
+    /// Probe every backend concurrently
+    pub async fn probe() -> Self {
+        let (i3, xdotool, breakrs, bluez, pulseaudio, ollama, camera) = tokio::join!(
+            probe_backend(Backend::I3),
+            probe_backend(Backend::Xdotool),
+            probe_backend(Backend::Breakrs),
+            probe_backend(Backend::Bluez),
+            probe_backend(Backend::Pulseaudio),
+            probe_backend(Backend::Ollama),
+            probe_backend(Backend::Camera),
+        );
+
+        Self {
+            probes: vec![i3, xdotool, breakrs, bluez, pulseaudio, ollama, camera],
+        }
+    }
+
+    /// The failed probe for a tool's backend, if the tool depends on a missing backend
+    pub fn unavailable(&self, tool_name: &str) -> Option<&Probe> {
+        let backend = backend_for(tool_name)?;
+        self.probes
+            .iter()
+            .find(|p| p.backend == backend && !p.available)
+    }
+
+    /// Human-readable report for `rmcp-presence doctor`
+    pub fn report(&self) -> String {
+        let mut output = String::from("Backend probes:\n\n");
+        for probe in &self.probes {
+            output.push_str(&format!(
+                "  [{}] {:<11} {}\n",
+                if probe.available { " ok " } else { "MISS" },
+                probe.backend.name(),
+                probe.detail
+            ));
+        }
+
+        let missing: Vec<&str> = self
+            .probes
+            .iter()
+            .filter(|p| !p.available)
+            .flat_map(|p| p.backend.tools().iter().copied())
+            .collect();
+        output.push_str(&format!(
+            "\n{} tool(s) depend on missing backends",
+            missing.len()
+        ));
+        if !missing.is_empty() {
+            output.push_str(&format!(":\n  {}\n", missing.join(", ")));
+        } else {
+            output.push('\n');
+        }
+        output
+    }
+}
+
+async fn probe_backend(backend: Backend) -> Probe {
+    let result = match tokio::time::timeout(PROBE_TIMEOUT, run_probe(backend)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "probe timed out after {}s",
+            PROBE_TIMEOUT.as_secs()
+        )),
+    };
+
+    let (available, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(detail) => (false, detail),
+    };
+
+    Probe {
+        backend,
+        available,
+        detail,
+    }
+}
+
+async fn run_probe(backend: Backend) -> Result<String, String> {
+    match backend {
+        #[cfg(all(feature = "linux", target_os = "linux"))]
+        Backend::I3 => crate::linux::i3::probe().await,
+        #[cfg(all(feature = "linux", target_os = "linux"))]
+        Backend::Xdotool => find_binary("xdotool"),
+        #[cfg(all(feature = "linux", target_os = "linux"))]
+        Backend::Bluez => crate::linux::bluer::probe().await,
+        #[cfg(all(feature = "linux", target_os = "linux"))]
+        Backend::Pulseaudio => crate::linux::pulseaudio::probe().await,
+        #[cfg(feature = "actuators")]
+        Backend::Breakrs => find_binary("breakrs"),
+        #[cfg(feature = "actuators")]
+        Backend::Ollama => crate::actuators::ollama::probe().await,
+        #[cfg(feature = "actuators")]
+        Backend::Camera => crate::actuators::camera::probe().await,
+        #[allow(unreachable_patterns)]
+        _ => Err("not compiled into this build".to_string()),
+    }
+}
+
+#[allow(dead_code)]
+fn find_binary(name: &str) -> Result<String, String> {
+    crate::shared::find_in_path(name)
+        .map(|path| path.display().to_string())
+        .ok_or_else(|| format!("{} not found on PATH", name))
+}
+
+// === Tool Functions ===
+
+#[derive(Debug, Serialize)]
+struct CapabilitiesReport<'a> {
+    backends: &'a [Probe],
+    unavailable_tools: Vec<&'static str>,
+    auto_disable_unavailable: bool,
+}
+
+pub async fn get_server_capabilities(
+    capabilities: &Capabilities,
+    auto_disable_unavailable: bool,
+) -> Result<CallToolResult, McpError> {
+    let unavailable_tools = capabilities
+        .probes
+        .iter()
+        .filter(|p| !p.available)
+        .flat_map(|p| p.backend.tools().iter().copied())
+        .collect();
+
+    let report = CapabilitiesReport {
+        backends: &capabilities.probes,
+        unavailable_tools,
+        auto_disable_unavailable,
+    };
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(backend: Backend, available: bool) -> Probe {
+        Probe {
+            backend,
+            available,
+            detail: if available { "found" } else { "missing" }.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_backend_for() {
+        assert_eq!(backend_for("switch_workspace"), Some(Backend::I3));
+        assert_eq!(backend_for("list_models"), Some(Backend::Ollama));
+        assert_eq!(backend_for("get_system_info"), None);
+
+        // No tool depends on two backends
+        for tool in Backend::ALL.iter().flat_map(|b| b.tools()) {
+            let count = Backend::ALL
+                .iter()
+                .filter(|b| b.tools().contains(tool))
+                .count();
+            assert_eq!(count, 1, "{}", tool);
+        }
+    }
+
+    #[test]
+    fn test_unavailable_and_report() {
+        let capabilities = Capabilities {
+            probes: vec![probe(Backend::I3, true), probe(Backend::Camera, false)],
+        };
+        assert!(capabilities.unavailable("switch_workspace").is_none());
+        assert_eq!(
+            capabilities
+                .unavailable("capture_camera")
+                .map(|p| p.backend),
+            Some(Backend::Camera)
+        );
+        assert!(capabilities.unavailable("get_system_info").is_none());
+
+        let report = capabilities.report();
+        assert!(report.contains("[ ok ] i3"));
+        assert!(report.contains("[MISS] camera      missing"));
+        assert!(report.contains("2 tool(s) depend on missing backends"));
+        assert!(report.contains("capture_camera, get_camera_info"));
+    }
+}
@@ -0,0 +1,195 @@
+//! User-defined composite tools (`[composites.<name>]` in tools.toml)
+//!
+//! Each composite is registered as a tool of its own. Calling it runs the listed tool
+//! calls concurrently with their fixed arguments and merges the outputs into one JSON
+//! object keyed by call. Member calls go through the same dispatch as direct calls, so
+//! disabled tools, policies, rate limits, consent and split-mode forwarding all apply.
+
+use futures::future::{join_all, FutureExt};
+use rmcp::{
+    handler::server::tool::{schema_for_type, ToolCallContext, ToolRoute},
+    model::*,
+    service::RequestContext,
+    ErrorData as McpError, RoleServer,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::Instrument;
+
+use crate::config::{self, CompositeCall, CompositeConfig, Config};
+use crate::shared::internal_error;
+use crate::{EmptyParams, PresenceServer};
+
+/// Router entries for the configured composites. Invalid ones are skipped with a warning.
+pub fn routes(config: &Config) -> Vec<ToolRoute<PresenceServer>> {
+    let mut known = config::all_tool_names();
+    known.extend(config.custom_tools.keys().map(String::as_str));
+    let mut routes = Vec::new();
+
+    for (name, composite) in &config.composites {
+        let validated = if config.workflows.contains_key(name) {
+            Err("name clashes with another tool".to_string())
+        } else {
+            validate(name, composite, &known)
+        };
+        if let Err(e) = validated {
+            tracing::warn!("Skipping composite {}: {}", name, e);
+            continue;
+        }
+
+        let description = composite.description.clone().unwrap_or_else(|| {
+            let tools: Vec<&str> = composite.calls.iter().map(|c| c.tool.as_str()).collect();
+            format!("Composite: runs {} in one call", tools.join(", "))
+        });
+        let tool = Tool::new(name.clone(), description, schema_for_type::<EmptyParams>());
+
+        let calls = Arc::new(composite.calls.clone());
+        routes.push(ToolRoute::new_dyn(
+            tool,
+            move |context: ToolCallContext<'_, PresenceServer>| {
+                let calls = calls.clone();
+                async move { run(context.service, &calls, context.request_context).await }.boxed()
+            },
+        ));
+        tracing::info!("Registered composite tool: {}", name);
+    }
+
+    routes
+}
+
+fn validate(name: &str, composite: &CompositeConfig, known: &[&str]) -> Result<(), String> {
+    if known.contains(&name) {
+        return Err("name clashes with another tool".to_string());
+    }
+    if composite.calls.is_empty() {
+        return Err("no calls listed".to_string());
+    }
+
+    let mut keys = HashSet::new();
+    for call in &composite.calls {
+        // Built-in and custom tools only: nesting composites could recurse forever
+        if !known.contains(&call.tool.as_str()) {
+            return Err(format!("unknown tool '{}'", call.tool));
+        }
+        if !keys.insert(call.key()) {
+            return Err(format!(
+                "duplicate key '{}' (set `key` on repeated tools)",
+                call.key()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Run all calls concurrently and merge their outputs
+async fn run(
+    server: &PresenceServer,
+    calls: &[CompositeCall],
+    context: RequestContext<RoleServer>,
+) -> Result<CallToolResult, McpError> {
+    let results = join_all(calls.iter().map(|call| {
+        let request = CallToolRequestParam {
+            name: call.tool.clone().into(),
+            arguments: Some(call.arguments.clone()),
+        };
+        server
+            .dispatch(&call.tool, request, context.clone())
+            .instrument(tracing::info_span!("composite_call", tool = %call.tool))
+    }))
+    .await;
+
+    let mut merged = serde_json::Map::new();
+    for (call, result) in calls.iter().zip(results) {
+        merged.insert(call.key().to_string(), output(result));
+    }
+
+    let json = serde_json::to_string_pretty(&merged)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+/// One call's entry in the merged result: its JSON output if it printed JSON, its text
+/// otherwise, or `{"error": ...}` if it failed
+pub fn output(result: Result<CallToolResult, McpError>) -> serde_json::Value {
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => return serde_json::json!({ "error": e.message }),
+    };
+
+    let text = text_of(&result);
+    if result.is_error == Some(true) {
+        return serde_json::json!({ "error": text });
+    }
+    serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+}
+
+/// The text content of a result, joined by newlines
+pub fn text_of(result: &CallToolResult) -> String {
+    let text: Vec<&str> = result
+        .content
+        .iter()
+        .filter_map(|content| match &content.raw {
+            RawContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    if text.is_empty() && !result.content.is_empty() {
+        "(non-text output omitted)".to_string()
+    } else {
+        text.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn composite(calls: &[(&str, Option<&str>)]) -> CompositeConfig {
+        CompositeConfig {
+            description: None,
+            calls: calls
+                .iter()
+                .map(|(tool, key)| CompositeCall {
+                    tool: tool.to_string(),
+                    key: key.map(str::to_string),
+                    ..CompositeCall::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let known = ["get_context", "get_git_info"];
+        assert!(validate("morning", &composite(&[("get_context", None)]), &known).is_ok());
+        assert!(validate("get_context", &composite(&[("get_context", None)]), &known).is_err());
+        assert!(validate("morning", &composite(&[]), &known).is_err());
+        assert!(validate("morning", &composite(&[("morning", None)]), &known).is_err());
+
+        let repeated = [("get_git_info", None), ("get_git_info", None)];
+        assert!(validate("repos", &composite(&repeated), &known).is_err());
+        let keyed = [
+            ("get_git_info", Some("work")),
+            ("get_git_info", Some("home")),
+        ];
+        assert!(validate("repos", &composite(&keyed), &known).is_ok());
+    }
+
+    #[test]
+    fn test_output() {
+        let json = CallToolResult::success(vec![Content::text("{\"online\": true}")]);
+        assert_eq!(output(Ok(json)), serde_json::json!({ "online": true }));
+
+        let text = CallToolResult::success(vec![Content::text("No failed units")]);
+        assert_eq!(output(Ok(text)), serde_json::json!("No failed units"));
+
+        let failed = CallToolResult::error(vec![Content::text("boom")]);
+        assert_eq!(output(Ok(failed)), serde_json::json!({ "error": "boom" }));
+
+        let refused = McpError::invalid_request("rate limited", None);
+        assert_eq!(
+            output(Err(refused)),
+            serde_json::json!({ "error": "rate limited" })
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Tool policies conditioned on time of day and user presence
+//!
+//! Each `[[policies]]` rule names tools or categories and an `allow_when` and/or
+//! `deny_when` condition set: local hours, user idle time, session lock state and
+//! battery power. A condition that cannot be evaluated (no idle sensor, no logind
+//! session, ...) refuses the call rather than guessing.
+
+use chrono::{NaiveTime, Timelike};
+use rmcp::ErrorData as McpError;
+
+use crate::audit;
+use crate::config::{Conditions, Config, PolicyRule};
+use crate::shared::policy_error;
+
+/// Refuse the call if a policy rule forbids it right now
+pub async fn check(config: &Config, tool_name: &str) -> Result<(), McpError> {
+    let rules: Vec<(usize, &PolicyRule)> = config
+        .policies
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| {
+            rule.tools
+                .iter()
+                .any(|name| name == tool_name || config.in_category(tool_name, name))
+        })
+        .collect();
+    if rules.is_empty() {
+        return Ok(());
+    }
+
+    let mut facts = Facts::default();
+    let now = chrono::Local::now().time();
+
+    for (index, rule) in rules {
+        if let Err((reason, message)) = evaluate(rule, &mut facts, now).await {
+            audit::condition_refused(tool_name, index, reason);
+            return Err(policy_error(
+                format!("{} is not allowed now: {}", tool_name, message),
+                serde_json::json!({
+                    "policy": "conditions",
+                    "reason": reason,
+                    "tool": tool_name,
+                    "rule": index,
+                }),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Reject rules that could never be evaluated, so a typo doesn't leave a tool
+/// refused (or a deny rule ignored) until someone notices at runtime
+pub fn validate(config: &Config) -> Result<(), String> {
+    for (index, rule) in config.policies.iter().enumerate() {
+        let hours = [&rule.allow_when, &rule.deny_when]
+            .into_iter()
+            .flatten()
+            .filter_map(|conditions| conditions.hours.as_deref());
+        for hours in hours {
+            parse_hours(hours).map_err(|e| format!("policies[{}]: {}", index, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Check one rule; on refusal returns the reason code and a human-readable message
+async fn evaluate(
+    rule: &PolicyRule,
+    facts: &mut Facts,
+    now: NaiveTime,
+) -> Result<(), (&'static str, String)> {
+    if let Some(conditions) = &rule.allow_when {
+        match holds(conditions, facts, now).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err((
+                    "condition_not_met",
+                    format!("only allowed when {}", describe(conditions)),
+                ))
+            }
+            Err(e) => return Err(("condition_unavailable", e)),
+        }
+    }
+
+    if let Some(conditions) = &rule.deny_when {
+        match holds(conditions, facts, now).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err((
+                    "condition_met",
+                    format!("refused when {}", describe(conditions)),
+                ))
+            }
+            Err(e) => return Err(("condition_unavailable", e)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether all set conditions hold
+async fn holds(conditions: &Conditions, facts: &mut Facts, now: NaiveTime) -> Result<bool, String> {
+    if let Some(hours) = &conditions.hours {
+        let (start, end) = parse_hours(hours)?;
+        if !in_window(now, start, end) {
+            return Ok(false);
+        }
+    }
+
+    if let Some(min_idle) = conditions.idle_for_secs {
+        if facts.idle_seconds()? < min_idle {
+            return Ok(false);
+        }
+    }
+
+    if let Some(locked) = conditions.locked {
+        if facts.session_locked().await? != locked {
+            return Ok(false);
+        }
+    }
+
+    if let Some(on_battery) = conditions.on_battery {
+        if facts.on_battery()? != on_battery {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn describe(conditions: &Conditions) -> String {
+    let mut parts = Vec::new();
+    if let Some(hours) = &conditions.hours {
+        parts.push(format!("between {}", hours));
+    }
+    if let Some(secs) = conditions.idle_for_secs {
+        parts.push(format!("the user is idle for {}s", secs));
+    }
+    if let Some(locked) = conditions.locked {
+        parts.push(
+            if locked {
+                "the session is locked"
+            } else {
+                "the session is unlocked"
+            }
+            .to_string(),
+        );
+    }
+    if let Some(on_battery) = conditions.on_battery {
+        parts.push(
+            if on_battery {
+                "on battery"
+            } else {
+                "on mains power"
+            }
+            .to_string(),
+        );
+    }
+    if parts.is_empty() {
+        "always".to_string()
+    } else {
+        parts.join(" and ")
+    }
+}
+
+/// Parse `HH:MM-HH:MM`
+fn parse_hours(hours: &str) -> Result<(NaiveTime, NaiveTime), String> {
+    let invalid = || format!("Invalid hours '{}', expected HH:MM-HH:MM", hours);
+    let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+    Ok((start, end))
+}
+
+/// Whether `now` falls in `[start, end)`, wrapping past midnight when end <= start
+fn in_window(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    // Minute resolution, matching the config format
+    let now = now
+        .with_second(0)
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(now);
+    if start < end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// System state, read at most once per call and only when a rule needs it
+#[derive(Debug, Default)]
+struct Facts {
+    idle_seconds: Option<Result<u64, String>>,
+    session_locked: Option<Result<bool, String>>,
+    on_battery: Option<Result<bool, String>>,
+}
+
+impl Facts {
+    fn idle_seconds(&mut self) -> Result<u64, String> {
+        self.idle_seconds
+            .get_or_insert_with(read_idle_seconds)
+            .clone()
+    }
+
+    async fn session_locked(&mut self) -> Result<bool, String> {
+        if let Some(locked) = &self.session_locked {
+            return locked.clone();
+        }
+        let locked = read_session_locked().await;
+        self.session_locked = Some(locked.clone());
+        locked
+    }
+
+    fn on_battery(&mut self) -> Result<bool, String> {
+        self.on_battery.get_or_insert_with(read_on_battery).clone()
+    }
+}
+
+#[cfg(feature = "sensors")]
+fn read_idle_seconds() -> Result<u64, String> {
+    crate::sensors::idle::idle_seconds()
+}
+
+#[cfg(not(feature = "sensors"))]
+fn read_idle_seconds() -> Result<u64, String> {
+    Err("Idle time needs the sensors feature".to_string())
+}
+
+#[cfg(all(feature = "linux", target_os = "linux"))]
+async fn read_session_locked() -> Result<bool, String> {
+    crate::linux::logind::session_locked().await
+}
+
+#[cfg(not(all(feature = "linux", target_os = "linux")))]
+async fn read_session_locked() -> Result<bool, String> {
+    Err("Session lock state needs logind (linux feature)".to_string())
+}
+
+#[cfg(feature = "sensors")]
+fn read_on_battery() -> Result<bool, String> {
+    crate::sensors::battery::on_battery()
+}
+
+#[cfg(not(feature = "sensors"))]
+fn read_on_battery() -> Result<bool, String> {
+    Err("Battery state needs the sensors feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn test_hours_window() {
+        let (start, end) = parse_hours("22:00-07:00").unwrap();
+        assert!(in_window(time(23, 30), start, end));
+        assert!(in_window(time(6, 59), start, end));
+        assert!(!in_window(time(7, 0), start, end));
+        assert!(!in_window(time(12, 0), start, end));
+
+        let (start, end) = parse_hours("09:00 - 17:30").unwrap();
+        assert!(in_window(time(9, 0), start, end));
+        assert!(!in_window(time(17, 30), start, end));
+
+        assert!(parse_hours("9-17").is_err());
+
+        let mut config = Config::default();
+        config.policies.push(PolicyRule {
+            tools: vec!["suspend".to_string()],
+            allow_when: None,
+            deny_when: Some(Conditions {
+                hours: Some("22-07".to_string()),
+                ..Conditions::default()
+            }),
+        });
+        assert!(validate(&config).unwrap_err().starts_with("policies[0]"));
+    }
+
+    #[tokio::test]
+    async fn test_allow_and_deny_rules() {
+        let mut facts = Facts {
+            idle_seconds: Some(Ok(10)),
+            session_locked: None,
+            on_battery: Some(Err("no battery sensor".to_string())),
+        };
+
+        let idle_only = PolicyRule {
+            tools: vec!["xdotool".to_string()],
+            allow_when: Some(Conditions {
+                idle_for_secs: Some(60),
+                ..Conditions::default()
+            }),
+            deny_when: None,
+        };
+        let refusal = evaluate(&idle_only, &mut facts, time(12, 0))
+            .await
+            .unwrap_err();
+        assert_eq!(refusal.0, "condition_not_met");
+        facts.idle_seconds = Some(Ok(120));
+        assert!(evaluate(&idle_only, &mut facts, time(12, 0)).await.is_ok());
+
+        let night = PolicyRule {
+            tools: vec!["capture_camera".to_string()],
+            allow_when: None,
+            deny_when: Some(Conditions {
+                hours: Some("22:00-07:00".to_string()),
+                ..Conditions::default()
+            }),
+        };
+        assert!(evaluate(&night, &mut facts, time(12, 0)).await.is_ok());
+        assert_eq!(
+            evaluate(&night, &mut facts, time(23, 0))
+                .await
+                .unwrap_err()
+                .0,
+            "condition_met"
+        );
+
+        // Unknown state refuses instead of guessing
+        let battery = PolicyRule {
+            tools: vec!["pull_model".to_string()],
+            allow_when: None,
+            deny_when: Some(Conditions {
+                on_battery: Some(true),
+                ..Conditions::default()
+            }),
+        };
+        assert_eq!(
+            evaluate(&battery, &mut facts, time(12, 0))
+                .await
+                .unwrap_err()
+                .0,
+            "condition_unavailable"
+        );
+    }
+}
@@ -3,60 +3,795 @@
 //! Reads/writes tool configuration from ~/.config/rmcp-presence/tools.toml
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
+use crate::capabilities::Backend;
+
 /// Tool configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// List of disabled tool names (all others are enabled)
     #[serde(default)]
     pub disabled: Vec<String>,
+
+    /// Remove tools whose backend (i3, xdotool, BlueZ, ...) is missing at startup,
+    /// instead of only marking them unavailable
+    #[serde(default)]
+    pub auto_disable_unavailable: bool,
+
+    /// Where tools may open network connections: any, allowlist, local or none
+    #[serde(default)]
+    pub network_egress: EgressMode,
+
+    /// Hosts (`api.example.com`, `*.example.com`), addresses and networks
+    /// (`10.0.0.0/8`) reachable besides local ones when `network_egress = "allowlist"`
+    #[serde(default)]
+    pub egress_allowlist: Vec<String>,
+
+    /// Secret redaction applied to every tool result
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// Named groups of tools referenced by policy sections. Built-in categories
+    /// (see `default_category`) apply unless redefined here.
+    #[serde(default)]
+    pub categories: BTreeMap<String, Vec<String>>,
+
+    /// Notifications, consent and quota for the `sensitive` category
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    /// Privilege separation between sensors and actuators
+    #[serde(default)]
+    pub split: SplitConfig,
+
+    /// Kernel-enforced filesystem and exec restrictions (Linux)
+    #[serde(default)]
+    pub sandbox: SandboxConfig,
+
+    /// Rate limits and daily quotas, keyed by tool or category name
+    #[serde(default)]
+    pub rate_limits: BTreeMap<String, RateLimitConfig>,
+
+    /// Rules allowing or refusing tools depending on time of day and user presence
+    #[serde(default)]
+    pub policies: Vec<PolicyRule>,
+
+    /// Prometheus endpoint for tool call statistics
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// OpenTelemetry trace export (used when built with the `otel` feature)
+    #[serde(default)]
+    pub otel: OtelConfig,
+
+    /// Background sampling of system metrics for `get_metric_history`
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    /// Safeguards for `signal_process` and `terminate_gracefully`
+    #[serde(default)]
+    pub signals: SignalConfig,
+
+    /// Service endpoints for `check_endpoints`, keyed by name
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, EndpointConfig>,
+
+    /// Service behind `get_public_ip` and the public IP in `get_network_info`
+    #[serde(default)]
+    pub public_ip: PublicIpConfig,
+
+    /// Service behind `get_weather` and `get_forecast`
+    #[serde(default)]
+    pub weather: WeatherConfig,
+
+    /// User-defined composite tools, keyed by tool name
+    #[serde(default)]
+    pub composites: BTreeMap<String, CompositeConfig>,
+
+    /// Tools backed by a D-Bus method or HTTP request template, keyed by tool name
+    #[serde(default)]
+    pub custom_tools: BTreeMap<String, CustomToolConfig>,
+
+    /// Sequential tool macros, keyed by tool name
+    #[serde(default)]
+    pub workflows: BTreeMap<String, WorkflowConfig>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             disabled: Vec::new(),
+            auto_disable_unavailable: false,
+            network_egress: EgressMode::default(),
+            egress_allowlist: Vec::new(),
+            redaction: RedactionConfig::default(),
+            categories: BTreeMap::new(),
+            privacy: PrivacyConfig::default(),
+            split: SplitConfig::default(),
+            sandbox: SandboxConfig::default(),
+            rate_limits: BTreeMap::new(),
+            policies: Vec::new(),
+            metrics: MetricsConfig::default(),
+            otel: OtelConfig::default(),
+            history: HistoryConfig::default(),
+            signals: SignalConfig::default(),
+            endpoints: BTreeMap::new(),
+            public_ip: PublicIpConfig::default(),
+            weather: WeatherConfig::default(),
+            composites: BTreeMap::new(),
+            custom_tools: BTreeMap::new(),
+            workflows: BTreeMap::new(),
+        }
+    }
+}
+
+/// Secret redaction settings (`[redaction]` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Redact tool output before it leaves the server
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Use the built-in detectors (API tokens, basic-auth URLs, private keys, ...)
+    #[serde(default = "default_true")]
+    pub builtin: bool,
+
+    /// Additional user-defined patterns
+    #[serde(default)]
+    pub patterns: Vec<RedactionPattern>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            builtin: true,
+            patterns: Vec::new(),
         }
     }
 }
 
+/// A user-defined redaction pattern (`[[redaction.patterns]]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionPattern {
+    /// Name recorded in the audit trail and the replacement marker
+    pub name: String,
+    /// Regular expression matching the secret
+    pub regex: String,
+}
+
+/// Privacy settings for sensitive captures (`[privacy]` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Show a desktop notification whenever a sensitive capture happens
+    #[serde(default = "default_true")]
+    pub notify: bool,
+
+    /// Ask the user (via the MCP client) before the first sensitive capture of a session
+    #[serde(default)]
+    pub require_consent: bool,
+
+    /// Maximum sensitive captures per rolling hour (unset = unlimited)
+    #[serde(default)]
+    pub max_captures_per_hour: Option<u32>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            notify: true,
+            require_consent: false,
+            max_captures_per_hour: None,
+        }
+    }
+}
+
+/// Privilege separation settings (`[split]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SplitConfig {
+    /// Forward actuator and Linux tools to `rmcp-presence helper` instead of running them in-process
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Helper socket path (default: `$XDG_RUNTIME_DIR/rmcp-presence/helper.sock`)
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
+
+    /// Additional user IDs allowed on the other end of the helper socket (the own UID
+    /// always is): clients of the helper, and the helper as seen from the front
+    #[serde(default)]
+    pub allow_uids: Vec<u32>,
+}
+
+impl SplitConfig {
+    /// Without a runtime directory, falls back to a per-user directory in the temp dir
+    #[cfg(unix)]
+    pub fn socket_path(&self) -> PathBuf {
+        self.socket.clone().unwrap_or_else(|| {
+            let dir = dirs::runtime_dir().map_or_else(
+                || {
+                    // SAFETY: getuid has no preconditions and cannot fail
+                    let uid = unsafe { libc::getuid() };
+                    std::env::temp_dir().join(format!("rmcp-presence-{}", uid))
+                },
+                |runtime| runtime.join("rmcp-presence"),
+            );
+            dir.join("helper.sock")
+        })
+    }
+}
+
+/// Self-sandboxing settings (`[sandbox]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// Apply Landlock and seccomp restrictions at startup
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Extra directories the server may write to, e.g. where `trash_file` should work
+    #[serde(default)]
+    pub write_roots: Vec<PathBuf>,
+
+    /// Extra programs the server may run, by name (looked up on PATH) or path
+    #[serde(default)]
+    pub allow_exec: Vec<String>,
+
+    /// Let `open_path` run `xdg-open` and the desktop openers. This also allows
+    /// `/bin/sh` and common shell tools (`sed`, `grep`, ...), which `xdg-open` needs.
+    #[serde(default)]
+    pub open_path: bool,
+}
+
+/// Token bucket and daily quota for one tool or category (`[rate_limits.<name>]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Sustained calls per minute (bucket refill rate)
+    #[serde(default)]
+    pub per_minute: Option<u32>,
+
+    /// Calls allowed back-to-back (bucket size, default: `per_minute`)
+    #[serde(default)]
+    pub burst: Option<u32>,
+
+    /// Calls per day, reset at midnight UTC
+    #[serde(default)]
+    pub per_day: Option<u32>,
+}
+
+impl RateLimitConfig {
+    /// Bucket size, if the token bucket is enabled
+    pub fn capacity(&self) -> Option<u32> {
+        self.per_minute.map(|per_minute| self.burst.unwrap_or(per_minute))
+    }
+}
+
+/// A conditional tool policy (`[[policies]]`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Tools or categories the rule applies to
+    pub tools: Vec<String>,
+
+    /// Allow the tools only while all of these conditions hold
+    #[serde(default)]
+    pub allow_when: Option<Conditions>,
+
+    /// Refuse the tools while all of these conditions hold
+    #[serde(default)]
+    pub deny_when: Option<Conditions>,
+}
+
+/// Conditions of a policy rule; all set fields must hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conditions {
+    /// Local time window `HH:MM-HH:MM`, may wrap past midnight (e.g. `22:00-07:00`)
+    #[serde(default)]
+    pub hours: Option<String>,
+
+    /// User idle for at least this many seconds
+    #[serde(default)]
+    pub idle_for_secs: Option<u64>,
+
+    /// Session locked (`true`) or unlocked (`false`)
+    #[serde(default)]
+    pub locked: Option<bool>,
+
+    /// Running on battery (`true`) or mains power (`false`)
+    #[serde(default)]
+    pub on_battery: Option<bool>,
+}
+
+/// Metrics settings (`[metrics]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Address for the Prometheus text endpoint, e.g. `127.0.0.1:9464` (unset = off)
+    #[serde(default)]
+    pub listen: Option<String>,
+}
+
+/// Trace export settings (`[otel]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OtelConfig {
+    /// OTLP/HTTP collector base URL, e.g. `http://localhost:4318`
+    /// (default: `OTEL_EXPORTER_OTLP_ENDPOINT`; unset = off)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+
+    /// `service.name` resource attribute (default: `rmcp-presence`)
+    #[serde(default)]
+    pub service_name: Option<String>,
+}
+
+impl OtelConfig {
+    /// Collector URL from the config file or the standard environment variable
+    pub fn endpoint(&self) -> Option<String> {
+        self.endpoint
+            .clone()
+            .or_else(|| std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok())
+            .filter(|e| !e.is_empty())
+    }
+}
+
+/// Destinations tools may connect to (`network_egress`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EgressMode {
+    /// No restriction
+    #[default]
+    Any,
+    /// Local addresses plus `egress_allowlist`
+    Allowlist,
+    /// Loopback, private and link-local addresses only
+    Local,
+    /// No network connections at all
+    None,
+}
+
+/// Metric history settings (`[history]` table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Sample system metrics in the background
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Seconds between samples
+    #[serde(default = "default_history_interval")]
+    pub interval_secs: u64,
+
+    /// How long samples are kept, in seconds
+    #[serde(default = "default_history_retention")]
+    pub retention_secs: u64,
+
+    /// Also append samples to `history.jsonl` in the state directory and reload them on start
+    #[serde(default)]
+    pub persist: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: default_history_interval(),
+            retention_secs: default_history_retention(),
+            persist: false,
+        }
+    }
+}
+
+fn default_history_interval() -> u64 {
+    10
+}
+
+fn default_history_retention() -> u64 {
+    6 * 3600
+}
+
+/// Process signalling settings (`[signals]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalConfig {
+    /// Process names that are never signalled, on top of the built-in list
+    /// (init systems, display servers, window managers, sshd, D-Bus)
+    #[serde(default)]
+    pub protected: Vec<String>,
+}
+
+/// A service endpoint checked by `check_endpoints` (`[endpoints.<name>]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EndpointConfig {
+    /// Hostname or IP address
+    pub host: String,
+
+    /// TCP port
+    pub port: u16,
+
+    /// Connection attempts per check (default: 1)
+    #[serde(default)]
+    pub count: Option<u32>,
+
+    /// Timeout per DNS lookup and per attempt in milliseconds (default: 2000)
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Public IP lookup settings (`[public_ip]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublicIpConfig {
+    /// Response format of the service (default: ipinfo)
+    #[serde(default)]
+    pub provider: IpProvider,
+
+    /// Lookup URL (default depends on the provider)
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl PublicIpConfig {
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or(match self.provider {
+            IpProvider::Ipinfo => "https://ipinfo.io/json",
+            IpProvider::Plain => "https://api.ipify.org",
+        })
+    }
+}
+
+/// Public IP services understood by `get_public_ip`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpProvider {
+    /// ipinfo.io JSON: address plus location and network owner
+    #[default]
+    Ipinfo,
+    /// The bare address as text, as served by ipify, icanhazip or `curl ifconfig.me`
+    Plain,
+}
+
+/// Weather settings (`[weather]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WeatherConfig {
+    /// Weather service (default: wttr)
+    #[serde(default)]
+    pub provider: WeatherProvider,
+
+    /// Base URL of the service (default depends on the provider)
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Open-Meteo geocoding base URL, used to turn place names into coordinates
+    #[serde(default)]
+    pub geocoding_url: Option<String>,
+
+    /// Units of returned values (default: metric)
+    #[serde(default)]
+    pub units: Units,
+
+    /// Where severe-weather alerts come from (default: none)
+    #[serde(default)]
+    pub alerts: AlertSource,
+
+    /// Base URL of the alert source (default: https://api.weather.gov)
+    #[serde(default)]
+    pub alerts_url: Option<String>,
+}
+
+impl WeatherConfig {
+    pub fn url(&self) -> &str {
+        let url = self.url.as_deref().unwrap_or(match self.provider {
+            WeatherProvider::Wttr => "https://wttr.in",
+            WeatherProvider::OpenMeteo => "https://api.open-meteo.com",
+        });
+        url.trim_end_matches('/')
+    }
+
+    pub fn geocoding_url(&self) -> &str {
+        self.geocoding_url
+            .as_deref()
+            .unwrap_or("https://geocoding-api.open-meteo.com")
+            .trim_end_matches('/')
+    }
+
+    pub fn alerts_url(&self) -> &str {
+        self.alerts_url
+            .as_deref()
+            .unwrap_or("https://api.weather.gov")
+            .trim_end_matches('/')
+    }
+}
+
+/// Weather services understood by `get_weather` and `get_forecast`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WeatherProvider {
+    /// wttr.in (`?format=j1`)
+    #[default]
+    Wttr,
+    /// Open-Meteo forecast API, with its geocoding API for place names
+    OpenMeteo,
+}
+
+/// Unit system of weather values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// °C, km/h, mm, hPa, km
+    #[default]
+    Metric,
+    /// °F, mph, in, inHg, mi
+    Imperial,
+}
+
+/// Severe-weather alert sources. Neither weather provider publishes alerts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSource {
+    #[default]
+    None,
+    /// US National Weather Service active alerts (US locations only)
+    Nws,
+}
+
+/// A user-defined composite tool (`[composites.<name>]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompositeConfig {
+    /// Tool description shown to the client (default: lists the member tools)
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Tool calls to run concurrently
+    pub calls: Vec<CompositeCall>,
+}
+
+/// One member call of a composite
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompositeCall {
+    /// Name of an existing tool
+    pub tool: String,
+
+    /// Fixed arguments passed to the tool
+    #[serde(default)]
+    pub arguments: serde_json::Map<String, serde_json::Value>,
+
+    /// Key of this call's output in the merged result (default: the tool name)
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl CompositeCall {
+    pub fn key(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.tool)
+    }
+}
+
+/// A user-defined workflow (`[workflows.<name>]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowConfig {
+    /// Tool description shown to the client (default: lists the steps)
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Steps, run in order
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// One step of a workflow
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    /// Name later conditions refer to (default: the tool name)
+    #[serde(default)]
+    pub id: Option<String>,
+
+    /// Name of an existing tool
+    pub tool: String,
+
+    /// Fixed arguments passed to the tool
+    #[serde(default)]
+    pub arguments: serde_json::Map<String, serde_json::Value>,
+
+    /// Run the step only if this holds for an earlier step's result
+    #[serde(default)]
+    pub when: Option<StepCondition>,
+
+    /// Give up on the step after this many seconds (default: 30)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Keep going if the step fails or times out (default: stop the workflow)
+    #[serde(default)]
+    pub continue_on_error: bool,
+
+    /// Count the step as failed when its output matches this regex, for tools that
+    /// report failures as ordinary text
+    #[serde(default)]
+    pub fail_if: Option<String>,
+}
+
+impl WorkflowStep {
+    pub fn id(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.tool)
+    }
+}
+
+/// Condition on an earlier step's result; all set fields must hold. A step that
+/// did not run satisfies no condition.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StepCondition {
+    /// ID of the earlier step
+    pub step: String,
+
+    /// The step succeeded (`true`) or failed (`false`)
+    #[serde(default)]
+    pub ok: Option<bool>,
+
+    /// The step's output contains this text
+    #[serde(default)]
+    pub contains: Option<String>,
+
+    /// The step's output does not contain this text
+    #[serde(default)]
+    pub not_contains: Option<String>,
+
+    /// The step's output matches this regex
+    #[serde(default)]
+    pub matches: Option<String>,
+}
+
+/// A tool defined by a request template (`[custom_tools.<name>]` table)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomToolConfig {
+    /// Tool description shown to the client
+    pub description: String,
+
+    /// Parameters the client passes; `{name}` in the template is replaced by the value
+    #[serde(default)]
+    pub params: Vec<CustomParam>,
+
+    /// Backend: an HTTP request (needs the sensors feature)
+    #[serde(default)]
+    pub http: Option<HttpTemplate>,
+
+    /// Backend: a D-Bus method call (needs the linux feature)
+    #[serde(default)]
+    pub dbus: Option<DbusTemplate>,
+}
+
+/// One parameter of a custom tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CustomParam {
+    pub name: String,
+
+    #[serde(rename = "type", default)]
+    pub kind: ParamType,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Value used when the argument is omitted; parameters without one are required
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+}
+
+/// JSON type of a custom tool parameter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+/// HTTP request template of a custom tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpTemplate {
+    /// HTTP method (default: GET)
+    #[serde(default)]
+    pub method: Option<String>,
+
+    /// URL; substituted values are percent-encoded
+    pub url: String,
+
+    /// Query parameters
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+
+    /// Request headers
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// JSON body; a string that is exactly `{name}` becomes the typed argument value
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+
+    /// Request timeout in seconds (default: 10)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// D-Bus method template of a custom tool. Only `args` may contain placeholders.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DbusTemplate {
+    /// Use the session bus instead of the system bus
+    #[serde(default)]
+    pub session: bool,
+
+    pub destination: String,
+    pub path: String,
+    pub interface: String,
+    pub method: String,
+
+    /// Method arguments as JSON; a string that is exactly `{name}` becomes the typed
+    /// argument value
+    #[serde(default)]
+    pub args: Vec<serde_json::Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Names of the built-in categories: `sensitive` plus one per backend (`xdotool`, `i3`, ...)
+fn builtin_categories() -> Vec<&'static str> {
+    let mut names = vec!["sensitive"];
+    names.extend(Backend::ALL.iter().map(|backend| backend.name()));
+    names
+}
+
+/// Built-in tool categories, used when the config does not define them
+pub fn default_category(name: &str) -> &'static [&'static str] {
+    match name {
+        "sensitive" => &[
+            "capture_camera",
+            "capture_audio",
+            "get_input_level",
+            "capture_monitor",
+            "capture_window",
+            "capture_region",
+        ],
+        _ => Backend::ALL
+            .iter()
+            .find(|backend| backend.name() == name)
+            .map(|backend| backend.tools())
+            .unwrap_or(&[]),
+    }
+}
+
 impl Config {
     /// Get the config file path
     pub fn path() -> Option<PathBuf> {
         dirs::config_dir().map(|p| p.join("rmcp-presence").join("tools.toml"))
     }
 
-    /// Load config from file, or return default if not found
-    pub fn load() -> Self {
+    /// Directory for runtime state (falls back to the local data dir where XDG_STATE_HOME is unknown)
+    pub fn state_dir() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|p| p.join("rmcp-presence"))
+    }
+
+    /// Load config from file, or return default if not found. A config file that
+    /// exists but can't be read or parsed is an error, rather than silently running
+    /// with defaults that may be far less restrictive.
+    pub fn load() -> anyhow::Result<Self> {
         let Some(path) = Self::path() else {
             tracing::warn!("Could not determine config directory, using defaults");
-            return Self::default();
+            return Ok(Self::default());
         };
 
         if !path.exists() {
             tracing::info!("No config file found at {:?}, using defaults (all tools enabled)", path);
-            return Self::default();
+            return Ok(Self::default());
         }
 
-        match fs::read_to_string(&path) {
-            Ok(contents) => match toml::from_str(&contents) {
-                Ok(config) => {
-                    tracing::info!("Loaded config from {:?}", path);
-                    config
-                }
-                Err(e) => {
-                    tracing::error!("Failed to parse config file: {}", e);
-                    Self::default()
-                }
-            },
-            Err(e) => {
-                tracing::error!("Failed to read config file: {}", e);
-                Self::default()
-            }
-        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config file {:?}: {}", path, e))?;
+        let config = toml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config file {:?}: {}", path, e))?;
+        crate::conditions::validate(&config)
+            .map_err(|e| anyhow::anyhow!("Invalid config file {:?}: {}", path, e))?;
+        tracing::info!("Loaded config from {:?}", path);
+        Ok(config)
     }
 
     /// Save config to file
@@ -79,6 +814,30 @@ impl Config {
         !self.disabled.contains(&tool_name.to_string())
     }
 
+    /// Check if a tool belongs to a category
+    pub fn in_category(&self, tool_name: &str, category: &str) -> bool {
+        match self.categories.get(category) {
+            Some(tools) => tools.iter().any(|t| t == tool_name),
+            None => default_category(category).contains(&tool_name),
+        }
+    }
+
+    /// Categories (configured or built-in) a tool belongs to
+    pub fn categories_of(&self, tool_name: &str) -> Vec<String> {
+        let mut names: Vec<&str> = self.categories.keys().map(String::as_str).collect();
+        names.extend(
+            builtin_categories()
+                .into_iter()
+                .filter(|name| !self.categories.contains_key(*name)),
+        );
+        names.sort();
+        names
+            .into_iter()
+            .filter(|name| self.in_category(tool_name, name))
+            .map(str::to_string)
+            .collect()
+    }
+
     /// Get set of disabled tools for fast lookup
     pub fn disabled_set(&self) -> HashSet<String> {
         self.disabled.iter().cloned().collect()
@@ -99,8 +858,18 @@ impl Config {
 
 /// Get list of all available tool names (compile-time known)
 pub fn all_tool_names() -> Vec<&'static str> {
+    let mut tools = sensor_tool_names();
+    tools.extend(actuator_tool_names());
+    tools
+}
+
+/// Server and sensor tools: read-only, served by the MCP-facing process in split mode
+pub fn sensor_tool_names() -> Vec<&'static str> {
     let mut tools = Vec::new();
 
+    // === SERVER ===
+    tools.extend(["get_server_capabilities", "get_server_stats"]);
+
     // === SENSORS ===
     #[cfg(feature = "sensors")]
     {
@@ -111,8 +880,10 @@ pub fn all_tool_names() -> Vec<&'static str> {
             "get_top_processes",
             "find_process",
             "get_process_details",
+            "get_process_tree",
             "list_processes",
             "get_network_stats",
+            "get_network_rates",
             "get_component_temps",
             "get_disk_info",
             "get_display_info",
@@ -124,6 +895,8 @@ pub fn all_tool_names() -> Vec<&'static str> {
             "get_public_ip",
             "is_online",
             "dns_lookup",
+            "probe_endpoint",
+            "check_endpoints",
             "get_network_info",
             "get_usb_devices",
             "get_battery_status",
@@ -138,11 +911,23 @@ pub fn all_tool_names() -> Vec<&'static str> {
             "get_diff_summary",
             "get_git_info",
             "get_users",
+            "get_metric_history",
             "get_weather",
             "get_forecast",
         ]);
     }
 
+    // === SENSORS (Linux /proc) ===
+    #[cfg(all(feature = "sensors", target_os = "linux"))]
+    tools.extend(["list_listening_ports", "list_connections"]);
+
+    tools
+}
+
+/// Actuator and Linux tools: served by the helper process in split mode
+pub fn actuator_tool_names() -> Vec<&'static str> {
+    let mut tools = Vec::new();
+
     // === ACTUATORS ===
     #[cfg(feature = "actuators")]
     {
@@ -162,6 +947,8 @@ pub fn all_tool_names() -> Vec<&'static str> {
             "empty_trash",
             "open_path",
             "open_with",
+            "signal_process",
+            "terminate_gracefully",
             "list_monitors",
             "capture_monitor",
             "list_windows",
@@ -186,6 +973,16 @@ pub fn all_tool_names() -> Vec<&'static str> {
             "clear_reminders",
             "daemon_status",
             "get_history",
+            "list_printers",
+            "get_printer_info",
+            "get_default_printer",
+            "print_file",
+            "print_text",
+            "list_jobs",
+            "cancel_job",
+            "pause_job",
+            "resume_job",
+            "restart_job",
         ]);
     }
 
@@ -292,6 +1089,10 @@ pub fn all_tool_names() -> Vec<&'static str> {
         ]);
     }
 
+    // === UNDO ===
+    // The journal lives with the actuators, so the helper serves these in split mode
+    tools.extend(["list_recent_actions", "undo_action"]);
+
     tools
 }
 
@@ -306,6 +1107,18 @@ mod tests {
         assert!(config.is_enabled("get_system_info"));
     }
 
+    #[test]
+    fn test_tool_lists_cover_router() {
+        let router = crate::PresenceServer::tool_router();
+        let sensors = sensor_tool_names();
+        let actuators = actuator_tool_names();
+        for name in router.map.keys() {
+            let count = sensors.iter().chain(&actuators).filter(|t| *t == name).count();
+            assert_eq!(count, 1, "{} must be in exactly one tool list", name);
+        }
+        assert_eq!(all_tool_names().len(), router.map.len());
+    }
+
     #[test]
     fn test_enable_disable() {
         let mut config = Config::default();
@@ -317,4 +1130,24 @@ mod tests {
         config.enable("capture_audio");
         assert!(config.is_enabled("capture_audio"));
     }
+
+    #[test]
+    fn test_categories() {
+        let mut config = Config::default();
+        assert!(config.in_category("capture_camera", "sensitive"));
+        assert!(!config.in_category("get_context", "sensitive"));
+
+        config
+            .categories
+            .insert("sensitive".to_string(), vec!["read_clipboard".to_string()]);
+        assert!(config.in_category("read_clipboard", "sensitive"));
+        assert!(!config.in_category("capture_camera", "sensitive"));
+
+        config
+            .categories
+            .insert("input".to_string(), vec!["read_clipboard".to_string()]);
+        assert_eq!(config.categories_of("read_clipboard"), ["input", "sensitive"]);
+        assert_eq!(config.categories_of("capture_camera"), ["camera"]);
+        assert_eq!(config.categories_of("click_at"), ["xdotool"]);
+    }
 }
@@ -0,0 +1,551 @@
+//! Config-defined tools backed by a D-Bus method or HTTP request template
+//!
+//! A `[custom_tools.<name>]` entry declares typed parameters and one backend. Calls
+//! validate the arguments against the declared types and substitute them into the
+//! template: percent-encoded in URLs, as typed JSON values in bodies and D-Bus
+//! arguments. Nothing is ever passed to a shell.
+
+use futures::future::FutureExt;
+use rmcp::{
+    handler::server::tool::{ToolCallContext, ToolRoute},
+    model::*,
+    ErrorData as McpError,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::config::{self, Config, CustomParam, CustomToolConfig, EgressMode, ParamType};
+use crate::{PresenceServer, Role};
+
+/// Responses longer than this are truncated
+#[cfg(feature = "sensors")]
+const MAX_RESPONSE_CHARS: usize = 16_000;
+
+/// Router entries for the custom tools this process serves. In split mode D-Bus tools
+/// belong to the helper and HTTP tools to the front. Invalid entries are skipped
+/// with a warning.
+pub fn routes(config: &Config, role: Role) -> Vec<ToolRoute<PresenceServer>> {
+    let known = config::all_tool_names();
+    let mut routes = Vec::new();
+
+    for (name, custom) in &config.custom_tools {
+        if let Err(e) = validate(name, custom, &known, config) {
+            tracing::warn!("Skipping custom tool {}: {}", name, e);
+            continue;
+        }
+        let served = match role {
+            Role::Standalone => true,
+            Role::Front => custom.http.is_some(),
+            Role::Helper => custom.dbus.is_some(),
+        };
+        if !served {
+            continue;
+        }
+        if custom.http.is_some() && config.network_egress == EgressMode::None {
+            tracing::info!("Disabled by network_egress: {}", name);
+            continue;
+        }
+
+        let tool = Tool::new(
+            name.clone(),
+            custom.description.clone(),
+            input_schema(&custom.params),
+        );
+        let custom = Arc::new(custom.clone());
+        routes.push(ToolRoute::new_dyn(
+            tool,
+            move |context: ToolCallContext<'_, PresenceServer>| {
+                let custom = custom.clone();
+                async move { call(context.service, &custom, context.arguments).await }.boxed()
+            },
+        ));
+        tracing::info!("Registered custom tool: {}", name);
+    }
+
+    routes
+}
+
+fn validate(
+    name: &str,
+    custom: &CustomToolConfig,
+    known: &[&str],
+    config: &Config,
+) -> Result<(), String> {
+    if known.contains(&name)
+        || config.composites.contains_key(name)
+        || config.workflows.contains_key(name)
+    {
+        return Err("name clashes with another tool".to_string());
+    }
+
+    let mut declared = HashSet::new();
+    for param in &custom.params {
+        if !is_identifier(&param.name) {
+            return Err(format!("invalid parameter name '{}'", param.name));
+        }
+        if !declared.insert(param.name.as_str()) {
+            return Err(format!("duplicate parameter '{}'", param.name));
+        }
+        if let Some(default) = &param.default {
+            if !matches_type(default, param.kind) {
+                return Err(format!("default of '{}' has the wrong type", param.name));
+            }
+        }
+    }
+
+    let mut templates: Vec<&str> = Vec::new();
+    match (&custom.http, &custom.dbus) {
+        (Some(http), None) => {
+            if !cfg!(feature = "sensors") {
+                return Err("HTTP tools need the sensors feature".to_string());
+            }
+            let method = http.method.as_deref().unwrap_or("GET").to_ascii_uppercase();
+            if !["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"].contains(&method.as_str()) {
+                return Err(format!("unsupported HTTP method '{}'", method));
+            }
+            templates.push(&http.url);
+            templates.extend(http.query.values().map(String::as_str));
+            templates.extend(http.headers.values().map(String::as_str));
+            if let Some(body) = &http.body {
+                json_strings(body, &mut templates);
+            }
+        }
+        (None, Some(dbus)) => {
+            if !cfg!(all(feature = "linux", target_os = "linux")) {
+                return Err("D-Bus tools need the linux feature".to_string());
+            }
+            for fixed in [&dbus.destination, &dbus.path, &dbus.interface, &dbus.method] {
+                if !placeholders(fixed).is_empty() {
+                    return Err(format!(
+                        "placeholders are only allowed in args: '{}'",
+                        fixed
+                    ));
+                }
+            }
+            for arg in &dbus.args {
+                json_strings(arg, &mut templates);
+            }
+        }
+        _ => return Err("needs exactly one of `http` or `dbus`".to_string()),
+    }
+
+    for template in templates {
+        for placeholder in placeholders(template) {
+            if !declared.contains(placeholder) {
+                return Err(format!("undeclared placeholder {{{}}}", placeholder));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// JSON schema for the declared parameters
+fn input_schema(params: &[CustomParam]) -> JsonObject {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+    for param in params {
+        let mut property = serde_json::Map::new();
+        property.insert("type".to_string(), type_name(param.kind).into());
+        if let Some(description) = &param.description {
+            property.insert("description".to_string(), description.clone().into());
+        }
+        match &param.default {
+            Some(default) => {
+                property.insert("default".to_string(), default.clone());
+            }
+            None => required.push(serde_json::Value::from(param.name.clone())),
+        }
+        properties.insert(param.name.clone(), property.into());
+    }
+
+    let mut schema = serde_json::Map::new();
+    schema.insert("type".to_string(), "object".into());
+    schema.insert("properties".to_string(), properties.into());
+    schema.insert("required".to_string(), required.into());
+    schema
+}
+
+async fn call(
+    server: &PresenceServer,
+    custom: &CustomToolConfig,
+    arguments: Option<JsonObject>,
+) -> Result<CallToolResult, McpError> {
+    let args = bind(&custom.params, arguments.unwrap_or_default())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    #[cfg(feature = "sensors")]
+    if let Some(http) = &custom.http {
+        server.egress.check_url(&http_url(http, &args)).await?;
+    }
+
+    let output = if let Some(http) = &custom.http {
+        run_http(server, http, &args).await
+    } else if let Some(dbus) = &custom.dbus {
+        run_dbus(dbus, &args).await
+    } else {
+        Err("No backend configured".to_string())
+    };
+
+    match output {
+        Ok(text) => Ok(CallToolResult::success(vec![Content::text(text)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
+    }
+}
+
+/// Check the client's arguments against the declared parameters and fill in defaults
+fn bind(params: &[CustomParam], arguments: JsonObject) -> Result<JsonObject, String> {
+    if let Some(unknown) = arguments
+        .keys()
+        .find(|key| !params.iter().any(|p| &p.name == *key))
+    {
+        return Err(format!("Unknown argument '{}'", unknown));
+    }
+
+    let mut bound = JsonObject::new();
+    for param in params {
+        let value = arguments
+            .get(&param.name)
+            .or(param.default.as_ref())
+            .ok_or_else(|| format!("Missing required argument '{}'", param.name))?;
+        if !matches_type(value, param.kind) {
+            return Err(format!(
+                "Argument '{}' must be of type {}",
+                param.name,
+                type_name(param.kind)
+            ));
+        }
+        bound.insert(param.name.clone(), value.clone());
+    }
+    Ok(bound)
+}
+
+/// Request URL with the arguments substituted, percent-encoded
+#[cfg(feature = "sensors")]
+fn http_url(http: &config::HttpTemplate, args: &JsonObject) -> String {
+    substitute(&http.url, args, |value| {
+        urlencoding::encode(&value).into_owned()
+    })
+}
+
+#[cfg(feature = "sensors")]
+async fn run_http(
+    server: &PresenceServer,
+    http: &config::HttpTemplate,
+    args: &JsonObject,
+) -> Result<String, String> {
+    use crate::shared::RequestBuilderExt;
+
+    let method = http.method.as_deref().unwrap_or("GET").to_ascii_uppercase();
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|e| format!("Invalid HTTP method: {}", e))?;
+    let url = http_url(http, args);
+
+    let query: Vec<(&str, String)> = http
+        .query
+        .iter()
+        .map(|(key, value)| (key.as_str(), substitute(value, args, |v| v)))
+        .collect();
+
+    // Already checked, but this pins the addresses and guards redirects
+    let client = server.egress.client(&url).await.map_err(|e| e.message.to_string())?;
+    let mut request = client
+        .request(method, &url)
+        .query(&query)
+        .timeout(std::time::Duration::from_secs(
+            http.timeout_secs.unwrap_or(10),
+        ));
+    for (key, value) in &http.headers {
+        let value = substitute(value, args, |v| v);
+        if value.chars().any(char::is_control) {
+            return Err(format!("Header {} contains control characters", key));
+        }
+        request = request.header(key.as_str(), value);
+    }
+    if let Some(body) = &http.body {
+        request = request.json(&fill_json(body, args));
+    }
+
+    let response = request
+        .traced_send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let status = response.status();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let mut output = if status.is_success() {
+        text
+    } else {
+        format!("HTTP {}: {}", status, text)
+    };
+    if let Some((cut, _)) = output.char_indices().nth(MAX_RESPONSE_CHARS) {
+        output.truncate(cut);
+        output.push_str("\n... (truncated)");
+    }
+    if status.is_success() {
+        Ok(output)
+    } else {
+        Err(output)
+    }
+}
+
+#[cfg(not(feature = "sensors"))]
+async fn run_http(
+    _server: &PresenceServer,
+    _http: &config::HttpTemplate,
+    _args: &JsonObject,
+) -> Result<String, String> {
+    Err("HTTP tools need the sensors feature".to_string())
+}
+
+#[cfg(all(feature = "linux", target_os = "linux"))]
+async fn run_dbus(dbus: &config::DbusTemplate, args: &JsonObject) -> Result<String, String> {
+    let values: Vec<serde_json::Value> = dbus.args.iter().map(|a| fill_json(a, args)).collect();
+    crate::linux::dbus::call_json(
+        dbus.session,
+        &dbus.destination,
+        &dbus.path,
+        &dbus.interface,
+        &dbus.method,
+        &values,
+    )
+    .await
+}
+
+#[cfg(not(all(feature = "linux", target_os = "linux")))]
+async fn run_dbus(_dbus: &config::DbusTemplate, _args: &JsonObject) -> Result<String, String> {
+    Err("D-Bus tools need the linux feature".to_string())
+}
+
+// === Templates ===
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Names of the `{name}` placeholders in a template. Braces around anything other
+/// than an identifier are literal text.
+fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    scan(template, |piece| {
+        if let Piece::Placeholder(name) = piece {
+            names.push(name);
+        }
+    });
+    names
+}
+
+/// Replace placeholders with the argument values, passed through `encode`. Inserted
+/// values are never scanned for placeholders themselves.
+fn substitute(template: &str, args: &JsonObject, encode: impl Fn(String) -> String) -> String {
+    let mut output = String::new();
+    scan(template, |piece| match piece {
+        Piece::Literal(literal) => output.push_str(literal),
+        Piece::Placeholder(name) => match args.get(name) {
+            Some(value) => output.push_str(&encode(text(value))),
+            None => output.push_str(&format!("{{{}}}", name)),
+        },
+    });
+    output
+}
+
+enum Piece<'a> {
+    Literal(&'a str),
+    Placeholder(&'a str),
+}
+
+fn scan<'a>(template: &'a str, mut visit: impl FnMut(Piece<'a>)) {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let name = rest[start + 1..]
+            .find('}')
+            .map(|end| &rest[start + 1..start + 1 + end])
+            .filter(|name| is_identifier(name));
+        match name {
+            Some(name) => {
+                visit(Piece::Literal(&rest[..start]));
+                visit(Piece::Placeholder(name));
+                rest = &rest[start + name.len() + 2..];
+            }
+            None => {
+                visit(Piece::Literal(&rest[..=start]));
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    visit(Piece::Literal(rest));
+}
+
+/// Fill a JSON template: a string that is exactly one placeholder becomes the typed
+/// value, other strings get the values substituted as text
+fn fill_json(template: &serde_json::Value, args: &JsonObject) -> serde_json::Value {
+    match template {
+        serde_json::Value::String(s) => {
+            let whole = s
+                .strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+                .and_then(|name| args.get(name));
+            match whole {
+                Some(value) => value.clone(),
+                None => substitute(s, args, |v| v).into(),
+            }
+        }
+        serde_json::Value::Array(items) => items.iter().map(|i| fill_json(i, args)).collect(),
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (key.clone(), fill_json(value, args)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        other => other.clone(),
+    }
+}
+
+fn json_strings<'a>(value: &'a serde_json::Value, strings: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => strings.push(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|i| json_strings(i, strings)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| json_strings(v, strings)),
+        _ => {}
+    }
+}
+
+fn text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn matches_type(value: &serde_json::Value, kind: ParamType) -> bool {
+    match kind {
+        ParamType::String => value.is_string(),
+        ParamType::Integer => value.is_i64() || value.is_u64(),
+        ParamType::Number => value.is_number(),
+        ParamType::Boolean => value.is_boolean(),
+    }
+}
+
+fn type_name(kind: ParamType) -> &'static str {
+    match kind {
+        ParamType::String => "string",
+        ParamType::Integer => "integer",
+        ParamType::Number => "number",
+        ParamType::Boolean => "boolean",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args() -> JsonObject {
+        json!({ "host": "nas/../admin?x=1", "count": 3 })
+            .as_object()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_substitution() {
+        assert_eq!(
+            placeholders("/api/{host}/{count}?{not a name}"),
+            ["host", "count"]
+        );
+
+        // Substituted values can't add path segments or a query string
+        #[cfg(feature = "sensors")]
+        {
+            let http = config::HttpTemplate {
+                url: "http://lab.local/hosts/{host}".to_string(),
+                ..config::HttpTemplate::default()
+            };
+            assert_eq!(
+                http_url(&http, &args()),
+                "http://lab.local/hosts/nas%2F..%2Fadmin%3Fx%3D1"
+            );
+        }
+
+        // Values are inserted once, never expanded again
+        let mut nested = args();
+        nested.insert("host".to_string(), json!("{count}"));
+        assert_eq!(substitute("{host}-{count}", &nested, |v| v), "{count}-3");
+
+        let body = fill_json(
+            &json!({ "limit": "{count}", "label": "host {host}", "tags": ["{host}"] }),
+            &args(),
+        );
+        assert_eq!(
+            body,
+            json!({ "limit": 3, "label": "host nas/../admin?x=1", "tags": ["nas/../admin?x=1"] })
+        );
+    }
+
+    #[test]
+    fn test_bind() {
+        let params = vec![
+            CustomParam {
+                name: "host".to_string(),
+                ..CustomParam::default()
+            },
+            CustomParam {
+                name: "count".to_string(),
+                kind: ParamType::Integer,
+                default: Some(json!(10)),
+                ..CustomParam::default()
+            },
+        ];
+
+        let bound = bind(
+            &params,
+            json!({ "host": "nas" }).as_object().unwrap().clone(),
+        )
+        .unwrap();
+        assert_eq!(bound.get("count"), Some(&json!(10)));
+
+        assert!(bind(&params, JsonObject::new()).is_err());
+        assert!(bind(&params, args_with("count", json!("many"))).is_err());
+        assert!(bind(&params, args_with("extra", json!(1))).is_err());
+    }
+
+    fn args_with(key: &str, value: serde_json::Value) -> JsonObject {
+        let mut args = json!({ "host": "nas" }).as_object().unwrap().clone();
+        args.insert(key.to_string(), value);
+        args
+    }
+
+    #[cfg(feature = "sensors")]
+    #[tokio::test]
+    async fn test_http_status_is_error() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 503 Service Unavailable\r\n\
+                            Content-Length: 4\r\nConnection: close\r\n\r\ndown";
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let server = PresenceServer::new(Config::default(), Role::Front).await;
+        let custom = CustomToolConfig {
+            http: Some(config::HttpTemplate {
+                url,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let result = call(&server, &custom, None).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(crate::composites::text_of(&result).contains("503"));
+    }
+}
@@ -0,0 +1,522 @@
+//! Network egress gate
+//!
+//! `network_egress` in tools.toml limits where tools may open connections: `any` (the
+//! default), `allowlist` (local addresses plus `egress_allowlist`), `local` (loopback,
+//! private and link-local addresses) or `none`. Every tool that connects somewhere asks
+//! the gate first and gets a policy error if the destination is off limits. Tools that
+//! always talk to the same remote host, or to any host at all in `none` mode, are
+//! removed at startup instead.
+//!
+//! Hostnames that the mode and allowlist don't settle by name are resolved, and every
+//! address they resolve to must be allowed. HTTP requests then connect to exactly those
+//! addresses, and outside `any` mode redirects are followed only to the same host or to
+//! hosts allowed by name.
+
+use rmcp::ErrorData as McpError;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::audit;
+use crate::config::{Config, EgressMode, WeatherProvider};
+use crate::shared::policy_error;
+
+/// Redirects followed before a request is given up
+const MAX_REDIRECTS: usize = 10;
+
+/// Built-in tools that always contact the same hosts, with each host or the URL it
+/// comes from. A tool listed with several hosts needs all of them. Public IP and
+/// weather services are configurable.
+fn fixed_destinations(config: &Config) -> Vec<(&'static str, Option<String>)> {
+    let host = |url: &str| url_host(url).map(|(host, _)| host);
+    let weather = host(config.weather.url());
+    let mut destinations = vec![
+        ("get_public_ip", host(config.public_ip.url())),
+        ("is_online", Some("1.1.1.1".to_string())),
+        ("get_weather", weather.clone()),
+        ("get_forecast", weather),
+        // The Ollama daemon does the download, but on our request
+        ("pull_model", Some("registry.ollama.ai".to_string())),
+    ];
+    // Open-Meteo looks place names up in a separate geocoding service
+    if config.weather.provider == WeatherProvider::OpenMeteo {
+        let geocoding = host(config.weather.geocoding_url());
+        destinations.push(("get_weather", geocoding.clone()));
+        destinations.push(("get_forecast", geocoding));
+    }
+    destinations
+}
+
+/// Built-in tools that connect to hosts chosen by the caller or the config
+const NETWORK_TOOLS: &[&str] = &[
+    "dns_lookup",
+    "probe_endpoint",
+    "check_endpoints",
+    "list_models",
+    "list_running",
+    "show_model",
+    "delete_model",
+    "get_ollama_status",
+];
+
+/// One `egress_allowlist` entry
+#[derive(Debug, Clone, PartialEq)]
+enum Allowed {
+    Host(String),
+    /// `*.example.com`: the domain and everything below it
+    Domain(String),
+    Network(IpAddr, u8),
+}
+
+impl Allowed {
+    fn parse(entry: &str) -> Option<Self> {
+        let entry = entry.trim().trim_end_matches('.').to_ascii_lowercase();
+        if let Some((addr, prefix)) = entry.split_once('/') {
+            let addr: IpAddr = addr.parse().ok()?;
+            let prefix: u8 = prefix.parse().ok()?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            return (prefix <= max).then_some(Allowed::Network(addr.to_canonical(), prefix));
+        }
+        if let Ok(addr) = entry.parse::<IpAddr>() {
+            let addr = addr.to_canonical();
+            let prefix = if addr.is_ipv4() { 32 } else { 128 };
+            return Some(Allowed::Network(addr, prefix));
+        }
+        if let Some(domain) = entry.strip_prefix("*.") {
+            return (!domain.is_empty()).then(|| Allowed::Domain(domain.to_string()));
+        }
+        (!entry.is_empty() && !entry.contains('*')).then_some(Allowed::Host(entry))
+    }
+
+    fn matches_name(&self, host: &str) -> bool {
+        match self {
+            Allowed::Host(name) => name == host,
+            Allowed::Domain(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            Allowed::Network(..) => false,
+        }
+    }
+
+    fn matches_ip(&self, ip: IpAddr) -> bool {
+        let Allowed::Network(network, prefix) = self else {
+            return false;
+        };
+        match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(*network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                u128::from(*network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Loopback, private (RFC 1918, unique local) and link-local addresses
+fn is_local(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(v4) => v4.is_loopback() || v4.is_private() || v4.is_link_local(),
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn mode_name(mode: EgressMode) -> &'static str {
+    match mode {
+        EgressMode::Any => "any",
+        EgressMode::Allowlist => "allowlist",
+        EgressMode::Local => "local",
+        EgressMode::None => "none",
+    }
+}
+
+/// Host and port of an absolute http(s) URL
+fn url_host(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let default_port = match scheme.to_ascii_lowercase().as_str() {
+        "http" => 80,
+        "https" => 443,
+        _ => return None,
+    };
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, after) = bracketed.split_once(']')?;
+            match after.strip_prefix(':') {
+                Some(port) => (host, port.parse().ok()?),
+                None if after.is_empty() => (host, default_port),
+                None => return None,
+            }
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port),
+        },
+    };
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+#[derive(Debug, Clone)]
+pub struct Egress {
+    mode: EgressMode,
+    allowed: Vec<Allowed>,
+    fixed: Vec<(&'static str, Option<String>)>,
+    /// Shared client for `any` mode, where nothing needs pinning
+    #[cfg(any(feature = "sensors", feature = "actuators"))]
+    http: reqwest::Client,
+}
+
+impl Egress {
+    pub fn new(config: &Config) -> Self {
+        let allowed = config
+            .egress_allowlist
+            .iter()
+            .filter_map(|entry| {
+                let parsed = Allowed::parse(entry);
+                if parsed.is_none() {
+                    tracing::warn!("Ignoring invalid egress_allowlist entry: {}", entry);
+                }
+                parsed
+            })
+            .collect();
+        if config.network_egress != EgressMode::Any {
+            tracing::info!("Network egress: {}", mode_name(config.network_egress));
+        }
+        Self {
+            mode: config.network_egress,
+            allowed,
+            fixed: fixed_destinations(config),
+            #[cfg(any(feature = "sensors", feature = "actuators"))]
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        match self.mode {
+            EgressMode::Any => true,
+            EgressMode::None => false,
+            EgressMode::Local => is_local(ip),
+            EgressMode::Allowlist => is_local(ip) || self.allowed.iter().any(|a| a.matches_ip(ip)),
+        }
+    }
+
+    /// Decide by the host string alone. `None` means only its addresses can tell.
+    fn allows_name(&self, host: &str) -> Option<bool> {
+        match self.mode {
+            EgressMode::Any => return Some(true),
+            EgressMode::None => return Some(false),
+            _ => {}
+        }
+        let host = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Some(self.allows_ip(ip));
+        }
+        if host == "localhost" || host.ends_with(".localhost") {
+            return Some(true);
+        }
+        if self.mode == EgressMode::Allowlist && self.allowed.iter().any(|a| a.matches_name(&host))
+        {
+            return Some(true);
+        }
+        None
+    }
+
+    /// Whether a fixed destination is allowed without resolving it
+    pub fn allows_fixed(&self, host: &str) -> bool {
+        self.allows_name(host).unwrap_or(false)
+    }
+
+    /// Tools to remove at startup because every call would be refused
+    pub fn hidden_tools(&self) -> Vec<&'static str> {
+        if self.mode == EgressMode::Any {
+            return Vec::new();
+        }
+        // An unparsable URL would be refused on every call as well
+        let mut hidden: Vec<&'static str> = self
+            .fixed
+            .iter()
+            .filter(|(_, host)| !host.as_deref().is_some_and(|host| self.allows_fixed(host)))
+            .map(|(tool, _)| *tool)
+            .collect();
+        if self.mode == EgressMode::None {
+            hidden.extend(NETWORK_TOOLS);
+        }
+        hidden.sort_unstable();
+        hidden.dedup();
+        hidden
+    }
+
+    fn blocked(&self, host: &str, reason: &str) -> McpError {
+        let mode = mode_name(self.mode);
+        audit::egress_blocked(host, mode, reason);
+        policy_error(
+            format!(
+                "Network access to {} is blocked by network_egress = \"{}\"",
+                host, mode
+            ),
+            serde_json::json!({
+                "policy": "network_egress",
+                "mode": mode,
+                "host": host,
+                "reason": reason,
+            }),
+        )
+    }
+
+    pub fn check_addr(&self, addr: SocketAddr) -> Result<(), McpError> {
+        if self.allows_ip(addr.ip()) {
+            Ok(())
+        } else {
+            Err(self.blocked(&addr.ip().to_string(), "address"))
+        }
+    }
+
+    /// Allow or refuse a connection to `host`, with the reason for a refusal. When the
+    /// name alone didn't decide, the allowed addresses it resolved to are returned.
+    async fn decide(&self, host: &str, port: u16) -> Result<Option<Vec<SocketAddr>>, &'static str> {
+        match self.allows_name(host) {
+            Some(true) => return Ok(None),
+            Some(false) => return Err("host"),
+            None => {}
+        }
+        let addrs: Vec<SocketAddr> = match tokio::net::lookup_host((host, port)).await {
+            Ok(addrs) => addrs.collect(),
+            Err(_) => return Err("unresolved"),
+        };
+        if addrs.is_empty() || !addrs.iter().all(|addr| self.allows_ip(addr.ip())) {
+            return Err("address");
+        }
+        Ok(Some(addrs))
+    }
+
+    /// Check a connection to `host`, resolving it when the name alone doesn't decide
+    pub async fn check_host(&self, host: &str, port: u16) -> Result<(), McpError> {
+        self.resolve_host(host, port).await.map(|_| ())
+    }
+
+    /// Like `check_host`, returning the addresses that were checked when `host` had to
+    /// be resolved. Connect to one of those rather than looking the name up again.
+    pub async fn resolve_host(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> Result<Option<Vec<SocketAddr>>, McpError> {
+        self.decide(host, port)
+            .await
+            .map_err(|reason| self.blocked(host, reason))
+    }
+
+    /// Check a request to an http(s) URL
+    pub async fn check_url(&self, url: &str) -> Result<(), McpError> {
+        if self.mode == EgressMode::Any {
+            return Ok(());
+        }
+        let Some((host, port)) = url_host(url) else {
+            return Err(self.blocked(url, "unparsable_url"));
+        };
+        self.check_host(&host, port).await
+    }
+
+    /// Like `check_url`, for optional requests that are skipped quietly when refused
+    pub async fn allows_url(&self, url: &str) -> bool {
+        if self.mode == EgressMode::Any {
+            return true;
+        }
+        match url_host(url) {
+            Some((host, port)) => self.decide(&host, port).await.is_ok(),
+            None => false,
+        }
+    }
+
+    /// HTTP client for a request to `url`, or a policy error if the gate refuses it.
+    /// A host that had to be resolved is pinned to the addresses that were checked, so
+    /// a second lookup can't send the request elsewhere.
+    #[cfg(any(feature = "sensors", feature = "actuators"))]
+    pub async fn client(&self, url: &str) -> Result<reqwest::Client, McpError> {
+        if self.mode == EgressMode::Any {
+            return Ok(self.http.clone());
+        }
+        let Some((host, port)) = url_host(url) else {
+            return Err(self.blocked(url, "unparsable_url"));
+        };
+        let host = host.to_ascii_lowercase();
+        let resolved = self
+            .decide(&host, port)
+            .await
+            .map_err(|reason| self.blocked(&host, reason))?;
+
+        let mut builder = reqwest::Client::builder().redirect(self.redirect_policy(host.clone()));
+        if let Some(addrs) = resolved {
+            builder = builder.resolve_to_addrs(&host, &addrs);
+        }
+        builder.build().map_err(|e| {
+            crate::shared::internal_error(format!("Failed to create HTTP client: {}", e))
+        })
+    }
+
+    /// Follow redirects to the checked host or to hosts allowed by name; anything that
+    /// would need a fresh lookup is refused
+    #[cfg(any(feature = "sensors", feature = "actuators"))]
+    fn redirect_policy(&self, checked: String) -> reqwest::redirect::Policy {
+        let egress = self.clone();
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            let host = attempt
+                .url()
+                .host_str()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if host == checked || egress.allows_name(&host) == Some(true) {
+                return attempt.follow();
+            }
+            let mode = mode_name(egress.mode);
+            audit::egress_blocked(&host, mode, "redirect");
+            attempt.error(format!(
+                "Redirect to {} is blocked by network_egress = \"{}\"",
+                host, mode
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn egress(mode: EgressMode, allowlist: &[&str]) -> Egress {
+        let config = Config {
+            network_egress: mode,
+            egress_allowlist: allowlist.iter().map(|s| s.to_string()).collect(),
+            ..Config::default()
+        };
+        Egress::new(&config)
+    }
+
+    #[test]
+    fn test_url_host() {
+        assert_eq!(
+            url_host("https://wttr.in/Paris?format=j1"),
+            Some(("wttr.in".into(), 443))
+        );
+        assert_eq!(
+            url_host("http://localhost:11434/api/tags"),
+            Some(("localhost".into(), 11434))
+        );
+        assert_eq!(
+            url_host("http://user:pw@[::1]:8080/x"),
+            Some(("::1".into(), 8080))
+        );
+        assert_eq!(url_host("ftp://example.com"), None);
+    }
+
+    #[test]
+    fn test_modes() {
+        let local = egress(EgressMode::Local, &[]);
+        assert!(local.allows_ip("192.168.1.20".parse().unwrap()));
+        assert!(local.allows_ip("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(local.allows_ip("fd00::1".parse().unwrap()));
+        assert!(!local.allows_ip("1.1.1.1".parse().unwrap()));
+        assert!(local.allows_fixed("localhost"));
+        assert!(!local.allows_fixed("wttr.in"));
+
+        let allow = egress(
+            EgressMode::Allowlist,
+            &["*.open-meteo.com", "8.8.8.0/24", "not valid*"],
+        );
+        assert!(allow.allows_fixed("api.open-meteo.com"));
+        assert!(allow.allows_fixed("open-meteo.com"));
+        assert!(!allow.allows_fixed("evil-open-meteo.com"));
+        assert!(allow.allows_ip("8.8.8.8".parse().unwrap()));
+        assert!(!allow.allows_ip("8.8.4.4".parse().unwrap()));
+
+        let none = egress(EgressMode::None, &[]);
+        assert!(!none.allows_fixed("127.0.0.1"));
+        assert!(none.hidden_tools().contains(&"dns_lookup"));
+        assert!(egress(EgressMode::Any, &[]).hidden_tools().is_empty());
+
+        // Weather served from the LAN stays available in local mode
+        let mut config = Config {
+            network_egress: EgressMode::Local,
+            ..Config::default()
+        };
+        config.weather.url = Some("http://192.168.1.5:8080".into());
+        let hidden = Egress::new(&config).hidden_tools();
+        assert!(!hidden.contains(&"get_forecast"));
+        assert!(hidden.contains(&"get_public_ip"));
+
+        // Open-Meteo also needs its geocoding host
+        config.weather.provider = WeatherProvider::OpenMeteo;
+        assert!(Egress::new(&config).hidden_tools().contains(&"get_forecast"));
+        config.weather.geocoding_url = Some("http://192.168.1.5:8081".into());
+        assert!(!Egress::new(&config).hidden_tools().contains(&"get_forecast"));
+    }
+
+    #[cfg(any(feature = "sensors", feature = "actuators"))]
+    #[tokio::test]
+    async fn test_redirects() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let local = base.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let len = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]);
+                let response = match request.split_whitespace().nth(1) {
+                    Some("/hop") => format!("HTTP/1.1 302 Found\r\nLocation: {}/ok\r\n", local),
+                    Some("/away") => "HTTP/1.1 302 Found\r\nLocation: http://1.1.1.1/\r\n".into(),
+                    _ => "HTTP/1.1 200 OK\r\n".into(),
+                };
+                let response = format!(
+                    "{}Content-Length: 2\r\nConnection: close\r\n\r\nok",
+                    response
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let local = egress(EgressMode::Local, &[]);
+        let url = format!("{}/hop", base);
+        let response = local
+            .client(&url)
+            .await
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        // An allowed host must not be able to bounce the request to a blocked one
+        let url = format!("{}/away", base);
+        let err = local
+            .client(&url)
+            .await
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap_err();
+        assert!(err.is_redirect());
+        assert!(format!("{:?}", err).contains("blocked by network_egress"));
+    }
+}
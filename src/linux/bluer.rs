@@ -51,6 +51,19 @@ async fn get_adapter(session: &Session, name: Option<&str>) -> Result<Adapter, S
     }
 }
 
+/// Check whether BlueZ is reachable on the system bus
+pub async fn probe() -> Result<String, String> {
+    let session = get_session().await?;
+    let adapters = session
+        .adapter_names()
+        .await
+        .map_err(|e| format!("Failed to list adapters: {}", e))?;
+    if adapters.is_empty() {
+        return Err("BlueZ is running but no adapters are present".to_string());
+    }
+    Ok(format!("adapters: {}", adapters.join(", ")))
+}
+
 fn parse_address(addr: &str) -> Result<Address, String> {
     addr.parse()
         .map_err(|_| format!("Invalid Bluetooth address: {}", addr))
@@ -22,6 +22,37 @@ pub struct SetBrightnessParams {
     pub device: Option<String>,
 }
 
+// === Helper Functions ===
+
+/// Name and current level of the device `set_brightness` would change, for the undo journal
+pub async fn current_brightness(device: Option<&str>) -> Result<(String, u32), String> {
+    let mut devices = brightness_devices();
+
+    while let Ok(Some(dev)) = devices.try_next().await {
+        let name = match dev.device_name().await {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+
+        if let Some(target) = device {
+            if !name.to_lowercase().contains(&target.to_lowercase()) {
+                continue;
+            }
+        }
+
+        return dev
+            .get()
+            .await
+            .map(|level| (name.clone(), level))
+            .map_err(|e| format!("Failed to get brightness for {}: {}", name, e));
+    }
+
+    Err(match device {
+        Some(d) => format!("Device '{}' not found", d),
+        None => "No brightness devices found".to_string(),
+    })
+}
+
 // === Tool Functions ===
 
 pub async fn list_brightness_devices() -> Result<CallToolResult, McpError> {
@@ -120,7 +151,7 @@ pub async fn set_brightness(params: SetBrightnessParams) -> Result<CallToolResul
                 ))]))
             }
             Err(e) => {
-                return Ok(CallToolResult::success(vec![Content::text(format!(
+                return Ok(CallToolResult::error(vec![Content::text(format!(
                     "Failed to set brightness for {}: {}",
                     name, e
                 ))]))
@@ -128,7 +159,7 @@ pub async fn set_brightness(params: SetBrightnessParams) -> Result<CallToolResul
         }
     }
 
-    Ok(CallToolResult::success(vec![Content::text(
+    Ok(CallToolResult::error(vec![Content::text(
         match params.device {
             Some(d) => format!("Device '{}' not found", d),
             None => "No brightness devices found".to_string(),
@@ -5,7 +5,7 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use zbus::{
     proxy::Proxy,
-    zvariant::{OwnedValue, Value},
+    zvariant::{OwnedValue, Signature, Structure, StructureBuilder, Value},
     Connection,
 };
 
@@ -124,8 +124,58 @@ fn owned_value_to_string(value: &OwnedValue) -> String {
     format!("{:?}", value)
 }
 
+/// Call a method with any number of JSON arguments and format the reply, for
+/// config-defined tools
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "call_json"))]
+pub async fn call_json(
+    session: bool,
+    destination: &str,
+    path: &str,
+    interface: &str,
+    method: &str,
+    args: &[serde_json::Value],
+) -> Result<String, String> {
+    let conn = get_connection(session).await?;
+    let proxy = Proxy::new(&conn, destination, path, interface)
+        .await
+        .map_err(|e| format!("Failed to create proxy: {}", e))?;
+
+    let values = args
+        .iter()
+        .map(json_to_value)
+        .collect::<Result<Vec<_>, String>>()?;
+    let reply = if values.is_empty() {
+        proxy.call_method(method, &()).await
+    } else {
+        let body = values
+            .into_iter()
+            .fold(StructureBuilder::new(), |builder, value| {
+                builder.append_field(value)
+            })
+            .build()
+            .map_err(|e| format!("Invalid arguments: {}", e))?;
+        proxy.call_method(method, &body).await
+    }
+    .map_err(|e| format!("Method call failed: {}", e))?;
+
+    let body = reply.body();
+    if *body.signature() == Signature::Unit {
+        return Ok("(no return value)".to_string());
+    }
+    let values: Structure = body
+        .deserialize()
+        .map_err(|e| format!("Failed to decode reply: {}", e))?;
+    Ok(values
+        .fields()
+        .iter()
+        .map(|value| format!("{:?}", value))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
 // === Tool Functions ===
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "list_names"))]
 pub async fn list_names(params: BusParams) -> Result<CallToolResult, McpError> {
     let conn = match get_connection(params.session).await {
         Ok(c) => c,
@@ -177,6 +227,7 @@ pub async fn list_names(params: BusParams) -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "introspect"))]
 pub async fn introspect(params: IntrospectParams) -> Result<CallToolResult, McpError> {
     let conn = match get_connection(params.session).await {
         Ok(c) => c,
@@ -216,6 +267,7 @@ pub async fn introspect(params: IntrospectParams) -> Result<CallToolResult, McpE
     ))]))
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "call_method"))]
 pub async fn call_method(params: MethodParams) -> Result<CallToolResult, McpError> {
     let conn = match get_connection(params.session).await {
         Ok(c) => c,
@@ -306,6 +358,7 @@ pub async fn call_method(params: MethodParams) -> Result<CallToolResult, McpErro
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "get_property"))]
 pub async fn get_property(params: PropertyParams) -> Result<CallToolResult, McpError> {
     let conn = match get_connection(params.session).await {
         Ok(c) => c,
@@ -357,6 +410,7 @@ pub async fn get_property(params: PropertyParams) -> Result<CallToolResult, McpE
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "set_property"))]
 pub async fn set_property(params: SetPropertyParams) -> Result<CallToolResult, McpError> {
     let conn = match get_connection(params.session).await {
         Ok(c) => c,
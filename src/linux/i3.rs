@@ -54,6 +54,34 @@ async fn connect() -> Result<I3, McpError> {
         .map_err(|e| internal_error(format!("Failed to connect to i3: {}", e)))
 }
 
+/// Check whether the i3 IPC socket is reachable
+pub async fn probe() -> Result<String, String> {
+    let mut conn = I3::connect()
+        .await
+        .map_err(|e| format!("i3 socket not reachable: {}", e))?;
+    let version = conn
+        .get_version()
+        .await
+        .map_err(|e| format!("i3 IPC error: {}", e))?;
+    Ok(format!("i3 {}", version.human_readable))
+}
+
+/// Name of the focused workspace, for the undo journal
+pub async fn focused_workspace() -> Result<String, String> {
+    let mut conn = I3::connect()
+        .await
+        .map_err(|e| format!("i3 socket not reachable: {}", e))?;
+    let workspaces = conn
+        .get_workspaces()
+        .await
+        .map_err(|e| format!("Failed to get workspaces: {}", e))?;
+    workspaces
+        .into_iter()
+        .find(|ws| ws.focused)
+        .map(|ws| ws.name)
+        .ok_or_else(|| "No focused workspace".to_string())
+}
+
 // === Tool Functions ===
 
 pub async fn get_workspaces() -> Result<CallToolResult, McpError> {
@@ -101,7 +129,7 @@ pub async fn switch_workspace(params: SwitchWorkspaceParams) -> Result<CallToolR
         ))]))
     } else {
         let errors: Vec<String> = results.iter().filter_map(|r| r.error.clone()).collect();
-        Ok(CallToolResult::success(vec![Content::text(format!(
+        Ok(CallToolResult::error(vec![Content::text(format!(
             "Failed to switch workspace: {}",
             errors.join(", ")
         ))]))
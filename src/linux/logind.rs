@@ -4,7 +4,7 @@ use rmcp::{model::*, ErrorData as McpError};
 use schemars::JsonSchema;
 use serde::Deserialize;
 use zbus::Connection;
-use zbus_systemd::login1::ManagerProxy;
+use zbus_systemd::login1::{ManagerProxy, SessionProxy};
 
 // === Parameter Types ===
 
@@ -32,8 +32,53 @@ async fn get_manager() -> Result<ManagerProxy<'static>, String> {
         .map_err(|e| format!("Failed to create logind proxy: {}", e))
 }
 
+/// Whether the user's seat sessions are all locked (logind `LockedHint`)
+pub async fn session_locked() -> Result<bool, String> {
+    let connection = Connection::system()
+        .await
+        .map_err(|e| format!("Failed to connect to system bus: {}", e))?;
+    let manager = ManagerProxy::new(&connection)
+        .await
+        .map_err(|e| format!("Failed to create logind proxy: {}", e))?;
+    let sessions = manager
+        .list_sessions()
+        .await
+        .map_err(|e| format!("Failed to list sessions: {}", e))?;
+
+    // SAFETY: getuid has no preconditions and cannot fail
+    let uid = unsafe { libc::getuid() };
+    let mut found = false;
+    for (_, session_uid, _, seat, path) in sessions {
+        // Only sessions on a seat have a screen to lock; skip SSH and the like
+        if session_uid != uid || seat.is_empty() {
+            continue;
+        }
+        let session = SessionProxy::builder(&connection)
+            .path(path)
+            .map_err(|e| format!("Invalid session path: {}", e))?
+            .build()
+            .await
+            .map_err(|e| format!("Failed to create session proxy: {}", e))?;
+        if !session
+            .locked_hint()
+            .await
+            .map_err(|e| format!("Failed to read LockedHint: {}", e))?
+        {
+            return Ok(false);
+        }
+        found = true;
+    }
+
+    if found {
+        Ok(true)
+    } else {
+        Err(format!("No local session for uid {}", uid))
+    }
+}
+
 // === Tool Functions ===
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "suspend"))]
 pub async fn suspend(params: InteractiveParams) -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -51,6 +96,7 @@ pub async fn suspend(params: InteractiveParams) -> Result<CallToolResult, McpErr
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "hibernate"))]
 pub async fn hibernate(params: InteractiveParams) -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -68,6 +114,7 @@ pub async fn hibernate(params: InteractiveParams) -> Result<CallToolResult, McpE
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "poweroff"))]
 pub async fn poweroff(params: InteractiveParams) -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -85,6 +132,7 @@ pub async fn poweroff(params: InteractiveParams) -> Result<CallToolResult, McpEr
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "reboot"))]
 pub async fn reboot(params: InteractiveParams) -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -102,6 +150,7 @@ pub async fn reboot(params: InteractiveParams) -> Result<CallToolResult, McpErro
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "lock_session"))]
 pub async fn lock_session(params: SessionIdParams) -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -120,6 +169,7 @@ pub async fn lock_session(params: SessionIdParams) -> Result<CallToolResult, Mcp
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "list_sessions"))]
 pub async fn list_sessions() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -154,6 +204,7 @@ pub async fn list_sessions() -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "can_suspend"))]
 pub async fn can_suspend() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -172,6 +223,7 @@ pub async fn can_suspend() -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "can_hibernate"))]
 pub async fn can_hibernate() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -190,6 +242,7 @@ pub async fn can_hibernate() -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "can_poweroff"))]
 pub async fn can_poweroff() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -208,6 +261,7 @@ pub async fn can_poweroff() -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "can_reboot"))]
 pub async fn can_reboot() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
@@ -226,6 +280,7 @@ pub async fn can_reboot() -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "get_capabilities"))]
 pub async fn get_capabilities() -> Result<CallToolResult, McpError> {
     let manager = match get_manager().await {
         Ok(m) => m,
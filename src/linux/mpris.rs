@@ -48,6 +48,16 @@ fn find_player(finder: &PlayerFinder, name: Option<&str>) -> Result<Player, Stri
     }
 }
 
+/// Identity and volume of the player `set_player_volume` would change, for the undo journal
+pub fn current_player_volume(name: Option<&str>) -> Result<(String, f64), String> {
+    let finder = get_player_finder()?;
+    let player = find_player(&finder, name)?;
+    let volume = player
+        .get_volume()
+        .map_err(|e| format!("Failed to get volume: {}", e))?;
+    Ok((player.identity().to_string(), volume))
+}
+
 fn format_metadata(player: &Player) -> String {
     let meta = match player.get_metadata() {
         Ok(m) => m,
@@ -286,7 +296,7 @@ pub async fn get_player_volume(params: PlayerParams) -> Result<CallToolResult, M
 pub async fn set_player_volume(params: SetVolumeParams) -> Result<CallToolResult, McpError> {
     let finder = match get_player_finder() {
         Ok(f) => f,
-        Err(e) => return Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
     };
 
     match find_player(&finder, params.player.as_deref()) {
@@ -298,12 +308,12 @@ pub async fn set_player_volume(params: SetVolumeParams) -> Result<CallToolResult
                     player.identity(),
                     vol * 100.0
                 ))])),
-                Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
                     "Failed to set volume: {}",
                     e
                 ))])),
             }
         }
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
     }
 }
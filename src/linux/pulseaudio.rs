@@ -38,6 +38,105 @@ pub struct MoveAppParams {
     pub device_name: String,
 }
 
+// === Capability Probe ===
+
+/// Check whether a PulseAudio (or pipewire-pulse) server accepts connections
+pub async fn probe() -> Result<String, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut handler = SinkController::create()
+            .map_err(|e| format!("Failed to connect to PulseAudio: {:?}", e))?;
+        let info = handler
+            .get_server_info()
+            .map_err(|e| format!("Failed to get server info: {:?}", e))?;
+        Ok(format!(
+            "{} {}",
+            info.server_name.unwrap_or_else(|| "pulseaudio".to_string()),
+            info.server_version.unwrap_or_default()
+        ))
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+}
+
+// === Undo Support ===
+
+/// Name of the default sink, for the undo journal
+pub async fn default_sink_name() -> Result<String, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut handler = SinkController::create()
+            .map_err(|e| format!("Failed to connect to PulseAudio: {:?}", e))?;
+        let dev = handler
+            .get_default_device()
+            .map_err(|e| format!("Failed to get default sink: {:?}", e))?;
+        dev.name.ok_or_else(|| "Default sink has no name".to_string())
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+}
+
+/// Name of the default source, for the undo journal
+pub async fn default_source_name() -> Result<String, String> {
+    tokio::task::spawn_blocking(|| {
+        let mut handler = SourceController::create()
+            .map_err(|e| format!("Failed to connect to PulseAudio: {:?}", e))?;
+        let dev = handler
+            .get_default_device()
+            .map_err(|e| format!("Failed to get default source: {:?}", e))?;
+        dev.name.ok_or_else(|| "Default source has no name".to_string())
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+}
+
+/// Raw per-channel volumes and mute state of an application, for the undo journal
+pub async fn sink_input_state(index: u32) -> Result<(Vec<u32>, bool), String> {
+    tokio::task::spawn_blocking(move || {
+        let mut handler = SinkController::create()
+            .map_err(|e| format!("Failed to connect to PulseAudio: {:?}", e))?;
+        let app = handler
+            .get_app_by_index(index)
+            .map_err(|e| format!("Failed to get app {}: {:?}", index, e))?;
+        Ok((app.volume.get().iter().map(|v| v.0).collect(), app.mute))
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+}
+
+/// Put back per-channel volumes recorded by `sink_input_state`
+pub async fn restore_sink_input_volume(
+    index: u32,
+    volumes: Vec<u32>,
+) -> Result<CallToolResult, McpError> {
+    let result = tokio::task::spawn_blocking(move || {
+        let mut handler = SinkController::create()
+            .map_err(|e| format!("Failed to connect to PulseAudio: {:?}", e))?;
+        let app = handler
+            .get_app_by_index(index)
+            .map_err(|e| format!("Failed to get app {}: {:?}", index, e))?;
+
+        let mut channels = app.volume;
+        for (channel, raw) in channels.get_mut().iter_mut().zip(&volumes) {
+            channel.0 = *raw;
+        }
+        let op = handler
+            .handler
+            .introspect
+            .set_sink_input_volume(index, &channels, None);
+        handler
+            .handler
+            .wait_for_operation(op)
+            .map_err(|e| format!("Failed to set volume: {:?}", e))?;
+        Ok::<_, String>(format!("Volume for app {} restored", index))
+    })
+    .await
+    .map_err(|e| internal_error(format!("Task failed: {}", e)))?;
+
+    match result {
+        Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
+    }
+}
+
 // === Tool Functions ===
 
 pub async fn list_sinks() -> Result<CallToolResult, McpError> {
@@ -281,7 +380,7 @@ pub async fn set_default_sink(params: NameParams) -> Result<CallToolResult, McpE
 
     match result {
         Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
     }
 }
 
@@ -300,7 +399,7 @@ pub async fn set_default_source(params: NameParams) -> Result<CallToolResult, Mc
 
     match result {
         Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
     }
 }
 
@@ -324,7 +423,7 @@ pub async fn set_sink_input_volume(params: VolumeParams) -> Result<CallToolResul
 
     match result {
         Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
     }
 }
 
@@ -348,7 +447,7 @@ pub async fn set_sink_input_mute(params: MuteParams) -> Result<CallToolResult, M
 
     match result {
         Ok(output) => Ok(CallToolResult::success(vec![Content::text(output)])),
-        Err(e) => Ok(CallToolResult::success(vec![Content::text(e)])),
+        Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
     }
 }
 
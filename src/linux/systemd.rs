@@ -7,6 +7,8 @@ use std::process::Command;
 use zbus::Connection;
 use zbus_systemd::systemd1::ManagerProxy;
 
+use crate::shared::CommandExt;
+
 // === Parameter Types ===
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -87,6 +89,7 @@ fn chrono_lite(unix_secs: u64) -> String {
 
 // === Tool Functions ===
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "list_units"))]
 pub async fn list_units(params: ListParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -141,6 +144,7 @@ pub async fn list_units(params: ListParams) -> Result<CallToolResult, McpError>
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "get_unit_status"))]
 pub async fn get_unit_status(params: UnitParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -214,6 +218,7 @@ pub async fn get_unit_status(params: UnitParams) -> Result<CallToolResult, McpEr
     Ok(CallToolResult::success(vec![Content::text(info.join("\n"))]))
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "start_unit"))]
 pub async fn start_unit(params: UnitParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -244,6 +249,7 @@ pub async fn start_unit(params: UnitParams) -> Result<CallToolResult, McpError>
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "stop_unit"))]
 pub async fn stop_unit(params: UnitParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -274,6 +280,7 @@ pub async fn stop_unit(params: UnitParams) -> Result<CallToolResult, McpError> {
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "restart_unit"))]
 pub async fn restart_unit(params: UnitParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -304,6 +311,7 @@ pub async fn restart_unit(params: UnitParams) -> Result<CallToolResult, McpError
     }
 }
 
+#[tracing::instrument(name = "dbus", skip_all, fields(op = "list_failed_units"))]
 pub async fn list_failed_units(params: FailedParams) -> Result<CallToolResult, McpError> {
     let user = params.user.unwrap_or(false);
     let conn = match get_connection(user).await {
@@ -359,7 +367,7 @@ pub async fn get_unit_logs(params: LogsParams) -> Result<CallToolResult, McpErro
         cmd.arg("--user");
     }
 
-    match cmd.output() {
+    match cmd.traced_output() {
         Ok(output) => {
             if output.status.success() {
                 let logs = String::from_utf8_lossy(&output.stdout);
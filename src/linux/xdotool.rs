@@ -1,6 +1,6 @@
 //! Mouse and keyboard automation via xdotool
 
-use crate::shared::internal_error;
+use crate::shared::{internal_error, CommandExt};
 use rmcp::{model::*, ErrorData as McpError};
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -105,7 +105,7 @@ fn button_name(button: u8) -> &'static str {
 pub async fn move_mouse(params: MoveMouseParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["mousemove", &params.x.to_string(), &params.y.to_string()])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -124,7 +124,7 @@ pub async fn move_mouse(params: MoveMouseParams) -> Result<CallToolResult, McpEr
 pub async fn click(params: ClickParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["click", &params.button.to_string()])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -149,7 +149,7 @@ pub async fn click_at(params: ClickAtParams) -> Result<CallToolResult, McpError>
             "click",
             &params.button.to_string(),
         ])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -170,7 +170,7 @@ pub async fn click_at(params: ClickAtParams) -> Result<CallToolResult, McpError>
 pub async fn type_text(params: TypeTextParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["type", "--delay", &params.delay.to_string(), &params.text])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -189,7 +189,7 @@ pub async fn type_text(params: TypeTextParams) -> Result<CallToolResult, McpErro
 pub async fn key_press(params: KeyPressParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["key", &params.key])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -220,7 +220,7 @@ pub async fn scroll(params: ScrollParams) -> Result<CallToolResult, McpError> {
 
     let output = Command::new("xdotool")
         .args(["click", "--repeat", &params.clicks.to_string(), button])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -239,7 +239,7 @@ pub async fn scroll(params: ScrollParams) -> Result<CallToolResult, McpError> {
 pub async fn get_mouse_position() -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["getmouselocation", "--shell"])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -268,7 +268,7 @@ pub async fn get_mouse_position() -> Result<CallToolResult, McpError> {
 pub async fn double_click() -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["click", "--repeat", "2", "1"])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -297,7 +297,7 @@ pub async fn search_window(params: SearchWindowParams) -> Result<CallToolResult,
 
     let output = Command::new("xdotool")
         .args(&args)
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -327,7 +327,7 @@ pub async fn search_window(params: SearchWindowParams) -> Result<CallToolResult,
 pub async fn get_active_window() -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["getactivewindow"])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -347,7 +347,7 @@ pub async fn get_active_window() -> Result<CallToolResult, McpError> {
 pub async fn get_window_geometry(params: WindowIdParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["getwindowgeometry", "--shell", &params.window_id])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
@@ -387,7 +387,7 @@ pub async fn get_window_geometry(params: WindowIdParams) -> Result<CallToolResul
 pub async fn get_window_name(params: WindowIdParams) -> Result<CallToolResult, McpError> {
     let output = Command::new("xdotool")
         .args(["getwindowname", &params.window_id])
-        .output()
+        .traced_output()
         .map_err(|e| internal_error(format!("Failed to run xdotool: {}", e)))?;
 
     if output.status.success() {
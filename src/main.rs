@@ -9,18 +9,43 @@
 
 use clap::{Parser, Subcommand};
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters, ServerHandler},
+    handler::server::{
+        router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters, ServerHandler,
+    },
     model::*,
+    service::{Peer, RequestContext},
     ErrorData as McpError,
+    RoleServer,
     ServiceExt,
 };
 use schemars::JsonSchema;
 use serde::Deserialize;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use std::sync::Arc;
+use tracing::Instrument;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
 // === Modules ===
 
+mod audit;
+mod capabilities;
+mod composites;
+mod conditions;
 mod config;
+mod custom_tools;
+mod egress;
+mod privacy;
+mod ratelimit;
+mod redact;
+#[cfg(target_os = "linux")]
+mod sandbox;
+mod snapshots;
+#[cfg(unix)]
+mod split;
+mod stats;
+#[cfg(feature = "otel")]
+mod telemetry;
+mod undo;
+mod workflows;
 
 #[cfg(feature = "sensors")]
 mod sensors;
@@ -47,6 +72,15 @@ struct Cli {
 enum Commands {
     /// Open the config file in your editor to enable/disable tools
     Config,
+    /// Probe backends (i3, xdotool, BlueZ, PulseAudio, Ollama, ...) and report what is available
+    Doctor,
+    /// Serve actuator and Linux tools on the split-mode helper socket
+    #[cfg(unix)]
+    Helper {
+        /// Socket path (overrides `[split] socket` in the config)
+        #[arg(long)]
+        socket: Option<std::path::PathBuf>,
+    },
 }
 
 // === Common Parameter Types ===
@@ -56,25 +90,70 @@ pub struct EmptyParams {}
 
 // === Server ===
 
-#[derive(Debug)]
+/// Which tools a server process exposes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Every compiled-in tool
+    Standalone,
+    /// Server and sensor tools; everything else is forwarded to the helper
+    Front,
+    /// Actuator and Linux tools, served on the helper socket
+    Helper,
+}
+
+#[derive(Debug, Clone)]
 pub struct PresenceServer {
     pub tool_router: ToolRouter<Self>,
+    pub config: config::Config,
+    pub capabilities: capabilities::Capabilities,
+    pub redactor: redact::Redactor,
+    pub privacy: Arc<privacy::Privacy>,
+    pub rate_limiter: Arc<ratelimit::RateLimiter>,
+    pub stats: Arc<stats::Stats>,
+    pub journal: Arc<undo::Journal>,
+    pub snapshots: Arc<snapshots::Snapshots>,
+    pub egress: egress::Egress,
+    #[cfg(unix)]
+    pub helper: Option<Arc<split::Forwarder>>,
     #[cfg(feature = "sensors")]
-    pub http_client: reqwest::Client,
-}
-
-impl Default for PresenceServer {
-    fn default() -> Self {
-        Self::new()
-    }
+    pub system: Arc<sensors::state::SystemState>,
+    #[cfg(feature = "sensors")]
+    pub history: Arc<sensors::history::History>,
 }
 
 impl PresenceServer {
-    pub fn new() -> Self {
+    pub async fn new(config: config::Config, role: Role) -> Self {
         let mut tool_router = Self::tool_router();
 
-        // Load config and filter disabled tools
-        let config = config::Config::load();
+        // In split mode each process only keeps its own half of the tools
+        let foreign = match role {
+            Role::Standalone => Vec::new(),
+            Role::Front => config::actuator_tool_names(),
+            // The front asks the helper for its backend probes
+            Role::Helper => config::sensor_tool_names()
+                .into_iter()
+                .filter(|tool_name| *tool_name != "get_server_capabilities")
+                .collect(),
+        };
+        for tool_name in foreign {
+            tool_router.remove_route(tool_name);
+        }
+
+        for route in custom_tools::routes(&config, role) {
+            tool_router.add_route(route);
+        }
+
+        // Composites and workflows run on the MCP-facing side, which can reach every tool
+        if role != Role::Helper {
+            for route in composites::routes(&config) {
+                tool_router.add_route(route);
+            }
+            for route in workflows::routes(&config) {
+                tool_router.add_route(route);
+            }
+        }
+
+        // Filter disabled tools
         let disabled_count = config.disabled.len();
 
         for tool_name in &config.disabled {
@@ -86,6 +165,36 @@ impl PresenceServer {
             }
         }
 
+        // Tools whose every call the egress setting would refuse
+        let egress = egress::Egress::new(&config);
+        for tool_name in egress.hidden_tools() {
+            if tool_router.has_route(tool_name) {
+                tool_router.remove_route(tool_name);
+                tracing::info!("Disabled by network_egress: {}", tool_name);
+            }
+        }
+
+        // Hide or flag tools whose backend is missing. All probed backends belong
+        // to the helper's half, so the front never touches them.
+        let capabilities = match role {
+            Role::Front => capabilities::Capabilities::default(),
+            _ => capabilities::Capabilities::probe().await,
+        };
+        for probe in capabilities.probes.iter().filter(|p| !p.available) {
+            for tool_name in probe.backend.tools() {
+                if config.auto_disable_unavailable && probe.backend.can_auto_disable() {
+                    if tool_router.has_route(tool_name) {
+                        tool_router.remove_route(tool_name);
+                        tracing::info!("Auto-disabled tool: {} ({})", tool_name, probe.detail);
+                    }
+                } else if let Some(route) = tool_router.map.get_mut(*tool_name) {
+                    let description = route.attr.description.clone().unwrap_or_default();
+                    route.attr.description =
+                        Some(format!("[unavailable: {}] {}", probe.detail, description).into());
+                }
+            }
+        }
+
         if disabled_count > 0 {
             tracing::info!(
                 "Loaded config: {} tools disabled, {} tools active",
@@ -94,37 +203,202 @@ impl PresenceServer {
             );
         }
 
+        let redactor = redact::Redactor::new(&config.redaction);
+        let privacy = Arc::new(privacy::Privacy::new(&config.privacy));
+        let rate_limiter = Arc::new(ratelimit::RateLimiter::new(&config));
+
+        #[cfg(unix)]
+        let helper = (role == Role::Front).then(|| {
+            tracing::info!(
+                "Split mode: forwarding actuator tools to {}",
+                config.split.socket_path().display()
+            );
+            Arc::new(split::Forwarder::new(&config.split))
+        });
+
+        // Sensor tools read from one shared sysinfo state; the helper has none of them
+        #[cfg(feature = "sensors")]
+        let system = Arc::new(sensors::state::SystemState::default());
+        #[cfg(feature = "sensors")]
+        let history = Arc::new(sensors::history::History::new(&config.history));
+        #[cfg(feature = "sensors")]
+        if role != Role::Helper {
+            system.start();
+            history.start(system.clone());
+        }
+
         Self {
             tool_router,
+            config,
+            capabilities,
+            redactor,
+            privacy,
+            rate_limiter,
+            stats: Arc::new(stats::Stats::default()),
+            journal: Arc::new(undo::Journal::default()),
+            snapshots: Arc::new(snapshots::Snapshots::default()),
+            egress,
+            #[cfg(unix)]
+            helper,
+            #[cfg(feature = "sensors")]
+            system,
             #[cfg(feature = "sensors")]
-            http_client: reqwest::Client::new(),
+            history,
         }
     }
+
+    /// A copy of the server for another MCP session, which has to ask for its own consent
+    #[cfg(unix)]
+    pub fn for_session(&self) -> Self {
+        Self {
+            privacy: self.privacy.for_session(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether `tool_name` is one of this server's tools, or the helper's in split mode
+    fn serves_tool(&self, tool_name: &str) -> bool {
+        if self.tool_router.has_route(tool_name) {
+            return true;
+        }
+        #[cfg(unix)]
+        if self.helper.is_some() {
+            return config::actuator_tool_names().contains(&tool_name)
+                || self.config.custom_tools.contains_key(tool_name);
+        }
+        false
+    }
+
+    /// Route a call to the local tools or, in split mode, to the helper
+    async fn dispatch(
+        &self,
+        tool_name: &str,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        #[cfg(unix)]
+        if let Some(helper) = &self.helper {
+            if !self.tool_router.has_route(tool_name) {
+                // The helper applies its own policy to the tools it serves
+                helper.set_upstream(&context.peer);
+                #[allow(unused_mut)]
+                let mut meta = Meta::new();
+                #[cfg(feature = "otel")]
+                if let Some(traceparent) = telemetry::current_traceparent() {
+                    meta.insert("traceparent".to_string(), traceparent.into());
+                }
+                return helper.call_tool(request, meta).await;
+            }
+        }
+
+        let capture = self.check_policy(tool_name, &context.peer).await?;
+
+        let tcc = ToolCallContext::new(self, request, context);
+        let result = self.tool_router.call(tcc).await;
+        if let Some(capture) = capture {
+            capture.finish(matches!(&result, Ok(r) if r.is_error != Some(true)));
+        }
+        result
+    }
+
+    /// Conditional policies, rate limits and privacy safeguards for a local call.
+    /// Sensitive tools get a capture slot that is settled when it goes out of scope.
+    pub async fn check_policy(
+        &self,
+        tool_name: &str,
+        peer: &Peer<RoleServer>,
+    ) -> Result<Option<privacy::Capture>, McpError> {
+        conditions::check(&self.config, tool_name).await?;
+        self.rate_limiter.acquire(&self.config, tool_name)?;
+
+        if self.config.in_category(tool_name, privacy::SENSITIVE) {
+            return self.privacy.before_capture(tool_name, peer).await.map(Some);
+        }
+        Ok(None)
+    }
 }
 
 // Tool implementations
 #[rmcp::tool_router]
 impl PresenceServer {
     // ============================================================
-    // SENSORS (Layer 1) - 30 tools
+    // SERVER - 2 tools
+    // ============================================================
+
+    #[rmcp::tool(description = "Get backend availability (i3, xdotool, breakrs, BlueZ, PulseAudio, Ollama, cameras) probed at startup, and which tools are unavailable as a result")]
+    pub async fn get_server_capabilities(
+        &self,
+        Parameters(_params): Parameters<EmptyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // All probed backends belong to the helper, which reports them in split mode
+        #[cfg(unix)]
+        if let Some(helper) = &self.helper {
+            let params = CallToolRequestParam {
+                name: "get_server_capabilities".into(),
+                arguments: None,
+            };
+            return helper.call_tool(params, Meta::new()).await;
+        }
+        capabilities::get_server_capabilities(&self.capabilities, self.config.auto_disable_unavailable).await
+    }
+
+    #[rmcp::tool(description = "Get server usage stats: per-tool call counts, error counts and latency (mean/p50/p95/max), never-called tools, and rate limit usage")]
+    pub async fn get_server_stats(
+        &self,
+        Parameters(_params): Parameters<EmptyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let enabled_tools = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect();
+        stats::get_server_stats(&self.stats, &self.rate_limiter, enabled_tools).await
+    }
+
+    // ============================================================
+    // UNDO - 2 tools
+    // ============================================================
+
+    #[rmcp::tool(description = "List recent reversible actuator changes (volume, brightness, default devices, app volume, clipboard, player volume, workspace, trash) with their IDs and previous state")]
+    pub async fn list_recent_actions(
+        &self,
+        Parameters(params): Parameters<undo::ListActionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        undo::list_recent_actions(&self.journal, params).await
+    }
+
+    #[rmcp::tool(description = "Undo a recent actuator change by ID from list_recent_actions, restoring the previous state. Trashed files are restored from the trash")]
+    pub async fn undo_action(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<undo::UndoParams>,
+    ) -> Result<CallToolResult, McpError> {
+        undo::undo_action(self, &peer, params).await
+    }
+
+    // ============================================================
+    // SENSORS (Layer 1) - 42 tools
     // ============================================================
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get comprehensive context: datetime, user, environment, system state, battery - everything an AI needs to know about operating conditions")]
+    #[rmcp::tool(description = "Get comprehensive context: datetime, user, environment, system state, battery - everything an AI needs to know about operating conditions. Pass since=<snapshot_id> to get only what changed")]
     pub async fn get_context(
         &self,
-        Parameters(_params): Parameters<EmptyParams>,
+        Parameters(params): Parameters<snapshots::SinceParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::context::get_context().await
+        let result = sensors::context::get_context(&self.system).await;
+        self.snapshots.apply("get_context", params.since.as_deref(), result)
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get all connected peripherals: displays, USB devices, cameras, microphones, bluetooth - everything plugged in")]
+    #[rmcp::tool(description = "Get all connected peripherals: displays, USB devices, cameras, microphones, bluetooth - everything plugged in. Pass since=<snapshot_id> to get only what changed")]
     pub async fn get_peripherals(
         &self,
-        Parameters(_params): Parameters<EmptyParams>,
+        Parameters(params): Parameters<snapshots::SinceParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::peripherals::get_peripherals().await
+        let result = sensors::peripherals::get_peripherals().await;
+        self.snapshots.apply("get_peripherals", params.since.as_deref(), result)
     }
 
     #[cfg(feature = "sensors")]
@@ -133,7 +407,7 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_system_info().await
+        sensors::sysinfo::get_system_info(&self.system).await
     }
 
     #[cfg(feature = "sensors")]
@@ -142,7 +416,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<sensors::sysinfo::TopProcessesParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_top_processes(params).await
+        sensors::sysinfo::get_top_processes(&self.system, params).await
     }
 
     #[cfg(feature = "sensors")]
@@ -151,16 +425,25 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<sensors::sysinfo::FindProcessParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::find_process(params).await
+        sensors::sysinfo::find_process(&self.system, params).await
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get detailed information about a specific process by PID")]
+    #[rmcp::tool(description = "Get detailed information about a specific process by PID: status, CPU, memory, command, threads, and on Linux open files, cgroup, I/O and environment (secrets redacted)")]
     pub async fn get_process_details(
         &self,
         Parameters(params): Parameters<sensors::sysinfo::ProcessIdParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_process_details(params).await
+        sensors::sysinfo::get_process_details(&self.system, params).await
+    }
+
+    #[cfg(feature = "sensors")]
+    #[rmcp::tool(description = "Get the process tree with children nested under parents and CPU/memory aggregated per subtree, busiest first. Optionally rooted at one PID")]
+    pub async fn get_process_tree(
+        &self,
+        Parameters(params): Parameters<sensors::sysinfo::ProcessTreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        sensors::sysinfo::get_process_tree(&self.system, params).await
     }
 
     #[cfg(feature = "sensors")]
@@ -169,16 +452,25 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::list_processes().await
+        sensors::sysinfo::list_processes(&self.system).await
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get network interface I/O statistics (bytes sent/received)")]
+    #[rmcp::tool(description = "Get network interface I/O statistics (bytes sent/received since boot, and current rates)")]
     pub async fn get_network_stats(
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_network_stats().await
+        sensors::sysinfo::get_network_stats(&self.system).await
+    }
+
+    #[cfg(feature = "sensors")]
+    #[rmcp::tool(description = "Get current network throughput: bytes/sec and packets/sec per interface over a window (default 10s), plus the processes with the most TCP traffic (Linux)")]
+    pub async fn get_network_rates(
+        &self,
+        Parameters(params): Parameters<sensors::traffic::NetworkRatesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        sensors::traffic::get_network_rates(&self.system, params).await
     }
 
     #[cfg(feature = "sensors")]
@@ -187,7 +479,7 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_component_temps().await
+        sensors::sysinfo::get_component_temps(&self.system).await
     }
 
     #[cfg(feature = "sensors")]
@@ -196,7 +488,7 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::sysinfo::get_disk_info().await
+        sensors::sysinfo::get_disk_info(&self.system).await
     }
 
     #[cfg(feature = "sensors")]
@@ -259,7 +551,7 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::network::get_public_ip().await
+        sensors::network::get_public_ip(&self.config.public_ip, &self.egress).await
     }
 
     #[cfg(feature = "sensors")]
@@ -268,25 +560,64 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::network::is_online().await
+        sensors::network::is_online(&self.egress).await
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Resolve a hostname to IP addresses via DNS")]
+    #[rmcp::tool(description = "Resolve a hostname via DNS: addresses from the system resolver, or A/AAAA/MX/TXT/CNAME/SRV/NS/SOA/PTR records from a chosen nameserver with TTLs and the resolver that answered")]
     pub async fn dns_lookup(
         &self,
-        Parameters(params): Parameters<sensors::network::DnsLookupParams>,
+        Parameters(params): Parameters<sensors::dns::DnsLookupParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::network::dns_lookup(params).await
+        sensors::dns::dns_lookup(&self.egress, params).await
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get comprehensive network status: online check, public IP, location, interfaces, and traffic stats - all in one call")]
+    #[rmcp::tool(description = "Probe a TCP endpoint: DNS resolution time, per-attempt connect latency, min/avg/max/jitter and failures. No ICMP or root needed")]
+    pub async fn probe_endpoint(
+        &self,
+        Parameters(params): Parameters<sensors::probe::ProbeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        sensors::probe::probe_endpoint(&self.egress, params).await
+    }
+
+    #[cfg(feature = "sensors")]
+    #[rmcp::tool(description = "Check the service endpoints configured in tools.toml ([endpoints.<name>]) concurrently - e.g. 'is the dev database up?'")]
+    pub async fn check_endpoints(
+        &self,
+        Parameters(params): Parameters<sensors::probe::CheckEndpointsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        sensors::probe::check_endpoints(&self.egress, &self.config.endpoints, params).await
+    }
+
+    #[cfg(feature = "sensors")]
+    #[rmcp::tool(description = "Get comprehensive network status: online check, public IP, location, interfaces, and traffic stats - all in one call. Pass since=<snapshot_id> to get only what changed")]
     pub async fn get_network_info(
         &self,
-        Parameters(_params): Parameters<EmptyParams>,
+        Parameters(params): Parameters<snapshots::SinceParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let result =
+            sensors::network::get_network_info(&self.system, &self.config.public_ip, &self.egress)
+                .await;
+        self.snapshots.apply("get_network_info", params.since.as_deref(), result)
+    }
+
+    #[cfg(all(feature = "sensors", target_os = "linux"))]
+    #[rmcp::tool(description = "List listening TCP ports and bound UDP ports with the owning PID and process name, e.g. 'what is listening on port 3000?'. Filter by port, pid or protocol (tcp/udp)")]
+    pub async fn list_listening_ports(
+        &self,
+        Parameters(params): Parameters<sensors::sockets::ListeningPortsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        sensors::sockets::list_listening_ports(params).await
+    }
+
+    #[cfg(all(feature = "sensors", target_os = "linux"))]
+    #[rmcp::tool(description = "List TCP, UDP and Unix socket connections with addresses, state, PID and process name. Filter by port (local or remote), pid, state (e.g. ESTABLISHED) or protocol")]
+    pub async fn list_connections(
+        &self,
+        Parameters(params): Parameters<sensors::sockets::ConnectionsParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::network::get_network_info().await
+        sensors::sockets::list_connections(params).await
     }
 
     #[cfg(feature = "sensors")]
@@ -407,25 +738,34 @@ impl PresenceServer {
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get current weather conditions for a location")]
+    #[rmcp::tool(description = "Get the recent history of a system metric (cpu, memory, swap, load, temperature, disk, net_rx, net_tx, battery) downsampled into buckets with min/avg/max - for trends like 'has CPU been pegged for ten minutes?'")]
+    pub async fn get_metric_history(
+        &self,
+        Parameters(params): Parameters<sensors::history::MetricHistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.history.get_metric_history(params).await
+    }
+
+    #[cfg(feature = "sensors")]
+    #[rmcp::tool(description = "Get current weather for a location as JSON: conditions, temperature, wind, humidity, pressure, UV, plus today's high/low, precipitation chance, sunrise/sunset and moon phase. Units follow the weather config; includes severe-weather alerts when an alert source is configured")]
     pub async fn get_weather(
         &self,
         Parameters(params): Parameters<sensors::weather::LocationParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::weather::get_weather(&self.http_client, params).await
+        sensors::weather::get_weather(&self.config.weather, &self.egress, params).await
     }
 
     #[cfg(feature = "sensors")]
-    #[rmcp::tool(description = "Get weather forecast for upcoming days")]
+    #[rmcp::tool(description = "Get a daily weather forecast as JSON: high/low, precipitation chance and amount, UV, sunrise/sunset and moon phase per day. Pass hourly=true for hour-by-hour entries")]
     pub async fn get_forecast(
         &self,
         Parameters(params): Parameters<sensors::weather::ForecastParams>,
     ) -> Result<CallToolResult, McpError> {
-        sensors::weather::get_forecast(&self.http_client, params).await
+        sensors::weather::get_forecast(&self.config.weather, &self.egress, params).await
     }
 
     // ============================================================
-    // ACTUATORS (Layer 2) - 31 tools
+    // ACTUATORS (Layer 2) - 51 tools
     // ============================================================
 
     #[cfg(feature = "actuators")]
@@ -443,7 +783,10 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::clipboard::WriteClipboardParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::clipboard::write_clipboard(params).await
+        let prior = actuators::clipboard::current_text().map(|text| undo::Prior::Clipboard { text });
+        let result = actuators::clipboard::write_clipboard(params).await;
+        self.journal.record("write_clipboard", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -452,7 +795,10 @@ impl PresenceServer {
         &self,
         Parameters(_params): Parameters<EmptyParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::clipboard::clear_clipboard().await
+        let prior = actuators::clipboard::current_text().map(|text| undo::Prior::Clipboard { text });
+        let result = actuators::clipboard::clear_clipboard().await;
+        self.journal.record("clear_clipboard", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -470,7 +816,12 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::audio::SetVolumeParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::audio::set_volume(params).await
+        let prior = Ok(undo::Prior::SystemVolume {
+            volume: actuators::audio::current_volume(),
+        });
+        let result = actuators::audio::set_volume(params).await;
+        self.journal.record("set_volume", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -488,7 +839,12 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::audio::SetMuteParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::audio::set_mute(params).await
+        let prior = Ok(undo::Prior::SystemMute {
+            muted: actuators::audio::current_mute(),
+        });
+        let result = actuators::audio::set_mute(params).await;
+        self.journal.record("set_mute", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -506,7 +862,10 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::trash::TrashFileParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::trash::trash_file(params).await
+        let prior = undo::trashed(std::slice::from_ref(&params.path));
+        let result = actuators::trash::trash_file(params).await;
+        self.journal.record("trash_file", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -515,7 +874,10 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::trash::TrashFilesParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::trash::trash_files(params).await
+        let prior = undo::trashed(&params.paths);
+        let result = actuators::trash::trash_files(params).await;
+        self.journal.record("trash_files", prior, &result);
+        result
     }
 
     #[cfg(feature = "actuators")]
@@ -563,6 +925,24 @@ impl PresenceServer {
         actuators::open::open_with(params).await
     }
 
+    #[cfg(feature = "actuators")]
+    #[rmcp::tool(description = "Send a signal (TERM, KILL, INT, HUP, QUIT, USR1, USR2, STOP, CONT) to one of your processes. expected_name must match the process name, guarding against reused PIDs; PID 1, the server, other users' and protected processes are refused")]
+    pub async fn signal_process(
+        &self,
+        Parameters(params): Parameters<actuators::process::SignalProcessParams>,
+    ) -> Result<CallToolResult, McpError> {
+        actuators::process::signal_process(&self.config.signals, params).await
+    }
+
+    #[cfg(feature = "actuators")]
+    #[rmcp::tool(description = "Stop one of your processes: send TERM, wait grace_secs (default 5, at most 60) for it to exit, then KILL. Same safeguards as signal_process")]
+    pub async fn terminate_gracefully(
+        &self,
+        Parameters(params): Parameters<actuators::process::TerminateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        actuators::process::terminate_gracefully(&self.config.signals, params).await
+    }
+
     #[cfg(feature = "actuators")]
     #[rmcp::tool(description = "List all available monitors/displays")]
     pub async fn list_monitors(
@@ -681,7 +1061,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::HostParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::list_models(params).await
+        actuators::ollama::list_models(&self.egress, params).await
     }
 
     #[cfg(feature = "actuators")]
@@ -690,7 +1070,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::HostParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::list_running(params).await
+        actuators::ollama::list_running(&self.egress, params).await
     }
 
     #[cfg(feature = "actuators")]
@@ -699,7 +1079,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::ModelParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::show_model(params).await
+        actuators::ollama::show_model(&self.egress, params).await
     }
 
     #[cfg(feature = "actuators")]
@@ -708,7 +1088,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::ModelParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::pull_model(params).await
+        actuators::ollama::pull_model(&self.egress, params).await
     }
 
     #[cfg(feature = "actuators")]
@@ -717,7 +1097,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::ModelParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::delete_model(params).await
+        actuators::ollama::delete_model(&self.egress, params).await
     }
 
     #[cfg(feature = "actuators")]
@@ -726,7 +1106,7 @@ impl PresenceServer {
         &self,
         Parameters(params): Parameters<actuators::ollama::HostParams>,
     ) -> Result<CallToolResult, McpError> {
-        actuators::ollama::get_ollama_status(params).await
+        actuators::ollama::get_ollama_status(&self.egress, params).await
     }
 
     // --- printers (10 tools) ---
@@ -858,7 +1238,7 @@ impl PresenceServer {
     }
 
     // ============================================================
-    // LINUX (Layer 3) - 83 tools
+    // LINUX (Layer 3) - 86 tools
     // ============================================================
 
     // --- i3 (15 tools) ---
@@ -877,7 +1257,12 @@ impl PresenceServer {
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Switch to a specific workspace by number or name")]
     pub async fn switch_workspace(&self, Parameters(params): Parameters<linux::i3::SwitchWorkspaceParams>) -> Result<CallToolResult, McpError> {
-        linux::i3::switch_workspace(params).await
+        let prior = linux::i3::focused_workspace()
+            .await
+            .map(|name| undo::Prior::Workspace { name });
+        let result = linux::i3::switch_workspace(params).await;
+        self.journal.record("switch_workspace", prior, &result);
+        result
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
@@ -953,9 +1338,10 @@ impl PresenceServer {
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
-    #[rmcp::tool(description = "Get i3 workspace status: all workspaces, focused window, outputs, scratchpad - spatial orientation in one call")]
-    pub async fn get_workspace_status(&self, Parameters(_params): Parameters<EmptyParams>) -> Result<CallToolResult, McpError> {
-        linux::i3::get_workspace_status().await
+    #[rmcp::tool(description = "Get i3 workspace status: all workspaces, focused window, outputs, scratchpad - spatial orientation in one call. Pass since=<snapshot_id> to get only what changed")]
+    pub async fn get_workspace_status(&self, Parameters(params): Parameters<snapshots::SinceParams>) -> Result<CallToolResult, McpError> {
+        let result = linux::i3::get_workspace_status().await;
+        self.snapshots.apply("get_workspace_status", params.since.as_deref(), result)
     }
 
     // --- xdotool (12 tools) ---
@@ -1089,7 +1475,11 @@ impl PresenceServer {
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Set player volume (0.0 to 1.0)")]
     pub async fn set_player_volume(&self, Parameters(params): Parameters<linux::mpris::SetVolumeParams>) -> Result<CallToolResult, McpError> {
-        linux::mpris::set_player_volume(params).await
+        let prior = linux::mpris::current_player_volume(params.player.as_deref())
+            .map(|(player, volume)| undo::Prior::PlayerVolume { player, volume });
+        let result = linux::mpris::set_player_volume(params).await;
+        self.journal.record("set_player_volume", prior, &result);
+        result
     }
 
     // --- systemd (7 tools) ---
@@ -1151,7 +1541,12 @@ impl PresenceServer {
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Set brightness level (0-100%) for a device")]
     pub async fn set_brightness(&self, Parameters(params): Parameters<linux::brightness::SetBrightnessParams>) -> Result<CallToolResult, McpError> {
-        linux::brightness::set_brightness(params).await
+        let prior = linux::brightness::current_brightness(params.device.as_deref())
+            .await
+            .map(|(device, level)| undo::Prior::Brightness { device, level });
+        let result = linux::brightness::set_brightness(params).await;
+        self.journal.record("set_brightness", prior, &result);
+        result
     }
 
     // --- bluer (9 tools) ---
@@ -1315,9 +1710,10 @@ impl PresenceServer {
 
     // --- audio_status (1 tool) ---
     #[cfg(all(feature = "linux", target_os = "linux"))]
-    #[rmcp::tool(description = "Get comprehensive audio status: volume, mute, default devices, now playing, apps using audio - all in one call")]
-    pub async fn get_audio_status(&self, Parameters(_params): Parameters<EmptyParams>) -> Result<CallToolResult, McpError> {
-        linux::audio_status::get_audio_status().await
+    #[rmcp::tool(description = "Get comprehensive audio status: volume, mute, default devices, now playing, apps using audio - all in one call. Pass since=<snapshot_id> to get only what changed")]
+    pub async fn get_audio_status(&self, Parameters(params): Parameters<snapshots::SinceParams>) -> Result<CallToolResult, McpError> {
+        let result = linux::audio_status::get_audio_status().await;
+        self.snapshots.apply("get_audio_status", params.since.as_deref(), result)
     }
 
     // --- pulseaudio (11 tools) ---
@@ -1360,25 +1756,51 @@ impl PresenceServer {
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Set the default audio output device by name")]
     pub async fn set_default_sink(&self, Parameters(params): Parameters<linux::pulseaudio::NameParams>) -> Result<CallToolResult, McpError> {
-        linux::pulseaudio::set_default_sink(params).await
+        let prior = linux::pulseaudio::default_sink_name()
+            .await
+            .map(|name| undo::Prior::DefaultSink { name });
+        let result = linux::pulseaudio::set_default_sink(params).await;
+        self.journal.record("set_default_sink", prior, &result);
+        result
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Set the default audio input device by name")]
     pub async fn set_default_source(&self, Parameters(params): Parameters<linux::pulseaudio::NameParams>) -> Result<CallToolResult, McpError> {
-        linux::pulseaudio::set_default_source(params).await
+        let prior = linux::pulseaudio::default_source_name()
+            .await
+            .map(|name| undo::Prior::DefaultSource { name });
+        let result = linux::pulseaudio::set_default_source(params).await;
+        self.journal.record("set_default_source", prior, &result);
+        result
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Adjust volume for a specific application (sink input). Use positive delta to increase, negative to decrease.")]
     pub async fn set_sink_input_volume(&self, Parameters(params): Parameters<linux::pulseaudio::VolumeParams>) -> Result<CallToolResult, McpError> {
-        linux::pulseaudio::set_sink_input_volume(params).await
+        let prior = linux::pulseaudio::sink_input_state(params.index)
+            .await
+            .map(|(volumes, _)| undo::Prior::AppVolume {
+                index: params.index,
+                volumes,
+            });
+        let result = linux::pulseaudio::set_sink_input_volume(params).await;
+        self.journal.record("set_sink_input_volume", prior, &result);
+        result
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
     #[rmcp::tool(description = "Mute or unmute a specific application (sink input)")]
     pub async fn set_sink_input_mute(&self, Parameters(params): Parameters<linux::pulseaudio::MuteParams>) -> Result<CallToolResult, McpError> {
-        linux::pulseaudio::set_sink_input_mute(params).await
+        let prior = linux::pulseaudio::sink_input_state(params.index)
+            .await
+            .map(|(_, muted)| undo::Prior::AppMute {
+                index: params.index,
+                muted,
+            });
+        let result = linux::pulseaudio::set_sink_input_mute(params).await;
+        self.journal.record("set_sink_input_mute", prior, &result);
+        result
     }
 
     #[cfg(all(feature = "linux", target_os = "linux"))]
@@ -1388,7 +1810,6 @@ impl PresenceServer {
     }
 }
 
-#[rmcp::tool_handler]
 impl ServerHandler for PresenceServer {
     fn get_info(&self) -> ServerInfo {
         let mut description = String::from("rmcp-presence: Unified AI environmental awareness.\n");
@@ -1409,24 +1830,138 @@ impl ServerHandler for PresenceServer {
             instructions: Some(description),
         }
     }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let tool_name = request.name.to_string();
+        let categories = self.config.categories_of(&tool_name).join(",");
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %tool_name,
+            category = (!categories.is_empty()).then_some(categories.as_str()),
+            outcome = tracing::field::Empty,
+            traceparent = context.meta.get("traceparent").and_then(|v| v.as_str()),
+        );
+
+        let started = std::time::Instant::now();
+        let result = self
+            .dispatch(&tool_name, request, context)
+            .instrument(span.clone())
+            .await;
+        let success = matches!(&result, Ok(r) if r.is_error != Some(true));
+        // Client-chosen names would give the metrics an unbounded set of labels
+        let stats_name = if self.serves_tool(&tool_name) { &tool_name } else { "unknown" };
+        self.stats.record(stats_name, started.elapsed(), success);
+        span.record(
+            "outcome",
+            match &result {
+                Ok(_) if success => "ok",
+                Ok(_) => "error",
+                Err(e) if e.code == ErrorCode::INVALID_REQUEST => "refused",
+                Err(_) => "error",
+            },
+        );
+
+        // Nothing leaves the server without passing the redactor
+        let result = match result {
+            Ok(mut result) => {
+                for redaction in self.redactor.redact_result(&mut result) {
+                    audit::redaction(&tool_name, &redaction);
+                }
+                Ok(result)
+            }
+            Err(mut error) => {
+                let mut message = error.message.to_string();
+                for redaction in self.redactor.redact_text(&mut message) {
+                    audit::redaction(&tool_name, &redaction);
+                }
+                error.message = message.into();
+                Err(error)
+            }
+        };
+
+        audit::tool_call(&tool_name, result.is_ok());
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        #[allow(unused_mut)]
+        let mut tools = self.tool_router.list_all();
+
+        #[cfg(unix)]
+        if let Some(helper) = &self.helper {
+            // Both sides serve get_server_capabilities; list it once
+            let helper_tools = helper.list_tools().await;
+            tools.extend(
+                helper_tools
+                    .into_iter()
+                    .filter(|tool| !self.tool_router.has_route(&tool.name)),
+            );
+        }
+
+        Ok(ListToolsResult {
+            tools,
+            meta: None,
+            next_cursor: None,
+        })
+    }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
         Some(Commands::Config) => {
             run_config_command()?;
         }
+        Some(Commands::Doctor) => {
+            runtime()?.block_on(run_doctor_command());
+        }
+        #[cfg(unix)]
+        Some(Commands::Helper { socket }) => {
+            run_helper(socket)?;
+        }
         None => {
-            run_server().await?;
+            run_server()?;
         }
     }
 
     Ok(())
 }
 
+/// The async runtime is built by hand so the sandbox can be applied first
+fn runtime() -> std::io::Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+}
+
+/// Apply the `[sandbox]` config. Runs before the runtime starts so every thread inherits it.
+fn enter_sandbox(config: &config::Config, role: Role) -> anyhow::Result<()> {
+    if !config.sandbox.enabled {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    sandbox::apply(config, role)
+        .map_err(|e| anyhow::anyhow!("Sandbox setup failed: {}", e))?;
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = role;
+        tracing::warn!("Sandboxing is only supported on Linux, running unrestricted");
+    }
+
+    Ok(())
+}
+
 /// Open config file in user's editor
 fn run_config_command() -> anyhow::Result<()> {
     let config_path = config::Config::path()
@@ -1464,19 +1999,94 @@ fn run_config_command() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Run the MCP server
-async fn run_server() -> anyhow::Result<()> {
-    tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
-        .init();
+/// Probe backends and print what is available
+async fn run_doctor_command() {
+    match config::Config::path() {
+        Some(path) if path.exists() => println!("Config: {}\n", path.display()),
+        Some(path) => println!("Config: {} (not found, using defaults)\n", path.display()),
+        None => println!("Config: could not determine config directory\n"),
+    }
+
+    let capabilities = capabilities::Capabilities::probe().await;
+    print!("{}", capabilities.report());
+}
 
+/// Run the MCP server
+fn run_server() -> anyhow::Result<()> {
+    init_logging();
     tracing::info!("Starting rmcp-presence server");
 
-    let server = PresenceServer::new();
-    let service = server.serve(rmcp::transport::stdio()).await?;
-    service.waiting().await?;
+    let config = config::Config::load()?;
+    let role = if config.split.enabled {
+        if cfg!(unix) {
+            Role::Front
+        } else {
+            tracing::warn!("Split mode needs Unix sockets, running all tools in-process");
+            Role::Standalone
+        }
+    } else {
+        Role::Standalone
+    };
 
-    tracing::info!("rmcp-presence server stopped");
-    Ok(())
+    enter_sandbox(&config, role)?;
+
+    runtime()?.block_on(async {
+        #[cfg(feature = "otel")]
+        telemetry::start(&config.otel);
+
+        let metrics_listen = config.metrics.listen.clone();
+        let server = PresenceServer::new(config, role).await;
+        if let Some(addr) = metrics_listen {
+            tokio::spawn(stats::serve_metrics(addr, server.stats.clone()));
+        }
+
+        let service = server.serve(rmcp::transport::stdio()).await?;
+        service.waiting().await?;
+
+        tracing::info!("rmcp-presence server stopped");
+        Ok(())
+    })
+}
+
+/// Run the split-mode helper serving actuator and Linux tools
+#[cfg(unix)]
+fn run_helper(socket: Option<std::path::PathBuf>) -> anyhow::Result<()> {
+    init_logging();
+    tracing::info!("Starting rmcp-presence helper");
+
+    let mut config = config::Config::load()?;
+    if socket.is_some() {
+        config.split.socket = socket;
+    }
+
+    enter_sandbox(&config, Role::Helper)?;
+
+    runtime()?.block_on(async {
+        #[cfg(feature = "otel")]
+        telemetry::start(&config.otel);
+
+        let split = config.split.clone();
+        let server = PresenceServer::new(config, Role::Helper).await;
+        split::run_helper(server, &split).await
+    })
+}
+
+/// Log to stderr; stdout carries the MCP protocol
+fn init_logging() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(filter),
+    );
+
+    // Traces cover our own spans regardless of the log level
+    #[cfg(feature = "otel")]
+    let registry = registry.with(
+        telemetry::layer().with_filter(tracing_subscriber::filter::filter_fn(|metadata| {
+            metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+        })),
+    );
+
+    registry.init();
 }
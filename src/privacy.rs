@@ -0,0 +1,339 @@
+//! Privacy safeguards for camera, microphone and screen capture
+//!
+//! Tools in the `sensitive` category announce themselves with a desktop notification,
+//! can require the user's consent once per session, and are limited by an hourly quota.
+//! The notification goes out before the capture starts. Failed captures don't count
+//! against the quota, and every outcome is audited.
+
+use rmcp::{service::Peer, ErrorData as McpError, RoleServer};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::audit;
+use crate::config::PrivacyConfig;
+use crate::shared::policy_error;
+
+/// Category of tools guarded by this module
+pub const SENSITIVE: &str = "sensitive";
+
+/// How long the user has to answer a consent prompt
+const CONSENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const QUOTA_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Consent form shown to the user through the MCP client
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CaptureConsent {
+    #[schemars(description = "Allow sensitive captures for the rest of this session")]
+    pub allow: bool,
+}
+
+rmcp::elicit_safe!(CaptureConsent);
+
+/// The quota slot of a sensitive capture, settled when dropped: a capture that
+/// succeeded keeps its slot and a failed one gives it back. If the call is dropped
+/// midway (timeout, cancelled request) the slot is kept, since the device may already
+/// have been used. Every outcome is audited.
+#[derive(Debug)]
+pub struct Capture {
+    privacy: Arc<Privacy>,
+    tool_name: String,
+    at: Instant,
+    succeeded: Option<bool>,
+}
+
+impl Capture {
+    /// Record how the capture ended
+    pub fn finish(mut self, succeeded: bool) {
+        self.succeeded = Some(succeeded);
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let outcome = match self.succeeded {
+            Some(true) => "ok",
+            Some(false) => {
+                self.privacy.refund(self.at);
+                "failed"
+            }
+            None => "cancelled",
+        };
+        audit::sensitive_capture(&self.tool_name, device_of(&self.tool_name), outcome);
+    }
+}
+
+/// Privacy state of one MCP session. Consent is per session; the quota is shared by
+/// every session of the process.
+#[derive(Debug)]
+pub struct Privacy {
+    config: PrivacyConfig,
+    consent_granted: AtomicBool,
+    captures: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl Privacy {
+    pub fn new(config: &PrivacyConfig) -> Self {
+        Self {
+            config: config.clone(),
+            consent_granted: AtomicBool::new(false),
+            captures: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// State for a new session: no consent yet, same quota
+    pub fn for_session(&self) -> Arc<Self> {
+        Arc::new(Self {
+            config: self.config.clone(),
+            consent_granted: AtomicBool::new(false),
+            captures: self.captures.clone(),
+        })
+    }
+
+    /// Run consent, reserve a quota slot and notify before a sensitive capture, so
+    /// the user knows while it happens. An error means the capture must not happen.
+    pub async fn before_capture(
+        self: &Arc<Self>,
+        tool_name: &str,
+        peer: &Peer<RoleServer>,
+    ) -> Result<Capture, McpError> {
+        if self.config.require_consent && !self.consent_granted.load(Ordering::SeqCst) {
+            self.request_consent(tool_name, peer).await?;
+        }
+
+        let at = Instant::now();
+        self.record_capture(tool_name, at)?;
+        if self.config.notify {
+            notify(tool_name);
+        }
+        Ok(Capture {
+            privacy: self.clone(),
+            tool_name: tool_name.to_string(),
+            at,
+            succeeded: None,
+        })
+    }
+
+    /// Give back the quota slot taken at `at`
+    fn refund(&self, at: Instant) {
+        let mut captures = self.captures.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = captures.iter().position(|t| *t == at) {
+            captures.remove(index);
+        }
+    }
+
+    async fn request_consent(
+        &self,
+        tool_name: &str,
+        peer: &Peer<RoleServer>,
+    ) -> Result<(), McpError> {
+        let denied = |reason: &str, msg: String| {
+            audit::consent(tool_name, false, reason);
+            Err(policy_error(
+                msg,
+                serde_json::json!({ "policy": "privacy", "reason": reason, "tool": tool_name }),
+            ))
+        };
+
+        if !peer.supports_elicitation() {
+            return denied(
+                "consent_unavailable",
+                format!(
+                    "{} requires user consent, but the client cannot prompt the user",
+                    tool_name
+                ),
+            );
+        }
+
+        let message = format!(
+            "The assistant wants to use your {} ({}). Allow sensitive captures for this session?",
+            device_of(tool_name),
+            tool_name
+        );
+
+        match peer
+            .elicit_with_timeout::<CaptureConsent>(message, Some(CONSENT_TIMEOUT))
+            .await
+        {
+            Ok(Some(CaptureConsent { allow: true })) => {
+                self.consent_granted.store(true, Ordering::SeqCst);
+                audit::consent(tool_name, true, "granted");
+                Ok(())
+            }
+            Ok(_) | Err(rmcp::service::ElicitationError::UserDeclined) => denied(
+                "consent_declined",
+                format!("User declined consent for {}", tool_name),
+            ),
+            Err(e) => denied(
+                "consent_failed",
+                format!("Could not obtain consent for {}: {}", tool_name, e),
+            ),
+        }
+    }
+
+    /// Count a capture against the hourly quota, refusing it once the quota is spent
+    fn record_capture(&self, tool_name: &str, now: Instant) -> Result<(), McpError> {
+        let mut captures = self.captures.lock().unwrap_or_else(|e| e.into_inner());
+        while captures
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= QUOTA_WINDOW)
+        {
+            captures.pop_front();
+        }
+
+        if let Some(max) = self.config.max_captures_per_hour {
+            if captures.len() >= max as usize {
+                let retry_after = captures
+                    .front()
+                    .map(|t| QUOTA_WINDOW.saturating_sub(now.duration_since(*t)))
+                    .unwrap_or(QUOTA_WINDOW);
+                audit::quota_exceeded(tool_name, SENSITIVE);
+                return Err(policy_error(
+                    format!(
+                        "Sensitive capture quota exceeded ({} per hour), retry in {}s",
+                        max,
+                        retry_after.as_secs()
+                    ),
+                    serde_json::json!({
+                        "policy": "privacy",
+                        "reason": "quota_exceeded",
+                        "tool": tool_name,
+                        "limit_per_hour": max,
+                        "retry_after_secs": retry_after.as_secs(),
+                    }),
+                ));
+            }
+        }
+
+        captures.push_back(now);
+        Ok(())
+    }
+}
+
+/// What a sensitive tool accesses, in words a user recognizes
+fn device_of(tool_name: &str) -> &'static str {
+    match tool_name {
+        "capture_camera" => "camera",
+        "capture_audio" | "get_input_level" => "microphone",
+        "capture_monitor" | "capture_window" | "capture_region" => "screen",
+        _ => "sensitive data",
+    }
+}
+
+/// Show a desktop notification without delaying the capture
+fn notify(tool_name: &str) {
+    let summary = format!("Assistant is accessing your {}", device_of(tool_name));
+    let body = format!("Tool: {}", tool_name);
+
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    tokio::spawn(async move {
+        if let Err(e) = send_notification(&summary, &body).await {
+            tracing::warn!("Failed to show privacy notification: {}", e);
+        }
+    });
+
+    #[cfg(not(all(feature = "linux", target_os = "linux")))]
+    tracing::warn!("{} ({})", summary, body);
+}
+
+#[cfg(all(feature = "linux", target_os = "linux"))]
+async fn send_notification(summary: &str, body: &str) -> zbus::Result<()> {
+    use std::collections::HashMap;
+    use zbus::zvariant::Value;
+
+    let conn = zbus::Connection::session().await?;
+    let proxy = zbus::Proxy::new(
+        &conn,
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+    )
+    .await?;
+
+    let actions: Vec<&str> = Vec::new();
+    let hints: HashMap<&str, Value> = HashMap::new();
+    let _id: u32 = proxy
+        .call(
+            "Notify",
+            &(
+                "rmcp-presence",
+                0u32,
+                "dialog-warning",
+                summary,
+                body,
+                actions,
+                hints,
+                5000i32,
+            ),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_quota() {
+        let privacy = Privacy::new(&PrivacyConfig {
+            max_captures_per_hour: Some(2),
+            ..PrivacyConfig::default()
+        });
+        let start = Instant::now();
+
+        assert!(privacy.record_capture("capture_camera", start).is_ok());
+        assert!(privacy.record_capture("capture_camera", start).is_ok());
+        let err = privacy.record_capture("capture_camera", start).unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], "quota_exceeded");
+
+        // Oldest captures fall out of the window
+        assert!(privacy
+            .record_capture("capture_camera", start + QUOTA_WINDOW)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_failed_capture_refunded() {
+        let privacy = Privacy::new(&PrivacyConfig {
+            max_captures_per_hour: Some(1),
+            ..PrivacyConfig::default()
+        });
+        let privacy = Arc::new(privacy);
+        let capture = |at| Capture {
+            privacy: privacy.clone(),
+            tool_name: "capture_camera".to_string(),
+            at,
+            succeeded: None,
+        };
+        let at = Instant::now();
+        privacy.record_capture("capture_camera", at).unwrap();
+        assert!(privacy.record_capture("capture_camera", at).is_err());
+
+        capture(at).finish(false);
+        assert!(privacy.record_capture("capture_camera", at).is_ok());
+
+        // A call dropped midway keeps its slot
+        drop(capture(at));
+        assert!(privacy.record_capture("capture_camera", at).is_err());
+    }
+
+    #[test]
+    fn test_session_consent() {
+        let privacy = Privacy::new(&PrivacyConfig {
+            max_captures_per_hour: Some(1),
+            ..PrivacyConfig::default()
+        });
+        privacy.consent_granted.store(true, Ordering::SeqCst);
+        let session = privacy.for_session();
+        assert!(!session.consent_granted.load(Ordering::SeqCst));
+
+        let at = Instant::now();
+        privacy.record_capture("capture_camera", at).unwrap();
+        assert!(session.record_capture("capture_camera", at).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! Rate limits and daily quotas per tool and per category
+//!
+//! Each `[rate_limits.<name>]` entry is a token bucket (`per_minute`, `burst`) plus
+//! an optional daily quota (`per_day`, reset at midnight UTC). `<name>` is either a
+//! tool or a category; a category entry is one shared budget for all its tools.
+
+use rmcp::ErrorData as McpError;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::audit;
+use crate::config::{Config, RateLimitConfig};
+use crate::shared::policy_error;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug)]
+struct Usage {
+    tokens: f64,
+    last_refill: Instant,
+    day: u64,
+    used_today: u32,
+    total: u64,
+    refused: u64,
+}
+
+/// Why a call was refused, and when to try again
+struct Refusal {
+    limit: String,
+    reason: &'static str,
+    message: String,
+    retry_after_secs: u64,
+}
+
+/// Shared limiter state for one server process
+#[derive(Debug)]
+pub struct RateLimiter {
+    limits: BTreeMap<String, RateLimitConfig>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            limits: config.rate_limits.clone(),
+            usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limits that apply to a tool: its own entry plus those of its categories
+    fn applicable(&self, config: &Config, tool_name: &str) -> Vec<(&str, &RateLimitConfig)> {
+        self.limits
+            .iter()
+            .filter(|(name, _)| *name == tool_name || config.in_category(tool_name, name))
+            .map(|(name, limit)| (name.as_str(), limit))
+            .collect()
+    }
+
+    /// Take one call from every applicable budget, or refuse without consuming any
+    pub fn acquire(&self, config: &Config, tool_name: &str) -> Result<(), McpError> {
+        let limits = self.applicable(config, tool_name);
+        if limits.is_empty() {
+            return Ok(());
+        }
+
+        let unix_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        match self.acquire_at(&limits, Instant::now(), unix_secs) {
+            Ok(()) => Ok(()),
+            Err(refusal) => {
+                audit::rate_limited(tool_name, &refusal.limit, refusal.reason);
+                Err(policy_error(
+                    format!(
+                        "Rate limited: {}, retry after {} seconds",
+                        refusal.message, refusal.retry_after_secs
+                    ),
+                    serde_json::json!({
+                        "policy": "rate_limit",
+                        "reason": refusal.reason,
+                        "tool": tool_name,
+                        "limit": refusal.limit,
+                        "retry_after_secs": refusal.retry_after_secs,
+                    }),
+                ))
+            }
+        }
+    }
+
+    fn acquire_at(
+        &self,
+        limits: &[(&str, &RateLimitConfig)],
+        now: Instant,
+        unix_secs: u64,
+    ) -> Result<(), Refusal> {
+        let day = unix_secs / SECS_PER_DAY;
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Refill and check everything first, so a refusal consumes nothing
+        let mut refusal: Option<Refusal> = None;
+        for (name, limit) in limits {
+            let entry = usage.entry(name.to_string()).or_insert_with(|| Usage {
+                tokens: limit.capacity().unwrap_or_default() as f64,
+                last_refill: now,
+                day,
+                used_today: 0,
+                total: 0,
+                refused: 0,
+            });
+
+            if entry.day != day {
+                entry.day = day;
+                entry.used_today = 0;
+            }
+
+            if let (Some(per_minute), Some(capacity)) = (limit.per_minute, limit.capacity()) {
+                let elapsed = now.duration_since(entry.last_refill).as_secs_f64();
+                entry.tokens =
+                    (entry.tokens + elapsed * per_minute as f64 / 60.0).min(capacity as f64);
+                entry.last_refill = now;
+
+                if entry.tokens < 1.0 {
+                    let wait = ((1.0 - entry.tokens) * 60.0 / per_minute.max(1) as f64).ceil();
+                    refusal = worst(
+                        refusal,
+                        Refusal {
+                            limit: name.to_string(),
+                            reason: "rate_limited",
+                            message: format!("{} allows {} calls per minute", name, per_minute),
+                            retry_after_secs: (wait as u64).max(1),
+                        },
+                    );
+                }
+            }
+
+            if let Some(per_day) = limit.per_day {
+                if entry.used_today >= per_day {
+                    refusal = worst(
+                        refusal,
+                        Refusal {
+                            limit: name.to_string(),
+                            reason: "daily_quota_exceeded",
+                            message: format!("{} allows {} calls per day", name, per_day),
+                            retry_after_secs: SECS_PER_DAY - unix_secs % SECS_PER_DAY,
+                        },
+                    );
+                }
+            }
+        }
+
+        if let Some(refusal) = refusal {
+            for (name, _) in limits {
+                if let Some(entry) = usage.get_mut(*name) {
+                    entry.refused += 1;
+                }
+            }
+            return Err(refusal);
+        }
+
+        for (name, limit) in limits {
+            if let Some(entry) = usage.get_mut(*name) {
+                if limit.per_minute.is_some() {
+                    entry.tokens -= 1.0;
+                }
+                entry.used_today += 1;
+                entry.total += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Current usage of every configured limit
+    pub fn snapshot(&self) -> Vec<LimitStats> {
+        let now = Instant::now();
+        let usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let today = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() / SECS_PER_DAY)
+            .unwrap_or_default();
+
+        self.limits
+            .iter()
+            .map(|(name, limit)| {
+                let entry = usage.get(name);
+                let tokens = match (entry, limit.per_minute, limit.capacity()) {
+                    (Some(u), Some(per_minute), Some(capacity)) => {
+                        let elapsed = now.duration_since(u.last_refill).as_secs_f64();
+                        Some(
+                            (u.tokens + elapsed * per_minute as f64 / 60.0)
+                                .min(capacity as f64)
+                                .floor() as u32,
+                        )
+                    }
+                    (None, Some(_), capacity) => capacity,
+                    _ => None,
+                };
+
+                LimitStats {
+                    name: name.clone(),
+                    per_minute: limit.per_minute,
+                    burst: limit.capacity(),
+                    per_day: limit.per_day,
+                    available_now: tokens,
+                    used_today: entry
+                        .filter(|u| u.day == today)
+                        .map(|u| u.used_today)
+                        .unwrap_or(0),
+                    total_calls: entry.map(|u| u.total).unwrap_or(0),
+                    refused: entry.map(|u| u.refused).unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Keep the refusal with the longest wait
+fn worst(current: Option<Refusal>, candidate: Refusal) -> Option<Refusal> {
+    match current {
+        Some(current) if current.retry_after_secs >= candidate.retry_after_secs => Some(current),
+        _ => Some(candidate),
+    }
+}
+
+/// Usage of one configured limit, as reported by `get_server_stats`
+#[derive(Debug, Serialize)]
+pub struct LimitStats {
+    pub name: String,
+    pub per_minute: Option<u32>,
+    pub burst: Option<u32>,
+    pub per_day: Option<u32>,
+    pub available_now: Option<u32>,
+    pub used_today: u32,
+    pub total_calls: u64,
+    pub refused: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn limiter(name: &str, limit: RateLimitConfig) -> RateLimiter {
+        let mut config = Config::default();
+        config.rate_limits.insert(name.to_string(), limit);
+        RateLimiter::new(&config)
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimitConfig {
+            per_minute: Some(60),
+            burst: Some(2),
+            per_day: None,
+        };
+        let limiter = limiter("click_at", limit.clone());
+        let limits = [("click_at", &limit)];
+        let start = Instant::now();
+
+        assert!(limiter.acquire_at(&limits, start, 0).is_ok());
+        assert!(limiter.acquire_at(&limits, start, 0).is_ok());
+        let refusal = limiter.acquire_at(&limits, start, 0).err().unwrap();
+        assert_eq!(refusal.reason, "rate_limited");
+        assert_eq!(refusal.retry_after_secs, 1);
+
+        // One token per second at 60/minute
+        assert!(limiter
+            .acquire_at(&limits, start + Duration::from_secs(1), 0)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_daily_quota() {
+        let limit = RateLimitConfig {
+            per_minute: None,
+            burst: None,
+            per_day: Some(1),
+        };
+        let limiter = limiter("pull_model", limit.clone());
+        let limits = [("pull_model", &limit)];
+        let now = Instant::now();
+
+        assert!(limiter.acquire_at(&limits, now, 100).is_ok());
+        let refusal = limiter.acquire_at(&limits, now, 100).err().unwrap();
+        assert_eq!(refusal.reason, "daily_quota_exceeded");
+        assert_eq!(refusal.retry_after_secs, SECS_PER_DAY - 100);
+
+        // New UTC day
+        assert!(limiter.acquire_at(&limits, now, SECS_PER_DAY).is_ok());
+    }
+
+    #[test]
+    fn test_refusal_consumes_nothing() {
+        let open = RateLimitConfig {
+            per_minute: None,
+            burst: None,
+            per_day: Some(10),
+        };
+        let closed = RateLimitConfig {
+            per_minute: None,
+            burst: None,
+            per_day: Some(0),
+        };
+        let limiter = limiter("input", open.clone());
+        let now = Instant::now();
+
+        assert!(limiter
+            .acquire_at(&[("input", &open), ("type_text", &closed)], now, 0)
+            .is_err());
+        assert!(limiter.acquire_at(&[("input", &open)], now, 0).is_ok());
+        assert_eq!(limiter.snapshot()[0].total_calls, 1);
+    }
+}
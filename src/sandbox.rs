@@ -0,0 +1,646 @@
+//! Landlock and seccomp self-sandboxing (Linux)
+//!
+//! When `[sandbox] enabled = true`, the server restricts itself before the async
+//! runtime starts, so every thread and child process inherits the restrictions:
+//!
+//! - Landlock: the whole filesystem is read-only, except the config and state
+//!   directories, the trash, the helper socket directory, `/dev/shm` and any
+//!   configured `write_roots`. The null, sound and camera devices can be written but
+//!   not created. Only the helper binaries of enabled tools (`xdotool`,
+//!   `journalctl`, `breakrs`), their interpreters and `allow_exec` entries can be
+//!   executed. With `open_path = true`, `xdg-open` and what it calls (including
+//!   `/bin/sh`) are added.
+//! - seccomp: `execveat` (which can run anonymous memfd files that Landlock never
+//!   sees), `ptrace`, cross-process memory access, module loading, `bpf`, `mount`
+//!   and similar escape hatches fail with `EPERM`.
+//!
+//! The dynamic loader must stay executable for helper binaries to start, so a
+//! compromised process could still use it to load other programs it can read.
+
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::capabilities::Backend;
+use crate::config::{Config, SandboxConfig};
+use crate::Role;
+
+// === Landlock ABI ===
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+/// Every right known to Landlock ABI 1
+const ACCESS_FS_ABI_1: u64 = (ACCESS_FS_MAKE_SYM << 1) - 1;
+/// ABI 2
+const ACCESS_FS_REFER: u64 = 1 << 13;
+/// ABI 3
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+/// ABI 5
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+const READ: u64 = ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+const WRITE: u64 = ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_REMOVE_DIR
+    | ACCESS_FS_REMOVE_FILE
+    | ACCESS_FS_MAKE_CHAR
+    | ACCESS_FS_MAKE_DIR
+    | ACCESS_FS_MAKE_REG
+    | ACCESS_FS_MAKE_SOCK
+    | ACCESS_FS_MAKE_FIFO
+    | ACCESS_FS_MAKE_BLOCK
+    | ACCESS_FS_MAKE_SYM
+    | ACCESS_FS_REFER
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+
+/// Rights that may be granted on a regular file (the rest only apply to directories)
+const FILE_RIGHTS: u64 = ACCESS_FS_EXECUTE
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_READ_FILE
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+// === seccomp ===
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// Syscalls that fail with EPERM inside the sandbox
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_execveat,
+    libc::SYS_ptrace,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_kexec_load,
+    libc::SYS_kexec_file_load,
+    libc::SYS_init_module,
+    libc::SYS_finit_module,
+    libc::SYS_delete_module,
+    libc::SYS_bpf,
+    libc::SYS_userfaultfd,
+    libc::SYS_mount,
+    libc::SYS_umount2,
+    libc::SYS_pivot_root,
+    libc::SYS_swapon,
+    libc::SYS_swapoff,
+];
+
+/// What `open_path` runs: the desktop's opener, and the tools `xdg-open` calls to find it.
+/// Only allowed with `[sandbox] open_path = true`, since `xdg-open` is a shell script.
+const OPENERS: &[&str] = &[
+    "xdg-open",
+    "xdg-mime",
+    "gio",
+    "kde-open",
+    "kde-open5",
+    "gnome-open",
+    "exo-open",
+    "basename",
+    "cut",
+    "dirname",
+    "file",
+    "grep",
+    "head",
+    "readlink",
+    "sed",
+    "tr",
+    "which",
+];
+
+/// Programs run by tools, and the tools that need them
+fn helper_programs(sandbox: &SandboxConfig) -> Vec<(&'static str, &'static [&'static str])> {
+    let mut programs = vec![
+        ("xdotool", Backend::Xdotool.tools()),
+        ("breakrs", Backend::Breakrs.tools()),
+        ("journalctl", &["get_unit_logs"][..]),
+    ];
+    if sandbox.open_path {
+        programs.extend(OPENERS.iter().map(|&name| (name, &["open_path"][..])));
+    }
+    programs
+}
+
+/// Device access: read, write and ioctl on existing nodes, but nothing is created
+const DEVICE: u64 = READ | ACCESS_FS_WRITE_FILE | ACCESS_FS_IOCTL_DEV;
+
+/// The parts of `/dev` tools write to: the null device for child processes, sound
+/// cards (audio, microphone), cameras and shared memory for the audio libraries.
+/// The rest of `/dev` stays read-only.
+fn device_rules() -> Vec<(PathBuf, u64)> {
+    let mut rules = vec![
+        (PathBuf::from("/dev/null"), DEVICE),
+        (PathBuf::from("/dev/snd"), DEVICE),
+        (PathBuf::from("/dev/shm"), READ | WRITE),
+    ];
+    // Cameras plugged in later stay out of reach until restart
+    if let Ok(entries) = std::fs::read_dir("/dev") {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with("video") {
+                rules.push((entry.path(), DEVICE));
+            }
+        }
+    }
+    rules
+}
+
+// === Public API ===
+
+/// Restrict this process. Must run before any other thread is spawned.
+///
+/// The split-mode front process never runs helper programs.
+pub fn apply(config: &Config, role: Role) -> Result<(), String> {
+    let sandbox = &config.sandbox;
+    let serves_actuators = role != Role::Front;
+
+    let mut rules: Vec<(PathBuf, u64)> = vec![(PathBuf::from("/"), READ)];
+    for dir in writable_dirs(config, role) {
+        rules.push((dir, READ | WRITE));
+    }
+    rules.extend(device_rules());
+
+    let executables = executables(config, sandbox, serves_actuators);
+    for path in &executables {
+        rules.push((path.clone(), ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE));
+    }
+
+    set_no_new_privs()?;
+    let abi = restrict_filesystem(&rules)?;
+    install_seccomp()?;
+
+    tracing::info!(
+        "Sandbox active (Landlock ABI {}): {} writable roots, executables: [{}]",
+        abi,
+        rules
+            .iter()
+            .filter(|(_, access)| access & WRITE != 0)
+            .count(),
+        executables
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(())
+}
+
+/// Directories the server may write to, created if missing
+fn writable_dirs(config: &Config, role: Role) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    // Created up front: Landlock rules can only be attached to existing paths
+    let owned = [
+        Config::path().and_then(|p| p.parent().map(Path::to_path_buf)),
+        Config::state_dir(),
+        dirs::data_local_dir().map(|p| p.join("Trash")),
+    ];
+    for dir in owned.into_iter().flatten() {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            tracing::warn!("Could not create {}: {}", dir.display(), e);
+        }
+        dirs.push(dir);
+    }
+
+    // The helper binds its socket after the sandbox is up, whatever its own
+    // config says about split mode
+    if role == Role::Helper {
+        if let Err(e) = crate::split::prepare_socket_dir(&config.split) {
+            tracing::warn!("Could not prepare the helper socket directory: {}", e);
+        }
+    }
+    if role == Role::Helper || config.split.enabled {
+        if let Some(dir) = config.split.socket_path().parent() {
+            dirs.push(dir.to_path_buf());
+        }
+    }
+
+    dirs.extend(config.sandbox.write_roots.iter().map(|p| expand_home(p)));
+    dirs
+}
+
+/// Helper binaries of enabled tools, `allow_exec` entries and their interpreters
+fn executables(config: &Config, sandbox: &SandboxConfig, serves_actuators: bool) -> Vec<PathBuf> {
+    let mut programs: Vec<PathBuf> = Vec::new();
+
+    if serves_actuators {
+        let compiled = crate::config::actuator_tool_names();
+        for (name, tools) in helper_programs(sandbox) {
+            let needed = tools
+                .iter()
+                .any(|t| compiled.contains(t) && config.is_enabled(t));
+            if needed {
+                programs.extend(crate::shared::find_in_path(name));
+            }
+        }
+    }
+
+    for entry in &sandbox.allow_exec {
+        let path = if entry.contains('/') {
+            Some(expand_home(Path::new(entry)))
+        } else {
+            crate::shared::find_in_path(entry)
+        };
+        match path {
+            Some(path) => programs.push(path),
+            None => tracing::warn!("Sandbox: allow_exec entry '{}' not found", entry),
+        }
+    }
+
+    let mut executables: Vec<PathBuf> = Vec::new();
+    for program in programs {
+        let mut chain = vec![program.clone()];
+        chain.extend(interpreters(&program, 2));
+        for path in chain {
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            if !executables.contains(&path) {
+                executables.push(path);
+            }
+        }
+    }
+    executables
+}
+
+/// Programs the kernel loads to run `path`: the ELF interpreter (dynamic loader)
+/// or the `#!` interpreter of a script, recursively
+fn interpreters(path: &Path, depth: u8) -> Vec<PathBuf> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let Some(interpreter) = read_interpreter(path) else {
+        return Vec::new();
+    };
+
+    let mut chain = interpreters(&interpreter, depth - 1);
+    chain.insert(0, interpreter);
+    chain
+}
+
+fn read_interpreter(path: &Path) -> Option<PathBuf> {
+    let mut file = File::open(path).ok()?;
+    let mut data = Vec::new();
+    file.by_ref().take(1 << 20).read_to_end(&mut data).ok()?;
+
+    if let Some(script) = data.strip_prefix(b"#!") {
+        let line = script.split(|&b| b == b'\n').next()?;
+        let line = std::str::from_utf8(line).ok()?;
+        return line.split_whitespace().next().map(PathBuf::from);
+    }
+
+    elf_interpreter(&data)
+}
+
+/// PT_INTERP of a 64-bit little-endian ELF image
+fn elf_interpreter(data: &[u8]) -> Option<PathBuf> {
+    const PT_INTERP: u32 = 3;
+
+    if data.len() < 64 || &data[..4] != b"\x7fELF" || data[4] != 2 || data[5] != 1 {
+        return None;
+    }
+
+    let u16_at = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let u64_at = |at: usize| Some(u64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?));
+
+    let phoff = u64_at(0x20)? as usize;
+    let phentsize = u16_at(0x36)? as usize;
+    let phnum = u16_at(0x38)? as usize;
+
+    for i in 0..phnum {
+        let header = phoff + i * phentsize;
+        if u32_at(header)? != PT_INTERP {
+            continue;
+        }
+        let offset = u64_at(header + 8)? as usize;
+        let size = u64_at(header + 32)? as usize;
+        let raw = data.get(offset..offset + size)?;
+        let raw = raw.strip_suffix(b"\0").unwrap_or(raw);
+        return std::str::from_utf8(raw).ok().map(PathBuf::from);
+    }
+    None
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+// === Syscalls ===
+
+fn last_error() -> std::io::Error {
+    std::io::Error::last_os_error()
+}
+
+fn set_no_new_privs() -> Result<(), String> {
+    // SAFETY: prctl with PR_SET_NO_NEW_PRIVS takes only integer arguments
+    let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+    if ret != 0 {
+        return Err(format!("PR_SET_NO_NEW_PRIVS failed: {}", last_error()));
+    }
+    Ok(())
+}
+
+/// Apply Landlock rules, returning the kernel's Landlock ABI version
+fn restrict_filesystem(rules: &[(PathBuf, u64)]) -> Result<i64, String> {
+    // SAFETY: a NULL attribute with size 0 and the VERSION flag only queries the ABI
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        return Err(format!("Landlock is not available: {}", last_error()));
+    }
+
+    let mut handled = ACCESS_FS_ABI_1;
+    if abi >= 2 {
+        handled |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled |= ACCESS_FS_TRUNCATE;
+    }
+    if abi >= 5 {
+        handled |= ACCESS_FS_IOCTL_DEV;
+    }
+
+    let attr = RulesetAttr {
+        handled_access_fs: handled,
+        handled_access_net: 0,
+    };
+    // Kernels before ABI 4 only know the first field
+    let attr_size = if abi >= 4 {
+        std::mem::size_of::<RulesetAttr>()
+    } else {
+        std::mem::size_of::<u64>()
+    };
+
+    // SAFETY: attr is a valid landlock_ruleset_attr of at least attr_size bytes
+    let ruleset = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            attr_size,
+            0u32,
+        )
+    };
+    if ruleset < 0 {
+        return Err(format!("landlock_create_ruleset failed: {}", last_error()));
+    }
+    // SAFETY: the kernel just returned this descriptor and nothing else owns it
+    let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as i32) };
+
+    for (path, access) in rules {
+        let file = match std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::debug!("Sandbox: skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let is_dir = file.metadata().map(|m| m.is_dir()).unwrap_or(false);
+        let mut allowed = access & handled;
+        if !is_dir {
+            allowed &= FILE_RIGHTS;
+        }
+
+        let beneath = PathBeneathAttr {
+            allowed_access: allowed,
+            parent_fd: file.as_raw_fd(),
+        };
+        // SAFETY: beneath is a valid landlock_path_beneath_attr and both fds are open
+        let ret = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &beneath as *const PathBeneathAttr,
+                0u32,
+            )
+        };
+        if ret != 0 {
+            return Err(format!(
+                "landlock_add_rule for {} failed: {}",
+                path.display(),
+                last_error()
+            ));
+        }
+    }
+
+    // SAFETY: restricts only the calling thread, which is the only one at this point
+    let ret = unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) };
+    if ret != 0 {
+        return Err(format!("landlock_restrict_self failed: {}", last_error()));
+    }
+
+    Ok(abi)
+}
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+fn install_seccomp() -> Result<(), String> {
+    use libc::{sock_filter, sock_fprog, BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W};
+
+    const NR_OFFSET: u32 = 0;
+    const ARCH_OFFSET: u32 = 4;
+
+    let stmt = |code: u32, k: u32| sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump_eq = |k: u32, jt: u8, jf: u8| sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt,
+        jf,
+        k,
+    };
+
+    let mut program = vec![
+        // Kill anything not using the native syscall ABI
+        stmt(BPF_LD | BPF_W | BPF_ABS, ARCH_OFFSET),
+        jump_eq(AUDIT_ARCH, 1, 0),
+        stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        stmt(BPF_LD | BPF_W | BPF_ABS, NR_OFFSET),
+    ];
+
+    // x32 syscalls share the x86_64 audit arch but set this bit in the number
+    #[cfg(target_arch = "x86_64")]
+    program.extend([
+        sock_filter {
+            code: (BPF_JMP | libc::BPF_JGE | BPF_K) as u16,
+            jt: 0,
+            jf: 1,
+            k: 0x4000_0000,
+        },
+        stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+    ]);
+
+    for nr in DENIED_SYSCALLS {
+        program.push(jump_eq(*nr as u32, 0, 1));
+        program.push(stmt(
+            BPF_RET | BPF_K,
+            libc::SECCOMP_RET_ERRNO | libc::EPERM as u32,
+        ));
+    }
+    program.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+
+    let fprog = sock_fprog {
+        len: program.len() as u16,
+        filter: program.as_mut_ptr(),
+    };
+
+    // SAFETY: fprog points at a valid BPF program that outlives the call
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            libc::SECCOMP_SET_MODE_FILTER,
+            libc::SECCOMP_FILTER_FLAG_TSYNC,
+            &fprog as *const sock_fprog,
+        )
+    };
+    if ret != 0 {
+        return Err(format!("seccomp filter failed: {}", last_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn install_seccomp() -> Result<(), String> {
+    tracing::warn!("Sandbox: seccomp filter is not implemented for this architecture");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elf_interpreter() {
+        // Test binaries are dynamically linked, so they name a loader
+        let exe = std::env::current_exe().unwrap();
+        let interpreter = read_interpreter(&exe).expect("dynamically linked test binary");
+        assert!(interpreter.is_absolute());
+        assert!(interpreter.exists());
+    }
+
+    #[test]
+    fn test_script_interpreter() {
+        let dir =
+            std::env::temp_dir().join(format!("rmcp-presence-sandbox-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("script");
+        std::fs::write(&script, "#!/bin/sh -e\necho hi\n").unwrap();
+
+        assert_eq!(read_interpreter(&script), Some(PathBuf::from("/bin/sh")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_defaults() {
+        // Openers bring a shell along, so they are opt-in
+        let mut sandbox = SandboxConfig::default();
+        let programs = helper_programs(&sandbox);
+        assert!(programs.contains(&("journalctl", &["get_unit_logs"][..])));
+        assert!(!programs.iter().any(|(name, _)| *name == "xdg-open"));
+        sandbox.open_path = true;
+        assert!(helper_programs(&sandbox).contains(&("xdg-open", &["open_path"][..])));
+
+        // /dev itself is no longer writable
+        let rules = device_rules();
+        assert!(rules.iter().all(|(path, _)| path != Path::new("/dev")));
+        assert!(rules.contains(&(PathBuf::from("/dev/null"), DEVICE)));
+        assert_eq!(DEVICE & ACCESS_FS_MAKE_CHAR, 0);
+    }
+
+    /// Set in the child process that `test_helper_socket_in_sandbox` sandboxes
+    const CHILD_ENV: &str = "RMCP_PRESENCE_SANDBOX_CHILD";
+
+    #[test]
+    fn test_helper_socket_in_sandbox() {
+        // The sandbox can't be lifted again, so it goes into a child process
+        let root = std::env::temp_dir().join(format!(
+            "rmcp-presence-sandbox-helper-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "sandbox::tests::test_helper_socket_child"])
+            .args(["--nocapture", "--test-threads=1"])
+            .env(CHILD_ENV, &root)
+            .env("HOME", &root)
+            .env("XDG_CONFIG_HOME", root.join("config"))
+            .env("XDG_DATA_HOME", root.join("data"))
+            .env("XDG_STATE_HOME", root.join("state"))
+            .output()
+            .unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}", stdout);
+        assert!(stdout.contains("1 passed"), "{}", stdout);
+    }
+
+    #[test]
+    fn test_helper_socket_child() {
+        let Some(root) = std::env::var_os(CHILD_ENV).map(PathBuf::from) else {
+            return;
+        };
+        // Neither the socket directory nor its parent exist, and split mode is off
+        let mut config = Config::default();
+        config.sandbox.enabled = true;
+        let socket = root.join("run/rmcp-presence/helper.sock");
+        config.split.socket = Some(socket.clone());
+
+        match apply(&config, Role::Helper) {
+            Err(e) if e.starts_with("Landlock is not available") => {
+                println!("skipped: {}", e);
+                return;
+            }
+            result => result.unwrap(),
+        }
+        crate::split::prepare_socket_dir(&config.split).unwrap();
+        std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert!(std::fs::write(root.join("outside"), "x").is_err());
+    }
+}
@@ -16,6 +16,32 @@ fn battery_state_to_string(state: battery::State) -> &'static str {
     }
 }
 
+/// Whether the machine runs on battery (any battery discharging); false without batteries
+pub fn on_battery() -> Result<bool, String> {
+    let manager =
+        battery::Manager::new().map_err(|e| format!("Failed to create battery manager: {}", e))?;
+    let batteries = manager
+        .batteries()
+        .map_err(|e| format!("Failed to get batteries: {}", e))?;
+
+    Ok(batteries
+        .filter_map(|b| b.ok())
+        .any(|b| matches!(b.state(), battery::State::Discharging | battery::State::Empty)))
+}
+
+/// Charge of the first battery in percent; `None` without batteries
+pub fn charge_percent() -> Result<Option<f32>, String> {
+    let manager =
+        battery::Manager::new().map_err(|e| format!("Failed to create battery manager: {}", e))?;
+    let mut batteries = manager
+        .batteries()
+        .map_err(|e| format!("Failed to get batteries: {}", e))?;
+
+    Ok(batteries
+        .find_map(|b| b.ok())
+        .map(|b| b.state_of_charge().value * 100.0))
+}
+
 // === Tool Functions ===
 
 pub async fn get_battery_status() -> Result<CallToolResult, McpError> {
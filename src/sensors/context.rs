@@ -3,6 +3,7 @@
 use rmcp::{model::{CallToolResult, Content}, ErrorData as McpError};
 use serde::Serialize;
 
+use super::state::SystemState;
 use crate::shared::internal_error;
 
 #[derive(Debug, Serialize)]
@@ -36,13 +37,13 @@ pub struct Context {
     pub battery_charging: Option<bool>,
 }
 
-pub async fn get_context() -> Result<CallToolResult, McpError> {
-    let context = build_context().map_err(|e| internal_error(e.to_string()))?;
+pub async fn get_context(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let context = build_context(state).map_err(|e| internal_error(e.to_string()))?;
     let json = serde_json::to_string_pretty(&context).map_err(|e| internal_error(e.to_string()))?;
     Ok(CallToolResult::success(vec![Content::text(json)]))
 }
 
-fn build_context() -> anyhow::Result<Context> {
+fn build_context(state: &SystemState) -> anyhow::Result<Context> {
     // Time
     let now_local = chrono::Local::now();
     let now_utc = chrono::Utc::now();
@@ -85,18 +86,12 @@ fn build_context() -> anyhow::Result<Context> {
         .ok()
         .map(|d| d.as_seconds());
 
-    // System stats
-    let mut sys = sysinfo::System::new();
-    sys.refresh_cpu_usage();
-    sys.refresh_memory();
-
-    // Small delay to get accurate CPU reading
-    std::thread::sleep(std::time::Duration::from_millis(100));
-    sys.refresh_cpu_usage();
-
+    // System stats, from the shared state
+    let sys = state.system();
     let cpu_usage_percent = Some(sys.global_cpu_usage());
     let memory_used_gb = Some(sys.used_memory() as f32 / 1_073_741_824.0);
     let memory_total_gb = Some(sys.total_memory() as f32 / 1_073_741_824.0);
+    drop(sys);
 
     // Battery
     let (battery_percent, battery_charging) = get_battery_info();
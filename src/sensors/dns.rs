@@ -0,0 +1,452 @@
+//! DNS lookups
+//!
+//! Plain address lookups go through the system resolver, so /etc/hosts and the rest of
+//! the NSS setup apply. Other record types, or a chosen nameserver, need the wire
+//! protocol: the query goes over UDP to each nameserver in turn (over TCP when the
+//! answer is truncated), and the first one that answers is reported with the records
+//! and their TTLs.
+
+use crate::egress::Egress;
+use crate::shared::internal_error;
+use hickory_proto::op::{Message, MessageType, Query, ResponseCode};
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+const DNS_PORT: u16 = 53;
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Per nameserver, for the UDP query and the TCP retry together
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+const SUPPORTED_TYPES: &[RecordType] = &[
+    RecordType::A,
+    RecordType::AAAA,
+    RecordType::MX,
+    RecordType::TXT,
+    RecordType::CNAME,
+    RecordType::SRV,
+    RecordType::NS,
+    RecordType::SOA,
+    RecordType::PTR,
+];
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DnsLookupParams {
+    /// Hostname to resolve (e.g., "google.com"), or an IP address for PTR lookups
+    pub hostname: String,
+    #[schemars(
+        description = "Record type: A, AAAA, MX, TXT, CNAME, SRV, NS, SOA or PTR. Omit to get addresses from the system resolver (default A when a nameserver is given)"
+    )]
+    #[serde(default)]
+    pub record_type: Option<String>,
+    #[schemars(
+        description = "Nameserver to ask, as IP, IP:port or hostname (default: nameservers from /etc/resolv.conf)"
+    )]
+    #[serde(default)]
+    pub nameserver: Option<String>,
+}
+
+// === Wire Queries ===
+
+/// Response from the first nameserver that answered
+#[derive(Debug)]
+struct Answer {
+    resolver: SocketAddr,
+    transport: &'static str,
+    elapsed: Duration,
+    message: Message,
+}
+
+/// Nameservers listed in resolv.conf, in order
+fn parse_resolv_conf(contents: &str) -> Vec<SocketAddr> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            if words.next()? != "nameserver" {
+                return None;
+            }
+            let ip: IpAddr = words.next()?.parse().ok()?;
+            Some(SocketAddr::new(ip, DNS_PORT))
+        })
+        .collect()
+}
+
+async fn nameserver_addrs(nameserver: Option<&str>) -> Result<Vec<SocketAddr>, McpError> {
+    let Some(nameserver) = nameserver else {
+        let contents = tokio::fs::read_to_string(RESOLV_CONF)
+            .await
+            .map_err(|e| internal_error(format!("Failed to read {}: {}", RESOLV_CONF, e)))?;
+        let servers = parse_resolv_conf(&contents);
+        if servers.is_empty() {
+            return Err(internal_error(format!(
+                "No nameservers in {} (pass one with `nameserver`)",
+                RESOLV_CONF
+            )));
+        }
+        return Ok(servers);
+    };
+
+    if let Ok(addr) = nameserver.parse::<SocketAddr>() {
+        return Ok(vec![addr]);
+    }
+    if let Ok(ip) = nameserver.parse::<IpAddr>() {
+        return Ok(vec![SocketAddr::new(ip, DNS_PORT)]);
+    }
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((nameserver, DNS_PORT))
+        .await
+        .map_err(|e| {
+            McpError::invalid_params(
+                format!("Cannot resolve nameserver '{}': {}", nameserver, e),
+                None,
+            )
+        })?
+        .collect();
+    Ok(addrs)
+}
+
+fn query_id() -> u16 {
+    RandomState::new().build_hasher().finish() as u16
+}
+
+async fn exchange_udp(server: SocketAddr, request: &[u8], id: u16) -> Result<Message, String> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        "0.0.0.0:0".parse().unwrap()
+    } else {
+        "[::]:0".parse().unwrap()
+    };
+    let socket = UdpSocket::bind(bind).await.map_err(|e| e.to_string())?;
+    socket.connect(server).await.map_err(|e| e.to_string())?;
+    socket.send(request).await.map_err(|e| e.to_string())?;
+
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let len = socket.recv(&mut buf).await.map_err(|e| e.to_string())?;
+        // Stray or late datagrams for an earlier query are skipped
+        match Message::from_vec(&buf[..len]) {
+            Ok(message)
+                if message.id() == id && message.message_type() == MessageType::Response =>
+            {
+                return Ok(message)
+            }
+            _ => continue,
+        }
+    }
+}
+
+async fn exchange_tcp(server: SocketAddr, request: &[u8], id: u16) -> Result<Message, String> {
+    let mut stream = TcpStream::connect(server)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut framed = (request.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(request);
+    stream.write_all(&framed).await.map_err(|e| e.to_string())?;
+
+    let mut len = [0u8; 2];
+    stream
+        .read_exact(&mut len)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream
+        .read_exact(&mut buf)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let message = Message::from_vec(&buf).map_err(|e| e.to_string())?;
+    if message.id() != id {
+        return Err("response ID does not match the query".to_string());
+    }
+    Ok(message)
+}
+
+async fn exchange(server: SocketAddr, request: &[u8], id: u16) -> Result<Answer, String> {
+    let started = Instant::now();
+    let message = exchange_udp(server, request, id).await?;
+    if !message.truncated() {
+        return Ok(Answer {
+            resolver: server,
+            transport: "udp",
+            elapsed: started.elapsed(),
+            message,
+        });
+    }
+    let message = exchange_tcp(server, request, id).await?;
+    Ok(Answer {
+        resolver: server,
+        transport: "tcp",
+        elapsed: started.elapsed(),
+        message,
+    })
+}
+
+/// Ask each server in turn until one gives a usable answer. Failures along the way are
+/// returned too, as (server, error) pairs.
+async fn query(
+    servers: &[SocketAddr],
+    name: &Name,
+    record_type: RecordType,
+) -> (Option<Answer>, Vec<(SocketAddr, String)>) {
+    let mut failures = Vec::new();
+
+    for &server in servers {
+        let id = query_id();
+        let mut request = Message::new();
+        request
+            .set_id(id)
+            .set_recursion_desired(true)
+            .add_query(Query::query(name.clone(), record_type));
+        let request = match request.to_vec() {
+            Ok(request) => request,
+            Err(e) => {
+                failures.push((server, e.to_string()));
+                return (None, failures);
+            }
+        };
+
+        let outcome =
+            match tokio::time::timeout(QUERY_TIMEOUT, exchange(server, &request, id)).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(format!("timed out after {}s", QUERY_TIMEOUT.as_secs())),
+            };
+        match outcome {
+            // These say nothing about the name, so another server may do better
+            Ok(answer)
+                if matches!(
+                    answer.message.response_code(),
+                    ResponseCode::ServFail | ResponseCode::Refused
+                ) =>
+            {
+                failures.push((server, answer.message.response_code().to_string()));
+            }
+            Ok(answer) => return (Some(answer), failures),
+            Err(e) => failures.push((server, e)),
+        }
+    }
+
+    (None, failures)
+}
+
+fn record_json(record: &Record) -> serde_json::Value {
+    let data = match record.data() {
+        Some(RData::MX(mx)) => serde_json::json!({
+            "preference": mx.preference(),
+            "exchange": mx.exchange().to_string(),
+        }),
+        Some(RData::SRV(srv)) => serde_json::json!({
+            "priority": srv.priority(),
+            "weight": srv.weight(),
+            "port": srv.port(),
+            "target": srv.target().to_string(),
+        }),
+        Some(RData::SOA(soa)) => serde_json::json!({
+            "mname": soa.mname().to_string(),
+            "rname": soa.rname().to_string(),
+            "serial": soa.serial(),
+            "refresh": soa.refresh(),
+            "retry": soa.retry(),
+            "expire": soa.expire(),
+            "minimum": soa.minimum(),
+        }),
+        // Long TXT values (SPF, DKIM) are split into strings that belong together
+        Some(RData::TXT(txt)) => serde_json::Value::String(
+            txt.iter()
+                .map(|part| String::from_utf8_lossy(part))
+                .collect(),
+        ),
+        Some(other) => serde_json::Value::String(other.to_string()),
+        None => serde_json::Value::Null,
+    };
+    serde_json::json!({
+        "name": record.name().to_string(),
+        "type": record.record_type().to_string(),
+        "ttl": record.ttl(),
+        "data": data,
+    })
+}
+
+fn parse_record_type(name: &str) -> Result<RecordType, McpError> {
+    RecordType::from_str(&name.trim().to_ascii_uppercase())
+        .ok()
+        .filter(|t| SUPPORTED_TYPES.contains(t))
+        .ok_or_else(|| {
+            McpError::invalid_params(
+                format!(
+                    "Unsupported record type '{}' (use A, AAAA, MX, TXT, CNAME, SRV, NS, SOA or PTR)",
+                    name
+                ),
+                None,
+            )
+        })
+}
+
+/// The name to ask about. IP addresses become their reverse name for PTR lookups.
+fn query_name(hostname: &str, record_type: RecordType) -> Result<Name, McpError> {
+    if record_type == RecordType::PTR {
+        if let Ok(ip) = hostname.parse::<IpAddr>() {
+            return Ok(Name::from(ip));
+        }
+    }
+    let mut name = Name::from_utf8(hostname).map_err(|e| {
+        McpError::invalid_params(format!("Invalid hostname '{}': {}", hostname, e), None)
+    })?;
+    name.set_fqdn(true);
+    Ok(name)
+}
+
+// === Tool Functions ===
+
+/// Resolve a hostname through the system resolver, or query records from a nameserver
+pub async fn dns_lookup(
+    egress: &Egress,
+    params: DnsLookupParams,
+) -> Result<CallToolResult, McpError> {
+    if params.record_type.is_none() && params.nameserver.is_none() {
+        return system_lookup(params.hostname).await;
+    }
+
+    let record_type = parse_record_type(params.record_type.as_deref().unwrap_or("A"))?;
+    let name = query_name(&params.hostname, record_type)?;
+    let servers = nameserver_addrs(params.nameserver.as_deref()).await?;
+    // Nameservers the egress setting rules out are skipped, and refused if none is left
+    let (servers, refused): (Vec<SocketAddr>, Vec<SocketAddr>) = servers
+        .into_iter()
+        .partition(|server| egress.allows_ip(server.ip()));
+    if let (true, Some(server)) = (servers.is_empty(), refused.first()) {
+        egress.check_addr(*server)?;
+    }
+
+    let (answer, failures) = query(&servers, &name, record_type).await;
+    let failures: Vec<serde_json::Value> = failures
+        .into_iter()
+        .map(|(server, error)| serde_json::json!({"resolver": server.to_string(), "error": error}))
+        .collect();
+
+    let mut json = serde_json::json!({
+        "hostname": params.hostname,
+        "query": name.to_string(),
+        "record_type": record_type.to_string(),
+    });
+    match answer {
+        Some(answer) => {
+            let records: Vec<serde_json::Value> =
+                answer.message.answers().iter().map(record_json).collect();
+            json["resolved"] = serde_json::json!(!records.is_empty());
+            json["status"] = serde_json::json!(answer.message.response_code().to_string());
+            json["resolver"] = serde_json::json!(answer.resolver.to_string());
+            json["transport"] = serde_json::json!(answer.transport);
+            json["authoritative"] = serde_json::json!(answer.message.authoritative());
+            json["elapsed_ms"] = serde_json::json!(answer.elapsed.as_millis() as u64);
+            json["records"] = serde_json::json!(records);
+        }
+        None => {
+            json["resolved"] = serde_json::json!(false);
+            json["error"] = serde_json::json!("No nameserver answered");
+        }
+    }
+    if !failures.is_empty() {
+        json["failed_resolvers"] = serde_json::json!(failures);
+    }
+
+    let json = serde_json::to_string_pretty(&json)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+async fn system_lookup(hostname: String) -> Result<CallToolResult, McpError> {
+    let lookup = hostname.clone();
+    let result = tokio::task::spawn_blocking(move || format!("{}:0", lookup).to_socket_addrs())
+        .await
+        .map_err(|e| internal_error(format!("Task error: {}", e)))?;
+
+    let json = match result {
+        Ok(addrs) => {
+            let ips: Vec<String> = addrs.map(|addr| addr.ip().to_string()).collect();
+            serde_json::json!({
+                "hostname": hostname,
+                "resolved": true,
+                "addresses": ips
+            })
+        }
+        Err(e) => serde_json::json!({
+            "hostname": hostname,
+            "resolved": false,
+            "error": e.to_string()
+        }),
+    };
+    Ok(CallToolResult::success(vec![Content::text(
+        serde_json::to_string_pretty(&json).unwrap(),
+    )]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_proto::rr::rdata::MX;
+
+    #[test]
+    fn test_parse_resolv_conf() {
+        let conf = "# generated\nnameserver 127.0.0.53\noptions edns0\nnameserver ::1\nnameserver fe80::1%eth0\nsearch lan\n";
+        assert_eq!(
+            parse_resolv_conf(conf),
+            vec![
+                "127.0.0.53:53".parse().unwrap(),
+                "[::1]:53".parse().unwrap()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_stub_server() {
+        // Answers a single MX query, like a minimal recursive resolver would
+        let stub = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let stub_addr = stub.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 512];
+            let (len, peer) = stub.recv_from(&mut buf).await.unwrap();
+            let request = Message::from_vec(&buf[..len]).unwrap();
+            let question = request.queries()[0].clone();
+            let exchange = Name::from_ascii("mail.example.com.").unwrap();
+            let mut response = Message::new();
+            response
+                .set_id(request.id())
+                .set_message_type(MessageType::Response)
+                .add_query(question.clone())
+                .add_answer(Record::from_rdata(
+                    question.name().clone(),
+                    300,
+                    RData::MX(MX::new(10, exchange)),
+                ));
+            stub.send_to(&response.to_vec().unwrap(), peer)
+                .await
+                .unwrap();
+        });
+
+        // Nothing listens here, so the first server fails and the stub answers
+        let dead = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let dead_addr = dead.local_addr().unwrap();
+        drop(dead);
+
+        let name = query_name("example.com", RecordType::MX).unwrap();
+        let (answer, failures) = query(&[dead_addr, stub_addr], &name, RecordType::MX).await;
+        let answer = answer.unwrap();
+        assert_eq!(answer.resolver, stub_addr);
+        assert_eq!(failures.len(), 1);
+
+        let record = record_json(&answer.message.answers()[0]);
+        assert_eq!(record["ttl"], 300);
+        assert_eq!(record["data"]["preference"], 10);
+        assert_eq!(record["data"]["exchange"], "mail.example.com.");
+    }
+}
@@ -0,0 +1,580 @@
+//! Metric history - periodic samples in a bounded ring, queried as trends
+//!
+//! A background task samples CPU, memory, swap, load, temperature, disk, network
+//! throughput and battery from the shared system state every `[history]
+//! interval_secs`. `get_metric_history` downsamples a window of that ring into
+//! buckets with min/avg/max, which answers "has CPU been pegged for ten minutes?"
+//! where a one-shot reading can't. With `persist = true` samples are also appended
+//! to `history.jsonl` in the state directory and reloaded on start.
+
+use super::state::SystemState;
+use crate::config::{Config, HistoryConfig};
+use crate::shared::internal_error;
+use chrono::TimeZone;
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+const DEFAULT_WINDOW_SECS: u64 = 600;
+
+/// Default number of buckets a window is split into
+const DEFAULT_BUCKETS: u64 = 30;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct MetricHistoryParams {
+    #[schemars(
+        description = "Metric: cpu, memory, swap, load, temperature, disk, net_rx, net_tx or battery"
+    )]
+    pub metric: String,
+    #[schemars(description = "How far back to look, in seconds (default 600)")]
+    #[serde(default)]
+    pub window: Option<u64>,
+    #[schemars(
+        description = "Bucket size in seconds (default: a 30th of the window, never below the sampling interval)"
+    )]
+    #[serde(default)]
+    pub resolution: Option<u64>,
+}
+
+// === Samples ===
+
+/// One reading of every metric. Missing values (no sensor, first rate sample) are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    /// Unix time in seconds
+    t: i64,
+    #[serde(default)]
+    cpu: Option<f64>,
+    #[serde(default)]
+    memory: Option<f64>,
+    #[serde(default)]
+    swap: Option<f64>,
+    #[serde(default)]
+    load: Option<f64>,
+    #[serde(default)]
+    temperature: Option<f64>,
+    #[serde(default)]
+    disk: Option<f64>,
+    #[serde(default)]
+    net_rx: Option<f64>,
+    #[serde(default)]
+    net_tx: Option<f64>,
+    #[serde(default)]
+    battery: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+    Cpu,
+    Memory,
+    Swap,
+    Load,
+    Temperature,
+    Disk,
+    NetRx,
+    NetTx,
+    Battery,
+}
+
+impl Metric {
+    const ALL: [Metric; 9] = [
+        Metric::Cpu,
+        Metric::Memory,
+        Metric::Swap,
+        Metric::Load,
+        Metric::Temperature,
+        Metric::Disk,
+        Metric::NetRx,
+        Metric::NetTx,
+        Metric::Battery,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::Memory => "memory",
+            Metric::Swap => "swap",
+            Metric::Load => "load",
+            Metric::Temperature => "temperature",
+            Metric::Disk => "disk",
+            Metric::NetRx => "net_rx",
+            Metric::NetTx => "net_tx",
+            Metric::Battery => "battery",
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        match self {
+            Metric::Cpu | Metric::Memory | Metric::Swap | Metric::Disk | Metric::Battery => {
+                "percent"
+            }
+            Metric::Load => "1m load average",
+            Metric::Temperature => "°C (hottest sensor)",
+            Metric::NetRx | Metric::NetTx => "bytes/s",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    fn value(self, sample: &Sample) -> Option<f64> {
+        match self {
+            Metric::Cpu => sample.cpu,
+            Metric::Memory => sample.memory,
+            Metric::Swap => sample.swap,
+            Metric::Load => sample.load,
+            Metric::Temperature => sample.temperature,
+            Metric::Disk => sample.disk,
+            Metric::NetRx => sample.net_rx,
+            Metric::NetTx => sample.net_tx,
+            Metric::Battery => sample.battery,
+        }
+    }
+}
+
+/// Cumulative network counters from the previous sample, for turning them into rates
+struct Counters {
+    at: Instant,
+    received: u64,
+    transmitted: u64,
+}
+
+fn percent(used: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| used as f64 / total as f64 * 100.0)
+}
+
+fn take_sample(state: &SystemState, previous: &mut Option<Counters>) -> Sample {
+    let (cpu, memory, swap) = {
+        let sys = state.system();
+        (
+            sys.global_cpu_usage() as f64,
+            percent(sys.used_memory(), sys.total_memory()),
+            percent(sys.used_swap(), sys.total_swap()),
+        )
+    };
+
+    let temperature = state
+        .components()
+        .iter()
+        .filter_map(|c| c.temperature())
+        .filter(|t| t.is_finite())
+        .map(f64::from)
+        .reduce(f64::max);
+
+    let (total, available) = state
+        .disks()
+        .iter()
+        .fold((0u64, 0u64), |(total, available), disk| {
+            (
+                total + disk.total_space(),
+                available + disk.available_space(),
+            )
+        });
+
+    let (received, transmitted) =
+        state
+            .networks()
+            .iter()
+            .fold((0u64, 0u64), |(received, transmitted), (_, data)| {
+                (
+                    received + data.total_received(),
+                    transmitted + data.total_transmitted(),
+                )
+            });
+    let now = Instant::now();
+    let rate = |current: u64, before: u64, since: Instant| {
+        let secs = now.duration_since(since).as_secs_f64();
+        // Counters can go backwards when an interface disappears
+        (secs > 0.0 && current >= before).then(|| (current - before) as f64 / secs)
+    };
+    let (net_rx, net_tx) = match previous.as_ref() {
+        Some(p) => (
+            rate(received, p.received, p.at),
+            rate(transmitted, p.transmitted, p.at),
+        ),
+        None => (None, None),
+    };
+    *previous = Some(Counters {
+        at: now,
+        received,
+        transmitted,
+    });
+
+    Sample {
+        t: chrono::Utc::now().timestamp(),
+        cpu: Some(cpu),
+        memory,
+        swap,
+        load: Some(System::load_average().one),
+        temperature,
+        disk: percent(total.saturating_sub(available), total),
+        net_rx,
+        net_tx,
+        battery: super::battery::charge_percent()
+            .ok()
+            .flatten()
+            .map(f64::from),
+    }
+}
+
+// === History ===
+
+#[derive(Debug)]
+pub struct History {
+    enabled: bool,
+    interval: Duration,
+    capacity: usize,
+    /// Persistence file, if enabled
+    file: Option<PathBuf>,
+    samples: Mutex<VecDeque<Sample>>,
+    /// Samples appended to the file since it was last rewritten
+    appended: AtomicUsize,
+}
+
+impl History {
+    pub fn new(config: &HistoryConfig) -> Self {
+        let interval = config.interval_secs.max(1);
+        let file = if config.persist {
+            let file = Config::state_dir().map(|dir| dir.join("history.jsonl"));
+            if file.is_none() {
+                tracing::warn!("No state directory, metric history is kept in memory only");
+            }
+            file
+        } else {
+            None
+        };
+        Self {
+            enabled: config.enabled,
+            interval: Duration::from_secs(interval),
+            capacity: (config.retention_secs / interval).max(1) as usize,
+            file,
+            samples: Mutex::new(VecDeque::new()),
+            appended: AtomicUsize::new(0),
+        }
+    }
+
+    /// Reload persisted samples and sample until the history is dropped
+    pub fn start(self: &Arc<Self>, state: Arc<SystemState>) {
+        if !self.enabled {
+            return;
+        }
+        self.load();
+
+        let period = self.interval;
+        let history = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // Give the shared state a full refresh interval before the first sample
+            interval.tick().await;
+
+            let mut previous = None;
+            loop {
+                interval.tick().await;
+                let history = history.clone();
+                let state = state.clone();
+                let sampled = tokio::task::spawn_blocking(move || {
+                    let history = history.upgrade()?;
+                    history.record(take_sample(&state, &mut previous));
+                    Some(previous)
+                })
+                .await;
+                match sampled {
+                    Ok(Some(counters)) => previous = counters,
+                    _ => break,
+                }
+            }
+        });
+    }
+
+    fn record(&self, sample: Sample) {
+        {
+            let mut samples = lock(&self.samples);
+            while samples.len() >= self.capacity {
+                samples.pop_front();
+            }
+            samples.push_back(sample.clone());
+        }
+
+        let Some(file) = &self.file else {
+            return;
+        };
+        // Appending is cheap; once the file holds about twice the ring, it is rewritten
+        if self.appended.fetch_add(1, Ordering::Relaxed) + 1 >= self.capacity {
+            self.appended.store(0, Ordering::Relaxed);
+            self.save();
+            return;
+        }
+        let appended = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .and_then(|mut f| {
+                let line = serde_json::to_string(&sample).map_err(std::io::Error::other)?;
+                writeln!(f, "{}", line)
+            });
+        if let Err(e) = appended {
+            tracing::warn!("Could not write {}: {}", file.display(), e);
+        }
+    }
+
+    /// Read persisted samples that are still within retention
+    fn load(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if let Some(dir) = file.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                tracing::warn!("Could not create {}: {}", dir.display(), e);
+            }
+        }
+        let Ok(contents) = fs::read_to_string(file) else {
+            return;
+        };
+
+        let cutoff = chrono::Utc::now().timestamp()
+            - (self.capacity as u64 * self.interval.as_secs()) as i64;
+        let loaded: Vec<Sample> = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Sample>(line).ok())
+            .filter(|sample| sample.t >= cutoff)
+            .collect();
+        tracing::info!("Loaded {} metric history samples", loaded.len());
+
+        {
+            let mut samples = lock(&self.samples);
+            let skip = loaded.len().saturating_sub(self.capacity);
+            samples.extend(loaded.into_iter().skip(skip));
+        }
+        self.save();
+    }
+
+    /// Rewrite the persistence file with the ring's current contents
+    fn save(&self) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let contents: String = lock(&self.samples)
+            .iter()
+            .filter_map(|sample| serde_json::to_string(sample).ok())
+            .map(|line| line + "\n")
+            .collect();
+        let tmp = file.with_extension("jsonl.tmp");
+        if let Err(e) = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, file)) {
+            tracing::warn!("Could not write {}: {}", file.display(), e);
+        }
+    }
+
+    pub async fn get_metric_history(
+        &self,
+        params: MetricHistoryParams,
+    ) -> Result<CallToolResult, McpError> {
+        if !self.enabled {
+            return Err(internal_error(
+                "Metric history is disabled (set [history] enabled = true)",
+            ));
+        }
+        let metric = Metric::parse(&params.metric).ok_or_else(|| {
+            let names: Vec<&str> = Metric::ALL.iter().map(|m| m.name()).collect();
+            McpError::invalid_params(
+                format!(
+                    "Unknown metric '{}' (expected one of: {})",
+                    params.metric,
+                    names.join(", ")
+                ),
+                None,
+            )
+        })?;
+
+        let window = params.window.unwrap_or(DEFAULT_WINDOW_SECS).max(1);
+        let resolution = params
+            .resolution
+            .unwrap_or(window / DEFAULT_BUCKETS)
+            .max(self.interval.as_secs());
+        let now = chrono::Utc::now().timestamp();
+        let from = now - window as i64;
+
+        let samples: Vec<Sample> = lock(&self.samples)
+            .iter()
+            .filter(|s| s.t > from)
+            .cloned()
+            .collect();
+        let (buckets, overall) = summarize(&samples, metric, from, resolution as i64);
+
+        let series: Vec<serde_json::Value> = buckets
+            .iter()
+            .map(|b| {
+                serde_json::json!({
+                    "start": format_time(b.start),
+                    "min": round(b.stats.min),
+                    "avg": round(b.stats.avg),
+                    "max": round(b.stats.max),
+                    "samples": b.stats.count,
+                })
+            })
+            .collect();
+
+        let mut output = serde_json::json!({
+            "metric": metric.name(),
+            "unit": metric.unit(),
+            "window_secs": window,
+            "resolution_secs": resolution,
+            "interval_secs": self.interval.as_secs(),
+            "samples": overall.as_ref().map_or(0, |s| s.count),
+            "series": series,
+        });
+        match overall {
+            Some(stats) => {
+                output["min"] = round(stats.min).into();
+                output["avg"] = round(stats.avg).into();
+                output["max"] = round(stats.max).into();
+            }
+            None => {
+                output["note"] = "No samples for this metric in the window yet".into();
+            }
+        }
+
+        let json = serde_json::to_string_pretty(&output)
+            .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn format_time(t: i64) -> String {
+    chrono::Local
+        .timestamp_opt(t, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%dT%H:%M:%S%:z").to_string())
+        .unwrap_or_default()
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// === Downsampling ===
+
+#[derive(Debug, Clone, PartialEq)]
+struct Stats {
+    min: f64,
+    avg: f64,
+    max: f64,
+    count: usize,
+}
+
+impl Stats {
+    fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(Self {
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            avg: values.iter().sum::<f64>() / values.len() as f64,
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            count: values.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Bucket {
+    /// Unix time the bucket starts at
+    start: i64,
+    stats: Stats,
+}
+
+/// Group samples after `from` into `resolution`-second buckets. Buckets without a
+/// value for the metric are left out.
+fn summarize(
+    samples: &[Sample],
+    metric: Metric,
+    from: i64,
+    resolution: i64,
+) -> (Vec<Bucket>, Option<Stats>) {
+    let resolution = resolution.max(1);
+    let mut buckets: Vec<(i64, Vec<f64>)> = Vec::new();
+    let mut all = Vec::new();
+
+    for sample in samples.iter().filter(|s| s.t > from) {
+        let Some(value) = metric.value(sample) else {
+            continue;
+        };
+        all.push(value);
+        let start = from + (sample.t - from) / resolution * resolution;
+        match buckets.last_mut() {
+            Some((last, values)) if *last == start => values.push(value),
+            _ => buckets.push((start, vec![value])),
+        }
+    }
+
+    let buckets = buckets
+        .into_iter()
+        .filter_map(|(start, values)| {
+            Some(Bucket {
+                start,
+                stats: Stats::of(&values)?,
+            })
+        })
+        .collect();
+    (buckets, Stats::of(&all))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpu(t: i64, value: f64) -> Sample {
+        Sample {
+            t,
+            cpu: Some(value),
+            ..Sample::default()
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let samples = [
+            cpu(105, 10.0),
+            cpu(110, 30.0),
+            cpu(120, 90.0),
+            Sample {
+                t: 125,
+                ..Sample::default()
+            },
+            cpu(130, 100.0),
+        ];
+        let (buckets, overall) = summarize(&samples, Metric::Cpu, 100, 20);
+
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].start, 100);
+        assert_eq!(buckets[0].stats.count, 2);
+        assert_eq!(buckets[0].stats.min, 10.0);
+        assert_eq!(buckets[0].stats.max, 30.0);
+        assert_eq!(buckets[1].start, 120);
+        assert_eq!(buckets[1].stats.avg, 95.0);
+
+        let overall = overall.unwrap();
+        assert_eq!(overall.count, 4);
+        assert_eq!(overall.avg, 57.5);
+
+        // Metrics without readings produce nothing
+        let (buckets, overall) = summarize(&samples, Metric::Battery, 100, 20);
+        assert!(buckets.is_empty());
+        assert!(overall.is_none());
+    }
+}
@@ -14,6 +14,15 @@ pub struct IdleThresholdParams {
     pub threshold_seconds: u64,
 }
 
+// === Helper Functions ===
+
+/// Seconds since the last keyboard or mouse input
+pub fn idle_seconds() -> Result<u64, String> {
+    UserIdle::get_time()
+        .map(|idle| idle.as_seconds())
+        .map_err(|e| format!("Failed to get idle time: {}", e))
+}
+
 // === Tool Functions ===
 
 pub async fn get_idle_time() -> Result<CallToolResult, McpError> {
@@ -4,10 +4,17 @@ pub mod battery;
 pub mod bluetooth;
 pub mod context;
 pub mod display;
+pub mod dns;
 pub mod git;
+pub mod history;
 pub mod idle;
 pub mod network;
 pub mod peripherals;
+pub mod probe;
+#[cfg(target_os = "linux")]
+pub mod sockets;
+pub mod state;
 pub mod sysinfo;
+pub mod traffic;
 pub mod usb;
 pub mod weather;
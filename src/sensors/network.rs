@@ -1,15 +1,21 @@
 //! Network interface sensors
 
-use crate::shared::internal_error;
+use super::state::SystemState;
+use super::traffic::{interface_rates, DEFAULT_WINDOW};
+use crate::config::{IpProvider, PublicIpConfig};
+use crate::egress::Egress;
+use crate::shared::{internal_error, RequestBuilderExt};
 use network_interface::{Addr, NetworkInterface, NetworkInterfaceConfig};
 use rmcp::{model::*, ErrorData as McpError};
-use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::net::{TcpStream, ToSocketAddrs};
+use std::net::{IpAddr, SocketAddr, TcpStream};
 use std::time::Duration;
 
-/// Response from ipinfo.io
-#[derive(Debug, Deserialize, Serialize)]
+/// Connectivity is checked with a TCP connect to Cloudflare DNS
+const ONLINE_CHECK: &str = "1.1.1.1:53";
+
+/// Response from ipinfo.io. Plain-text providers fill in only `ip`.
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct IpInfo {
     pub ip: String,
     #[serde(default)]
@@ -89,19 +95,59 @@ pub async fn get_interfaces() -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(result)]))
 }
 
-/// Get public IP address and geolocation info from ipinfo.io
-pub async fn get_public_ip() -> Result<CallToolResult, McpError> {
-    let client = reqwest::Client::new();
-
-    let info: IpInfo = client
-        .get("https://ipinfo.io/json")
-        .header("Accept", "application/json")
-        .send()
+/// Ask the configured service for our public address
+async fn fetch_public_ip(
+    client: &reqwest::Client,
+    config: &PublicIpConfig,
+    timeout: Duration,
+) -> Result<IpInfo, String> {
+    let accept = match config.provider {
+        IpProvider::Ipinfo => "application/json",
+        IpProvider::Plain => "text/plain",
+    };
+    let response = client
+        .get(config.url())
+        .header("Accept", accept)
+        .timeout(timeout)
+        .traced_send()
         .await
-        .map_err(|e| internal_error(format!("Failed to fetch IP info: {}", e)))?
-        .json()
+        .map_err(|e| format!("Failed to fetch IP info: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("IP service returned status: {}", response.status()));
+    }
+
+    match config.provider {
+        IpProvider::Ipinfo => response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse IP info: {}", e)),
+        IpProvider::Plain => {
+            let body = response
+                .text()
+                .await
+                .map_err(|e| format!("Failed to read IP info: {}", e))?;
+            let ip: IpAddr = body
+                .trim()
+                .parse()
+                .map_err(|_| format!("IP service did not return an address: {:.40}", body))?;
+            Ok(IpInfo {
+                ip: ip.to_string(),
+                ..IpInfo::default()
+            })
+        }
+    }
+}
+
+/// Get public IP address, and geolocation info where the provider has it
+pub async fn get_public_ip(
+    config: &PublicIpConfig,
+    egress: &Egress,
+) -> Result<CallToolResult, McpError> {
+    let client = egress.client(config.url()).await?;
+
+    let info = fetch_public_ip(&client, config, Duration::from_secs(10))
         .await
-        .map_err(|e| internal_error(format!("Failed to parse IP info: {}", e)))?;
+        .map_err(internal_error)?;
 
     let json = serde_json::to_string_pretty(&info)
         .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
@@ -111,22 +157,16 @@ pub async fn get_public_ip() -> Result<CallToolResult, McpError> {
 
 // === Params ===
 
-#[derive(Debug, Deserialize, JsonSchema)]
-pub struct DnsLookupParams {
-    /// Hostname to resolve (e.g., "google.com")
-    pub hostname: String,
-}
-
 // === New Tools ===
 
 /// Check if we have internet connectivity
-pub async fn is_online() -> Result<CallToolResult, McpError> {
-    // Try to connect to Cloudflare DNS (1.1.1.1:53) with a 3 second timeout
-    let result = tokio::task::spawn_blocking(|| {
-        TcpStream::connect_timeout(
-            &"1.1.1.1:53".parse().unwrap(),
-            Duration::from_secs(3),
-        )
+pub async fn is_online(egress: &Egress) -> Result<CallToolResult, McpError> {
+    let target: SocketAddr = ONLINE_CHECK.parse().unwrap();
+    egress.check_addr(target)?;
+
+    // Try to connect with a 3 second timeout
+    let result = tokio::task::spawn_blocking(move || {
+        TcpStream::connect_timeout(&target, Duration::from_secs(3))
     })
     .await
     .map_err(|e| internal_error(format!("Task error: {}", e)))?;
@@ -134,7 +174,7 @@ pub async fn is_online() -> Result<CallToolResult, McpError> {
     let online = result.is_ok();
     let json = serde_json::json!({
         "online": online,
-        "checked_host": "1.1.1.1:53",
+        "checked_host": ONLINE_CHECK,
         "method": "tcp_connect"
     });
 
@@ -143,49 +183,11 @@ pub async fn is_online() -> Result<CallToolResult, McpError> {
     )]))
 }
 
-/// Resolve a hostname to IP addresses
-pub async fn dns_lookup(params: DnsLookupParams) -> Result<CallToolResult, McpError> {
-    let hostname = params.hostname.clone();
-
-    let result = tokio::task::spawn_blocking(move || {
-        let lookup = format!("{}:0", hostname);
-        lookup.to_socket_addrs()
-    })
-    .await
-    .map_err(|e| internal_error(format!("Task error: {}", e)))?;
-
-    match result {
-        Ok(addrs) => {
-            let ips: Vec<String> = addrs
-                .map(|addr| addr.ip().to_string())
-                .collect();
-
-            let json = serde_json::json!({
-                "hostname": params.hostname,
-                "resolved": true,
-                "addresses": ips
-            });
-            Ok(CallToolResult::success(vec![Content::text(
-                serde_json::to_string_pretty(&json).unwrap(),
-            )]))
-        }
-        Err(e) => {
-            let json = serde_json::json!({
-                "hostname": params.hostname,
-                "resolved": false,
-                "error": e.to_string()
-            });
-            Ok(CallToolResult::success(vec![Content::text(
-                serde_json::to_string_pretty(&json).unwrap(),
-            )]))
-        }
-    }
-}
-
 /// Comprehensive network status - everything at once
 #[derive(Debug, Serialize)]
 pub struct NetworkInfo {
-    pub online: bool,
+    /// Unknown when `network_egress` rules out the check
+    pub online: Option<bool>,
     pub public_ip: Option<String>,
     pub location: Option<NetworkLocation>,
     pub interfaces: Vec<InterfaceInfo>,
@@ -213,30 +215,37 @@ pub struct InterfaceInfo {
 pub struct NetworkStats {
     pub bytes_sent: u64,
     pub bytes_received: u64,
+    /// Averaged over the last few seconds, all interfaces together
+    pub sent_per_sec: f64,
+    pub received_per_sec: f64,
 }
 
-pub async fn get_network_info() -> Result<CallToolResult, McpError> {
-    // Check online status
-    let online = tokio::task::spawn_blocking(|| {
-        TcpStream::connect_timeout(
-            &"1.1.1.1:53".parse().unwrap(),
-            Duration::from_secs(3),
-        ).is_ok()
-    })
-    .await
-    .unwrap_or(false);
+pub async fn get_network_info(
+    state: &SystemState,
+    ip_config: &PublicIpConfig,
+    egress: &Egress,
+) -> Result<CallToolResult, McpError> {
+    // Check online status, unless the egress setting forbids it
+    let target: SocketAddr = ONLINE_CHECK.parse().unwrap();
+    let online = if egress.allows_ip(target.ip()) {
+        let reachable = tokio::task::spawn_blocking(move || {
+            TcpStream::connect_timeout(&target, Duration::from_secs(3)).is_ok()
+        })
+        .await
+        .unwrap_or(false);
+        Some(reachable)
+    } else {
+        None
+    };
 
     // Get public IP if online
-    let (public_ip, location) = if online {
-        let client = reqwest::Client::new();
-        match client
-            .get("https://ipinfo.io/json")
-            .header("Accept", "application/json")
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await
-        {
-            Ok(resp) => match resp.json::<IpInfo>().await {
+    let (public_ip, location) =
+        if online == Some(true) && egress.allows_url(ip_config.url()).await {
+            let info = match egress.client(ip_config.url()).await {
+                Ok(client) => fetch_public_ip(&client, ip_config, Duration::from_secs(5)).await,
+                Err(e) => Err(e.message.to_string()),
+            };
+            match info {
                 Ok(info) => (
                     Some(info.ip),
                     Some(NetworkLocation {
@@ -247,12 +256,10 @@ pub async fn get_network_info() -> Result<CallToolResult, McpError> {
                     }),
                 ),
                 Err(_) => (None, None),
-            },
-            Err(_) => (None, None),
-        }
-    } else {
-        (None, None)
-    };
+            }
+        } else {
+            (None, None)
+        };
 
     // Get interfaces
     let interfaces = NetworkInterface::show()
@@ -296,14 +303,21 @@ pub async fn get_network_info() -> Result<CallToolResult, McpError> {
         .collect();
 
     // Get network stats
-    let networks = sysinfo::Networks::new_with_refreshed_list();
-
-    let (bytes_sent, bytes_received) = networks
+    let (bytes_sent, bytes_received) = state
+        .networks()
         .iter()
         .fold((0u64, 0u64), |(sent, recv), (_, data)| {
             (sent + data.total_transmitted(), recv + data.total_received())
         });
 
+    let (_, rates) = interface_rates(state, DEFAULT_WINDOW);
+    let (sent_per_sec, received_per_sec) = rates.iter().fold((0.0, 0.0), |(sent, recv), r| {
+        (
+            sent + r.transmitted_bytes_per_sec,
+            recv + r.received_bytes_per_sec,
+        )
+    });
+
     let info = NetworkInfo {
         online,
         public_ip,
@@ -312,6 +326,8 @@ pub async fn get_network_info() -> Result<CallToolResult, McpError> {
         stats: NetworkStats {
             bytes_sent,
             bytes_received,
+            sent_per_sec: (sent_per_sec * 10.0).round() / 10.0,
+            received_per_sec: (received_per_sec * 10.0).round() / 10.0,
         },
     };
 
@@ -0,0 +1,347 @@
+//! TCP reachability probes
+//!
+//! A probe resolves the host once, timing the lookup, then makes a number of TCP
+//! connections and times each handshake. Plain TCP works without raw sockets or root,
+//! and also says whether the service itself is up, which ICMP can't.
+
+use crate::config::EndpointConfig;
+use crate::egress::Egress;
+use crate::shared::internal_error;
+use futures::future::join_all;
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+const DEFAULT_COUNT: u32 = 3;
+const MAX_COUNT: u32 = 20;
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+
+/// Pause between attempts, so they don't all land in the same instant
+const ATTEMPT_GAP: Duration = Duration::from_millis(100);
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ProbeParams {
+    #[schemars(description = "Hostname or IP address")]
+    pub host: String,
+    #[schemars(description = "TCP port")]
+    pub port: u16,
+    #[schemars(description = "Number of connection attempts (default 3, at most 20)")]
+    #[serde(default)]
+    pub count: Option<u32>,
+    #[schemars(
+        description = "Timeout per DNS lookup and per attempt in milliseconds (default 2000)"
+    )]
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CheckEndpointsParams {
+    #[schemars(
+        description = "Names of endpoints from [endpoints] in tools.toml to check (default: all)"
+    )]
+    #[serde(default)]
+    pub names: Option<Vec<String>>,
+}
+
+// === Probe ===
+
+#[derive(Debug, Serialize)]
+pub struct Attempt {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct LatencyStats {
+    pub min_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// Mean difference between consecutive successful attempts
+    pub jitter_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProbeResult {
+    pub host: String,
+    pub port: u16,
+    /// At least one attempt connected
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dns_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<SocketAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub sent: u32,
+    pub failed: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 100_000.0).round() / 100.0
+}
+
+fn latency_stats(latencies: &[f64]) -> Option<LatencyStats> {
+    if latencies.is_empty() {
+        return None;
+    }
+    let round = |v: f64| (v * 100.0).round() / 100.0;
+    let jitter = if latencies.len() > 1 {
+        latencies
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .sum::<f64>()
+            / (latencies.len() - 1) as f64
+    } else {
+        0.0
+    };
+    Some(LatencyStats {
+        min_ms: round(latencies.iter().copied().fold(f64::INFINITY, f64::min)),
+        avg_ms: round(latencies.iter().sum::<f64>() / latencies.len() as f64),
+        max_ms: round(latencies.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        jitter_ms: round(jitter),
+    })
+}
+
+impl ProbeResult {
+    fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            reachable: false,
+            dns_ms: None,
+            address: None,
+            error: None,
+            sent: 0,
+            failed: 0,
+            latency: None,
+            attempts: Vec::new(),
+        }
+    }
+}
+
+/// Check `host` against the egress policy and resolve it once, then time `count` TCP
+/// connects to the first address. When the policy had to resolve the host, its
+/// addresses are used, so the probe connects only to an address that was checked.
+pub async fn probe(
+    egress: &Egress,
+    host: &str,
+    port: u16,
+    count: u32,
+    timeout: Duration,
+) -> Result<ProbeResult, McpError> {
+    let mut result = ProbeResult::new(host, port);
+
+    let started = Instant::now();
+    let address = match egress.resolve_host(host, port).await? {
+        Some(addrs) => {
+            result.dns_ms = Some(millis(started.elapsed()));
+            addrs.first().copied()
+        }
+        // Allowed by name, so any address it resolves to is fine
+        None => {
+            let resolved =
+                tokio::time::timeout(timeout, tokio::net::lookup_host((host, port))).await;
+            match resolved {
+                Ok(Ok(mut addrs)) => {
+                    result.dns_ms = Some(millis(started.elapsed()));
+                    addrs.next()
+                }
+                Ok(Err(e)) => {
+                    result.error = Some(format!("DNS lookup failed: {}", e));
+                    return Ok(result);
+                }
+                Err(_) => {
+                    result.error = Some(format!(
+                        "DNS lookup timed out after {}ms",
+                        timeout.as_millis()
+                    ));
+                    return Ok(result);
+                }
+            }
+        }
+    };
+    let Some(address) = address else {
+        result.error = Some("DNS lookup returned no addresses".to_string());
+        return Ok(result);
+    };
+    result.address = Some(address);
+
+    let mut latencies = Vec::new();
+    for i in 0..count {
+        if i > 0 {
+            tokio::time::sleep(ATTEMPT_GAP).await;
+        }
+        let started = Instant::now();
+        let attempt =
+            match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address)).await {
+                Ok(Ok(_stream)) => {
+                    let latency = millis(started.elapsed());
+                    latencies.push(latency);
+                    Attempt {
+                        ok: true,
+                        latency_ms: Some(latency),
+                        error: None,
+                    }
+                }
+                Ok(Err(e)) => Attempt {
+                    ok: false,
+                    latency_ms: None,
+                    error: Some(e.to_string()),
+                },
+                Err(_) => Attempt {
+                    ok: false,
+                    latency_ms: None,
+                    error: Some(format!("timed out after {}ms", timeout.as_millis())),
+                },
+            };
+        result.sent += 1;
+        if !attempt.ok {
+            result.failed += 1;
+        }
+        result.attempts.push(attempt);
+    }
+
+    result.reachable = !latencies.is_empty();
+    result.latency = latency_stats(&latencies);
+    Ok(result)
+}
+
+// === Tool Functions ===
+
+pub async fn probe_endpoint(
+    egress: &Egress,
+    params: ProbeParams,
+) -> Result<CallToolResult, McpError> {
+    let count = params.count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+    let timeout = Duration::from_millis(params.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS).max(1));
+
+    let result = probe(egress, &params.host, params.port, count, timeout).await?;
+
+    let json = serde_json::to_string_pretty(&result)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+pub async fn check_endpoints(
+    egress: &Egress,
+    endpoints: &BTreeMap<String, EndpointConfig>,
+    params: CheckEndpointsParams,
+) -> Result<CallToolResult, McpError> {
+    if endpoints.is_empty() {
+        return Err(internal_error(
+            "No endpoints configured (add [endpoints.<name>] tables to tools.toml)",
+        ));
+    }
+
+    let selected: Vec<(&String, &EndpointConfig)> = match &params.names {
+        Some(names) => {
+            let unknown: Vec<&str> = names
+                .iter()
+                .filter(|name| !endpoints.contains_key(*name))
+                .map(String::as_str)
+                .collect();
+            if !unknown.is_empty() {
+                let known: Vec<&str> = endpoints.keys().map(String::as_str).collect();
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Unknown endpoint(s): {} (configured: {})",
+                        unknown.join(", "),
+                        known.join(", ")
+                    ),
+                    None,
+                ));
+            }
+            endpoints
+                .iter()
+                .filter(|(name, _)| names.contains(name))
+                .collect()
+        }
+        None => endpoints.iter().collect(),
+    };
+
+    let results = join_all(selected.iter().map(|(_, endpoint)| async move {
+        let timeout =
+            Duration::from_millis(endpoint.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS).max(1));
+        let count = endpoint.count.unwrap_or(1).clamp(1, MAX_COUNT);
+        match probe(egress, &endpoint.host, endpoint.port, count, timeout).await {
+            Ok(result) => result,
+            Err(e) => ProbeResult {
+                error: Some(e.message.to_string()),
+                ..ProbeResult::new(&endpoint.host, endpoint.port)
+            },
+        }
+    }))
+    .await;
+
+    let mut up = 0;
+    let mut report = serde_json::Map::new();
+    for ((name, _), mut result) in selected.into_iter().zip(results) {
+        if result.reachable {
+            up += 1;
+        }
+        // Per-attempt detail is noise in an overview
+        result.attempts.clear();
+        let value = serde_json::to_value(&result)
+            .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+        report.insert(name.clone(), value);
+    }
+
+    let json = serde_json::to_string_pretty(&serde_json::json!({
+        "up": up,
+        "down": report.len() - up,
+        "endpoints": report,
+    }))
+    .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        assert_eq!(latency_stats(&[]), None);
+        let stats = latency_stats(&[10.0, 14.0, 12.0]).unwrap();
+        assert_eq!(stats.min_ms, 10.0);
+        assert_eq!(stats.avg_ms, 12.0);
+        assert_eq!(stats.max_ms, 14.0);
+        assert_eq!(stats.jitter_ms, 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_probe_local_listener() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { while let Ok((_stream, _)) = listener.accept().await {} });
+
+        let egress = Egress::new(&crate::config::Config::default());
+        let result = probe(&egress, "127.0.0.1", port, 2, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(result.reachable);
+        assert_eq!((result.sent, result.failed), (2, 0));
+
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = closed.local_addr().unwrap().port();
+        drop(closed);
+        let result = probe(&egress, "127.0.0.1", port, 1, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(!result.reachable);
+        assert_eq!(result.failed, 1);
+    }
+}
@@ -0,0 +1,600 @@
+//! Socket sensors - listening ports and active connections (Linux)
+//!
+//! Sockets come from `/proc/net/{tcp,tcp6,udp,udp6,unix}`. Their owning processes are
+//! found by matching socket inodes against the `socket:[inode]` links in
+//! `/proc/<pid>/fd`, which only works for processes we may inspect (our own, or all
+//! of them as root); other sockets are listed without a PID.
+//!
+//! `/proc/net/tcp` has no byte counts, so per-socket TCP traffic is read from a
+//! `sock_diag` netlink dump instead, which includes each socket's `tcp_info`.
+
+use crate::shared::internal_error;
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const DEFAULT_LIMIT: usize = 200;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListeningPortsParams {
+    #[schemars(description = "Only this port")]
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[schemars(description = "Only sockets of this process")]
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[schemars(description = "Only 'tcp' or 'udp' (IPv4 and IPv6)")]
+    #[serde(default)]
+    pub protocol: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConnectionsParams {
+    #[schemars(description = "Only connections with this local or remote port")]
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[schemars(description = "Only sockets of this process")]
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[schemars(
+        description = "Only this state, e.g. ESTABLISHED, LISTEN, TIME_WAIT, CLOSE_WAIT (case-insensitive)"
+    )]
+    #[serde(default)]
+    pub state: Option<String>,
+    #[schemars(description = "Only 'tcp', 'udp' or 'unix'")]
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[schemars(description = "Maximum number of sockets to return (default 200)")]
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+// === Parsing ===
+
+/// One socket from `/proc/net`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Socket {
+    /// tcp, tcp6, udp, udp6 or unix
+    pub protocol: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local: Option<SocketAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<SocketAddr>,
+    /// Unix socket type (stream, dgram, seqpacket)
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<&'static str>,
+    /// Unix socket path; abstract names start with `@`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub state: &'static str,
+    #[serde(skip)]
+    pub inode: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+}
+
+impl Socket {
+    /// Accepting connections (TCP) or bound to a port without a peer (UDP)
+    fn is_listening(&self) -> bool {
+        match self.protocol {
+            "tcp" | "tcp6" => self.state == "LISTEN",
+            "udp" | "udp6" => self.remote.is_some_and(|r| r.port() == 0),
+            _ => self.state == "LISTEN",
+        }
+    }
+
+    /// `tcp6` counts as `tcp`, `udp6` as `udp`
+    fn is_protocol(&self, protocol: &str) -> bool {
+        self.protocol.trim_end_matches('6') == protocol.to_ascii_lowercase()
+    }
+
+    fn has_port(&self, port: u16) -> bool {
+        self.local.is_some_and(|a| a.port() == port)
+            || self.remote.is_some_and(|a| a.port() == port)
+    }
+}
+
+fn tcp_state(code: u8) -> &'static str {
+    match code {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        0x0C => "NEW_SYN_RECV",
+        _ => "UNKNOWN",
+    }
+}
+
+/// `0100007F:0BB8` - the address is printed as native-endian 32-bit words, the port in hex
+fn parse_address(field: &str) -> Option<SocketAddr> {
+    let (ip, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in ip.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => {
+            let v6 = Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?);
+            // Dual-stack sockets show IPv4 peers as ::ffff:a.b.c.d
+            v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Parse `/proc/net/{tcp,tcp6,udp,udp6}` contents
+fn parse_inet(protocol: &'static str, contents: &str) -> Vec<Socket> {
+    let udp = protocol.starts_with("udp");
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local = parse_address(fields.get(1)?)?;
+            let remote = parse_address(fields.get(2)?)?;
+            let code = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+            let inode = fields.get(9)?.parse().ok()?;
+            let state = match (udp, code) {
+                (true, 0x07) => "UNCONN",
+                _ => tcp_state(code),
+            };
+            Some(Socket {
+                protocol,
+                local: Some(local),
+                remote: Some(remote),
+                kind: None,
+                path: None,
+                state,
+                inode,
+                pid: None,
+                process: None,
+            })
+        })
+        .collect()
+}
+
+/// Parse `/proc/net/unix` contents
+fn parse_unix(contents: &str) -> Vec<Socket> {
+    const ACCEPTCON: u32 = 0x0001_0000;
+
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+            let kind = match fields.get(4).copied()? {
+                "0001" => "stream",
+                "0002" => "dgram",
+                "0005" => "seqpacket",
+                _ => "other",
+            };
+            let state = if flags & ACCEPTCON != 0 {
+                "LISTEN"
+            } else {
+                match fields.get(5).copied()? {
+                    "01" => "UNCONNECTED",
+                    "02" => "CONNECTING",
+                    "03" => "CONNECTED",
+                    "04" => "DISCONNECTING",
+                    _ => "UNKNOWN",
+                }
+            };
+            Some(Socket {
+                protocol: "unix",
+                local: None,
+                remote: None,
+                kind: Some(kind),
+                path: (fields.len() > 7).then(|| fields[7..].join(" ")),
+                state,
+                inode: fields.get(6)?.parse().ok()?,
+                pid: None,
+                process: None,
+            })
+        })
+        .collect()
+}
+
+/// Every socket in the current network namespace, with owners filled in where visible
+fn read_sockets(unix: bool) -> Result<Vec<Socket>, McpError> {
+    let mut sockets = Vec::new();
+    let tables: [(&'static str, &str); 4] = [
+        ("tcp", "/proc/net/tcp"),
+        ("tcp6", "/proc/net/tcp6"),
+        ("udp", "/proc/net/udp"),
+        ("udp6", "/proc/net/udp6"),
+    ];
+    for (protocol, path) in tables {
+        // tcp6/udp6 are missing when IPv6 is disabled
+        match fs::read_to_string(path) {
+            Ok(contents) => sockets.extend(parse_inet(protocol, &contents)),
+            Err(e) if protocol.ends_with('6') => tracing::debug!("Skipping {}: {}", path, e),
+            Err(e) => return Err(internal_error(format!("Failed to read {}: {}", path, e))),
+        }
+    }
+    if unix {
+        let contents = fs::read_to_string("/proc/net/unix")
+            .map_err(|e| internal_error(format!("Failed to read /proc/net/unix: {}", e)))?;
+        sockets.extend(parse_unix(&contents));
+    }
+
+    let owners = socket_owners();
+    for socket in &mut sockets {
+        if let Some((pid, name)) = owners.get(&socket.inode) {
+            socket.pid = Some(*pid);
+            socket.process = Some(name.clone());
+        }
+    }
+    Ok(sockets)
+}
+
+/// Socket inode -> (PID, process name), from the fd links we are allowed to read
+pub fn socket_owners() -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return owners;
+    };
+
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let mut name = None;
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            let name = name.get_or_insert_with(|| {
+                fs::read_to_string(entry.path().join("comm"))
+                    .map(|comm| comm.trim().to_string())
+                    .unwrap_or_default()
+            });
+            // A socket shared after fork belongs to the lowest PID, usually the parent
+            owners.entry(inode).or_insert((pid, name.clone()));
+        }
+    }
+    owners
+}
+
+// === TCP Byte Counters ===
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const INET_DIAG_INFO: u16 = 2;
+const NLMSG_HEADER_LEN: usize = 16;
+
+/// `struct inet_diag_msg`: family, state, timer, retrans, the 48-byte socket id, then
+/// expires, rqueue, wqueue, uid and inode
+const DIAG_MSG_INODE: usize = 68;
+const DIAG_MSG_LEN: usize = 72;
+
+/// `tcpi_bytes_acked` and `tcpi_bytes_received` in `struct tcp_info` (Linux 4.1+)
+const TCPI_BYTES_ACKED: usize = 120;
+const TCPI_BYTES_RECEIVED: usize = 128;
+
+#[repr(C)]
+struct InetDiagReqV2 {
+    family: u8,
+    protocol: u8,
+    ext: u8,
+    pad: u8,
+    states: u32,
+    /// `struct inet_diag_sockid`, all zero for a dump
+    id: [u8; 48],
+}
+
+#[repr(C)]
+struct DiagRequest {
+    header: libc::nlmsghdr,
+    body: InetDiagReqV2,
+}
+
+/// What a TCP socket has sent (and had acknowledged) and received so far
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TcpBytes {
+    pub sent: u64,
+    pub received: u64,
+}
+
+fn ne_u16(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn ne_u32(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn ne_u64(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_ne_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn nl_align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Inode and byte counters from one `inet_diag_msg` and its attributes
+fn parse_diag_msg(msg: &[u8]) -> Option<(u64, TcpBytes)> {
+    let inode = ne_u32(msg, DIAG_MSG_INODE)?;
+    let mut attrs = msg.get(DIAG_MSG_LEN..)?;
+    while attrs.len() >= 4 {
+        let len = ne_u16(attrs, 0)? as usize;
+        if len < 4 || len > attrs.len() {
+            break;
+        }
+        if ne_u16(attrs, 2)? == INET_DIAG_INFO {
+            let info = &attrs[4..len];
+            let bytes = TcpBytes {
+                sent: ne_u64(info, TCPI_BYTES_ACKED)?,
+                received: ne_u64(info, TCPI_BYTES_RECEIVED)?,
+            };
+            // Sockets in TIME_WAIT no longer have an inode
+            return (inode != 0).then_some((inode as u64, bytes));
+        }
+        attrs = &attrs[nl_align(len).min(attrs.len())..];
+    }
+    None
+}
+
+/// Walk the netlink messages in one datagram. Returns true once the dump is complete.
+fn parse_diag_datagram(mut buf: &[u8], counters: &mut HashMap<u64, TcpBytes>) -> io::Result<bool> {
+    while buf.len() >= NLMSG_HEADER_LEN {
+        let len = ne_u32(buf, 0).unwrap_or(0) as usize;
+        if len < NLMSG_HEADER_LEN || len > buf.len() {
+            break;
+        }
+        match ne_u16(buf, 4).unwrap_or(0) as libc::c_int {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let errno = ne_u32(buf, NLMSG_HEADER_LEN).unwrap_or(0) as i32;
+                return Err(io::Error::from_raw_os_error(-errno));
+            }
+            _ => {
+                if let Some((inode, bytes)) = parse_diag_msg(&buf[NLMSG_HEADER_LEN..len]) {
+                    counters.insert(inode, bytes);
+                }
+            }
+        }
+        buf = &buf[nl_align(len).min(buf.len())..];
+    }
+    Ok(false)
+}
+
+fn dump_tcp_family(
+    socket: &OwnedFd,
+    family: libc::c_int,
+    counters: &mut HashMap<u64, TcpBytes>,
+) -> io::Result<()> {
+    // SAFETY: both are plain C structs for which all-zero is a valid value
+    let mut request: DiagRequest = unsafe { std::mem::zeroed() };
+    request.header.nlmsg_len = std::mem::size_of::<DiagRequest>() as u32;
+    request.header.nlmsg_type = SOCK_DIAG_BY_FAMILY;
+    request.header.nlmsg_flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+    request.body.family = family as u8;
+    request.body.protocol = libc::IPPROTO_TCP as u8;
+    request.body.ext = 1 << (INET_DIAG_INFO - 1);
+    request.body.states = !0;
+    let mut kernel: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+    // SAFETY: the pointers and lengths describe live, correctly sized values
+    let sent = unsafe {
+        libc::sendto(
+            socket.as_raw_fd(),
+            &request as *const DiagRequest as *const libc::c_void,
+            std::mem::size_of::<DiagRequest>(),
+            0,
+            &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        // SAFETY: the kernel writes at most `buf.len()` bytes into `buf`
+        let len = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if len < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if len == 0 || parse_diag_datagram(&buf[..len as usize], counters)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Socket inode -> byte counters for every TCP socket in the network namespace. No
+/// privileges are needed, but only Linux 4.1 and later report the counters.
+pub fn tcp_bytes() -> io::Result<HashMap<u64, TcpBytes>> {
+    // SAFETY: plain socket(2) call; the descriptor is owned right after
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` is a fresh descriptor nothing else owns
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut counters = HashMap::new();
+    dump_tcp_family(&socket, libc::AF_INET, &mut counters)?;
+    dump_tcp_family(&socket, libc::AF_INET6, &mut counters)?;
+    Ok(counters)
+}
+
+// === Tool Functions ===
+
+pub async fn list_listening_ports(
+    params: ListeningPortsParams,
+) -> Result<CallToolResult, McpError> {
+    let mut sockets: Vec<Socket> = read_sockets(false)?
+        .into_iter()
+        .filter(|s| s.is_listening())
+        .filter(|s| {
+            params
+                .port
+                .is_none_or(|port| s.local.is_some_and(|a| a.port() == port))
+        })
+        .filter(|s| params.pid.is_none_or(|pid| s.pid == Some(pid)))
+        .filter(|s| params.protocol.as_deref().is_none_or(|p| s.is_protocol(p)))
+        // A listening socket's peer is always the wildcard
+        .map(|s| Socket { remote: None, ..s })
+        .collect();
+    sockets.sort_by_key(|s| (s.local.map(|a| a.port()), s.protocol));
+
+    let json = serde_json::to_string_pretty(&serde_json::json!({
+        "count": sockets.len(),
+        "listening": sockets,
+    }))
+    .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+pub async fn list_connections(params: ConnectionsParams) -> Result<CallToolResult, McpError> {
+    let unix = params
+        .protocol
+        .as_deref()
+        .is_none_or(|p| p.eq_ignore_ascii_case("unix"));
+    let sockets: Vec<Socket> = read_sockets(unix)?
+        .into_iter()
+        .filter(|s| params.port.is_none_or(|port| s.has_port(port)))
+        .filter(|s| params.pid.is_none_or(|pid| s.pid == Some(pid)))
+        .filter(|s| {
+            params
+                .state
+                .as_deref()
+                .is_none_or(|state| s.state.eq_ignore_ascii_case(state))
+        })
+        .filter(|s| params.protocol.as_deref().is_none_or(|p| s.is_protocol(p)))
+        .collect();
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    let mut output = serde_json::json!({
+        "count": sockets.len(),
+        "connections": sockets.iter().take(limit).collect::<Vec<_>>(),
+    });
+    if sockets.len() > limit {
+        output["note"] = format!(
+            "Showing {} of {}; filter by port, pid, state or protocol, or raise limit",
+            limit,
+            sockets.len()
+        )
+        .into();
+    }
+
+    let json = serde_json::to_string_pretty(&output)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_parse_inet() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+            0: 0100007F:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4242 1 0000000000000000 100 0 0 10 0\n   \
+            1: 0100007F:C350 0100007F:1538 01 00000000:00000000 00:00000000 00000000  1000        0 4343 1 0000000000000000 20 4 30 10 -1\n";
+        let sockets = parse_inet("tcp", tcp);
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].local, Some("127.0.0.1:3000".parse().unwrap()));
+        assert_eq!(sockets[0].state, "LISTEN");
+        assert_eq!(sockets[0].inode, 4242);
+        assert!(sockets[0].is_listening());
+        assert_eq!(sockets[1].remote, Some("127.0.0.1:5432".parse().unwrap()));
+        assert_eq!(sockets[1].state, "ESTABLISHED");
+        assert!(sockets[1].has_port(5432));
+
+        let tcp6 = "header\n   \
+            0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 99 1\n   \
+            1: 0000000000000000FFFF00000100007F:1F90 0000000000000000FFFF00000100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 100 1\n";
+        let sockets = parse_inet("tcp6", tcp6);
+        assert_eq!(sockets[0].local, Some("[::1]:8080".parse().unwrap()));
+        assert_eq!(sockets[1].local, Some("127.0.0.1:8080".parse().unwrap()));
+        assert!(sockets[1].is_protocol("TCP"));
+    }
+
+    #[test]
+    fn test_parse_unix() {
+        let unix = "Num       RefCount Protocol Flags    Type St Inode Path\n\
+            0000000000000000: 00000002 00000000 00010000 0001 01 20511 /run/user/1000/bus\n\
+            0000000000000000: 00000003 00000000 00000000 0001 03 20977\n";
+        let sockets = parse_unix(unix);
+        assert_eq!(sockets[0].state, "LISTEN");
+        assert_eq!(sockets[0].path.as_deref(), Some("/run/user/1000/bus"));
+        assert_eq!(sockets[1].state, "CONNECTED");
+        assert_eq!(sockets[1].kind, Some("stream"));
+        assert_eq!(sockets[1].path, None);
+    }
+
+    #[test]
+    fn test_tcp_bytes() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        client.write_all(&[7u8; 1000]).unwrap();
+        server.read_exact(&mut [0u8; 1000]).unwrap();
+
+        let link = fs::read_link(format!("/proc/self/fd/{}", server.as_raw_fd())).unwrap();
+        let inode: u64 = link
+            .to_str()
+            .and_then(|t| t.strip_prefix("socket:["))
+            .and_then(|t| t.strip_suffix(']'))
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        let counters = tcp_bytes().unwrap();
+        assert_eq!(counters[&inode].received, 1000);
+    }
+}
@@ -0,0 +1,196 @@
+//! Shared sysinfo state, refreshed incrementally in the background
+//!
+//! Building a fresh `System` per call is slow, and CPU percentages are meaningless
+//! without two refreshes spaced apart. The server keeps one long-lived copy instead,
+//! a background task refreshes it on a fixed tick, and tools read whatever the last
+//! refresh saw. Per-interface network deltas from recent refreshes are kept as well,
+//! so traffic rates can be given over a window longer than one tick.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+use sysinfo::{
+    Components, CpuRefreshKind, Disks, MemoryRefreshKind, Networks, ProcessRefreshKind,
+    ProcessesToUpdate, System, UpdateKind,
+};
+
+/// How often CPU, memory, processes and network counters are refreshed
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Disks and temperatures change slowly, so they are refreshed every few ticks
+const SLOW_TICKS: u32 = 5;
+
+/// How far back network deltas are kept for rate queries
+pub const NETWORK_WINDOW: Duration = Duration::from_secs(60);
+
+/// Traffic on one interface, either during one refresh interval or summed over several
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Traffic {
+    pub received: u64,
+    pub transmitted: u64,
+    pub packets_received: u64,
+    pub packets_transmitted: u64,
+}
+
+#[derive(Debug)]
+struct NetworkDelta {
+    at: Instant,
+    elapsed: Duration,
+    interfaces: BTreeMap<String, Traffic>,
+}
+
+#[derive(Debug, Default)]
+struct NetworkDeltas {
+    last_refresh: Option<Instant>,
+    deltas: VecDeque<NetworkDelta>,
+}
+
+#[derive(Debug, Default)]
+pub struct SystemState {
+    system: Mutex<System>,
+    networks: Mutex<Networks>,
+    network_deltas: Mutex<NetworkDeltas>,
+    disks: Mutex<Disks>,
+    components: Mutex<Components>,
+}
+
+impl SystemState {
+    /// Take the first sample now and keep refreshing until the state is dropped
+    pub fn start(self: &Arc<Self>) {
+        self.refresh(true);
+
+        let state = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately and the first sample is already taken
+            interval.tick().await;
+
+            let mut tick: u32 = 0;
+            loop {
+                interval.tick().await;
+                tick = tick.wrapping_add(1);
+                let slow = tick.is_multiple_of(SLOW_TICKS);
+
+                let state: Weak<Self> = state.clone();
+                let refreshed = tokio::task::spawn_blocking(move || match state.upgrade() {
+                    Some(state) => {
+                        state.refresh(slow);
+                        true
+                    }
+                    None => false,
+                })
+                .await;
+                if !matches!(refreshed, Ok(true)) {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn refresh(&self, slow: bool) {
+        {
+            let mut system = lock(&self.system);
+            system.refresh_cpu_specifics(CpuRefreshKind::everything());
+            system.refresh_memory_specifics(MemoryRefreshKind::everything());
+            // Command lines and paths don't change, so they are read once per process
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing()
+                    .with_cpu()
+                    .with_memory()
+                    .with_exe(UpdateKind::OnlyIfNotSet)
+                    .with_cmd(UpdateKind::OnlyIfNotSet)
+                    .with_cwd(UpdateKind::OnlyIfNotSet),
+            );
+        }
+
+        {
+            let mut networks = lock(&self.networks);
+            networks.refresh(true);
+            let now = Instant::now();
+            let mut recent = lock(&self.network_deltas);
+            // The first refresh has nothing to compare against
+            if let Some(last) = recent.last_refresh.replace(now) {
+                let interfaces = networks
+                    .iter()
+                    .map(|(name, data)| {
+                        let traffic = Traffic {
+                            received: data.received(),
+                            transmitted: data.transmitted(),
+                            packets_received: data.packets_received(),
+                            packets_transmitted: data.packets_transmitted(),
+                        };
+                        (name.clone(), traffic)
+                    })
+                    .collect();
+                recent.deltas.push_back(NetworkDelta {
+                    at: now,
+                    elapsed: now.duration_since(last),
+                    interfaces,
+                });
+                while recent
+                    .deltas
+                    .front()
+                    .is_some_and(|d| now.duration_since(d.at) > NETWORK_WINDOW)
+                {
+                    recent.deltas.pop_front();
+                }
+            }
+        }
+
+        if slow {
+            lock(&self.disks).refresh(true);
+            lock(&self.components).refresh(true);
+        }
+    }
+
+    /// CPU, memory and processes. CPU figures cover the last refresh interval.
+    pub fn system(&self) -> MutexGuard<'_, System> {
+        lock(&self.system)
+    }
+
+    /// Network counters. Per-refresh deltas cover the last refresh interval.
+    pub fn networks(&self) -> MutexGuard<'_, Networks> {
+        lock(&self.networks)
+    }
+
+    /// Traffic per interface over the refresh intervals closest to the last `window` (at
+    /// most [`NETWORK_WINDOW`]), along with the time actually covered, which is zero
+    /// before the second refresh
+    pub fn network_traffic(&self, window: Duration) -> (Duration, BTreeMap<String, Traffic>) {
+        let recent = lock(&self.network_deltas);
+        let now = Instant::now();
+        let mut covered = Duration::ZERO;
+        let mut totals: BTreeMap<String, Traffic> = BTreeMap::new();
+        for delta in recent.deltas.iter().rev() {
+            // Stop at whichever number of whole intervals comes closest to the window
+            let overshoots = covered + delta.elapsed / 2 > window;
+            if (overshoots && !covered.is_zero()) || now.duration_since(delta.at) > NETWORK_WINDOW {
+                break;
+            }
+            covered += delta.elapsed;
+            for (name, traffic) in &delta.interfaces {
+                let total = totals.entry(name.clone()).or_default();
+                total.received += traffic.received;
+                total.transmitted += traffic.transmitted;
+                total.packets_received += traffic.packets_received;
+                total.packets_transmitted += traffic.packets_transmitted;
+            }
+        }
+        (covered, totals)
+    }
+
+    pub fn disks(&self) -> MutexGuard<'_, Disks> {
+        lock(&self.disks)
+    }
+
+    pub fn components(&self) -> MutexGuard<'_, Components> {
+        lock(&self.components)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -1,12 +1,13 @@
 //! System information sensors - CPU, memory, disk, processes, temps, users
 
+use super::state::SystemState;
+use super::traffic::{interface_rates, DEFAULT_WINDOW};
 use crate::shared::{format_bytes, format_duration, internal_error};
 use rmcp::{model::*, ErrorData as McpError};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sysinfo::{
-    Components, CpuRefreshKind, Disks, MemoryRefreshKind, Networks, RefreshKind, System, Users,
-};
+use std::collections::HashMap;
+use sysinfo::{Pid, Process, System, ThreadKind, Users};
 
 // === Parameter Types ===
 
@@ -32,19 +33,21 @@ pub struct ProcessIdParams {
     pub pid: u32,
 }
 
-// === Tool Functions ===
-
-pub async fn get_system_info() -> Result<CallToolResult, McpError> {
-    let mut sys = System::new_with_specifics(
-        RefreshKind::nothing()
-            .with_cpu(CpuRefreshKind::everything())
-            .with_memory(MemoryRefreshKind::everything()),
-    );
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ProcessTreeParams {
+    #[schemars(description = "PID to start the tree at (default: every top-level process)")]
+    #[serde(default)]
+    pub root_pid: Option<u32>,
+    #[schemars(description = "Levels to show below the root (default: all)")]
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
 
-    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-    sys.refresh_cpu_all();
+// === Tool Functions ===
 
-    let disks = Disks::new_with_refreshed_list();
+pub async fn get_system_info(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
+    let disks = state.disks();
 
     let cpu_count = sys.cpus().len();
     let cpu_usage: f32 = sys.cpus().iter().map(|c| c.cpu_usage()).sum::<f32>() / cpu_count as f32;
@@ -103,10 +106,11 @@ pub async fn get_system_info() -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn get_top_processes(params: TopProcessesParams) -> Result<CallToolResult, McpError> {
-    let mut sys = System::new_all();
-    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-    sys.refresh_all();
+pub async fn get_top_processes(
+    state: &SystemState,
+    params: TopProcessesParams,
+) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
 
     let count = params.count.unwrap_or(10);
     let sort_by = params.sort_by.unwrap_or_else(|| "cpu".to_string());
@@ -146,21 +150,17 @@ pub async fn get_top_processes(params: TopProcessesParams) -> Result<CallToolRes
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn find_process(params: FindProcessParams) -> Result<CallToolResult, McpError> {
-    let mut sys = System::new_all();
-    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-    sys.refresh_all();
+pub async fn find_process(
+    state: &SystemState,
+    params: FindProcessParams,
+) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
 
     let search = params.name.to_lowercase();
     let mut matches: Vec<_> = sys
         .processes()
         .values()
-        .filter(|p| {
-            p.name()
-                .to_string_lossy()
-                .to_lowercase()
-                .contains(&search)
-        })
+        .filter(|p| p.name().to_string_lossy().to_lowercase().contains(&search))
         .collect();
 
     matches.sort_by(|a, b| {
@@ -200,10 +200,11 @@ pub async fn find_process(params: FindProcessParams) -> Result<CallToolResult, M
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn get_process_details(params: ProcessIdParams) -> Result<CallToolResult, McpError> {
-    let mut sys = System::new_all();
-    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-    sys.refresh_all();
+pub async fn get_process_details(
+    state: &SystemState,
+    params: ProcessIdParams,
+) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
 
     let pid = sysinfo::Pid::from_u32(params.pid);
 
@@ -248,13 +249,311 @@ pub async fn get_process_details(params: ProcessIdParams) -> Result<CallToolResu
         }
     }
 
+    if let Some(tasks) = proc.tasks() {
+        // The main thread isn't among its tasks
+        output.push_str(&format!("Threads: {}\n", tasks.len() + 1));
+    }
+    drop(sys);
+
+    #[cfg(target_os = "linux")]
+    proc_details(params.pid, &mut output);
+
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn list_processes() -> Result<CallToolResult, McpError> {
-    let mut sys = System::new_all();
-    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
-    sys.refresh_all();
+/// Open files, cgroup, I/O counters and environment from `/proc`. Fields the
+/// process doesn't let us read (other users' processes) are left out.
+#[cfg(target_os = "linux")]
+fn proc_details(pid: u32, output: &mut String) {
+    let dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+
+    if let Ok(entries) = std::fs::read_dir(dir.join("fd")) {
+        output.push_str(&format!("Open Files: {}\n", entries.count()));
+    }
+
+    if let Ok(cgroup) = std::fs::read_to_string(dir.join("cgroup")) {
+        // `hierarchy:controllers:path`; cgroup v2 has a single `0::` line, v1 one per
+        // controller, mostly the root
+        let mut paths: Vec<&str> = Vec::new();
+        for path in cgroup.lines().filter_map(|line| line.splitn(3, ':').nth(2)) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        if paths.len() > 1 {
+            paths.retain(|path| *path != "/");
+        }
+        if !paths.is_empty() {
+            output.push_str(&format!("Cgroup: {}\n", paths.join(", ")));
+        }
+    }
+
+    if let Ok(io) = std::fs::read_to_string(dir.join("io")) {
+        let field = |name: &str| {
+            io.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+        output.push_str("I/O:\n");
+        if let (Some(read), Some(written)) = (field("read_bytes"), field("write_bytes")) {
+            output.push_str(&format!(
+                "  Disk: {} read, {} written\n",
+                format_bytes(read),
+                format_bytes(written)
+            ));
+        }
+        if let (Some(read), Some(written)) = (field("rchar"), field("wchar")) {
+            output.push_str(&format!(
+                "  Total (incl. cache, pipes, sockets): {} read, {} written\n",
+                format_bytes(read),
+                format_bytes(written)
+            ));
+        }
+    }
+
+    if let Ok(environ) = std::fs::read(dir.join("environ")) {
+        let vars: Vec<String> = environ
+            .split(|b| *b == 0)
+            .filter(|var| !var.is_empty())
+            .map(|var| redact_env(&String::from_utf8_lossy(var)))
+            .collect();
+        output.push_str(&format!("Environment ({} variables):\n", vars.len()));
+        for var in vars.iter().take(MAX_ENV_VARS) {
+            output.push_str(&format!("  {}\n", var));
+        }
+        if vars.len() > MAX_ENV_VARS {
+            output.push_str(&format!("  ... and {} more\n", vars.len() - MAX_ENV_VARS));
+        }
+    }
+}
+
+/// Environment variables listed by `get_process_details`
+#[cfg(target_os = "linux")]
+const MAX_ENV_VARS: usize = 100;
+
+/// Hide the value of variables whose name suggests a credential. The output
+/// redactor still runs afterwards and catches tokens under innocuous names.
+#[cfg(any(target_os = "linux", test))]
+fn redact_env(var: &str) -> String {
+    match var.split_once('=') {
+        Some((name, value)) if !value.is_empty() && is_secret_name(name) => {
+            format!("{}=[REDACTED]", name)
+        }
+        _ => var.to_string(),
+    }
+}
+
+/// Match whole `_`-separated words of the name, so `XAUTHORITY` or `KEYMAP` don't
+/// count as credentials
+#[cfg(any(target_os = "linux", test))]
+fn is_secret_name(name: &str) -> bool {
+    // Credentials wherever they appear, also glued on at the end (`PGPASSWORD`)
+    const SECRET_WORDS: &[&str] = &[
+        "SECRET",
+        "TOKEN",
+        "PASSWORD",
+        "PASSWD",
+        "CREDENTIAL",
+        "CREDENTIALS",
+        "PRIVATE",
+        "COOKIE",
+        "APIKEY",
+    ];
+    // Credentials only as the last word: `API_KEY`, but not `XDG_SESSION_TYPE`
+    // or `SSH_AUTH_SOCK`
+    const SECRET_SUFFIXES: &[&str] = &["KEY", "PASS", "AUTH", "SESSION"];
+
+    let name = name.to_ascii_uppercase();
+    let words: Vec<&str> = name.split('_').collect();
+    SECRET_WORDS
+        .iter()
+        .any(|word| words.contains(word) || name.ends_with(word))
+        || words
+            .last()
+            .is_some_and(|last| SECRET_SUFFIXES.contains(last))
+}
+
+pub async fn get_process_tree(
+    state: &SystemState,
+    params: ProcessTreeParams,
+) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
+
+    // Threads are listed as processes on Linux; they share their process's memory and
+    // are already counted in its CPU, so the tree leaves them out
+    let processes: HashMap<Pid, &Process> = sys
+        .processes()
+        .iter()
+        .filter(|(_, p)| p.thread_kind() != Some(ThreadKind::Userland))
+        .map(|(pid, p)| (*pid, p))
+        .collect();
+
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (pid, proc) in &processes {
+        match proc
+            .parent()
+            .filter(|parent| processes.contains_key(parent))
+        {
+            Some(parent) => children.entry(parent).or_default().push(*pid),
+            None => roots.push(*pid),
+        }
+    }
+
+    if let Some(root) = params.root_pid {
+        let root = Pid::from_u32(root);
+        if !processes.contains_key(&root) {
+            return Err(internal_error(format!("Process {} not found", root)));
+        }
+        roots = vec![root];
+    }
+
+    let mut tree = Tree {
+        processes: &processes,
+        children: &children,
+        totals: HashMap::new(),
+        max_depth: params.max_depth.unwrap_or(usize::MAX),
+        lines: Vec::new(),
+        omitted: 0,
+    };
+    for root in &roots {
+        tree.total(*root);
+    }
+    tree.sort(&mut roots);
+
+    let last = roots.len().saturating_sub(1);
+    for (i, root) in roots.iter().enumerate() {
+        tree.render(*root, "", i == last, 0, true);
+    }
+
+    let mut output = match params.root_pid {
+        Some(pid) => format!("Process tree for PID {}:\n\n", pid),
+        None => String::from("Process tree:\n\n"),
+    };
+    output.push_str("PID  Name  CPU%  Memory  [subtree: CPU%, memory, processes]\n\n");
+    for line in &tree.lines {
+        output.push_str(line);
+        output.push('\n');
+    }
+    if tree.omitted > 0 {
+        output.push_str(&format!("\n... and {} more processes\n", tree.omitted));
+    }
+
+    Ok(CallToolResult::success(vec![Content::text(output)]))
+}
+
+/// Lines printed by `get_process_tree` before the rest is summarized
+const MAX_TREE_LINES: usize = 300;
+
+/// Aggregated CPU, memory and process count of a subtree
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    cpu: f32,
+    memory: u64,
+    count: usize,
+}
+
+struct Tree<'a> {
+    processes: &'a HashMap<Pid, &'a Process>,
+    children: &'a HashMap<Pid, Vec<Pid>>,
+    totals: HashMap<Pid, Totals>,
+    max_depth: usize,
+    lines: Vec<String>,
+    omitted: usize,
+}
+
+impl Tree<'_> {
+    fn total(&mut self, pid: Pid) -> Totals {
+        if let Some(totals) = self.totals.get(&pid) {
+            return *totals;
+        }
+        // Placeholder first, so a PID-reuse loop in parent links can't recurse forever
+        self.totals.insert(pid, Totals::default());
+
+        let proc = self.processes[&pid];
+        let mut totals = Totals {
+            cpu: proc.cpu_usage(),
+            memory: proc.memory(),
+            count: 1,
+        };
+        for child in self.children.get(&pid).into_iter().flatten() {
+            let child = self.total(*child);
+            totals.cpu += child.cpu;
+            totals.memory += child.memory;
+            totals.count += child.count;
+        }
+        self.totals.insert(pid, totals);
+        totals
+    }
+
+    /// Busiest subtrees first, then by PID
+    fn sort(&self, pids: &mut [Pid]) {
+        pids.sort_by(|a, b| {
+            let (ta, tb) = (self.totals[a], self.totals[b]);
+            tb.cpu
+                .partial_cmp(&ta.cpu)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(tb.memory.cmp(&ta.memory))
+                .then(a.cmp(b))
+        });
+    }
+
+    fn render(&mut self, pid: Pid, prefix: &str, last: bool, depth: usize, root: bool) {
+        let totals = self.totals[&pid];
+        if self.lines.len() >= MAX_TREE_LINES {
+            self.omitted += totals.count;
+            return;
+        }
+
+        let proc = self.processes[&pid];
+        let branch = match (root, last) {
+            (true, _) => "",
+            (false, true) => "└─ ",
+            (false, false) => "├─ ",
+        };
+        let mut line = format!(
+            "{}{}{} {}  {:.1}%  {}",
+            prefix,
+            branch,
+            pid,
+            proc.name().to_string_lossy(),
+            proc.cpu_usage(),
+            format_bytes(proc.memory())
+        );
+        if totals.count > 1 {
+            line.push_str(&format!(
+                "  [{:.1}%, {}, {} processes]",
+                totals.cpu,
+                format_bytes(totals.memory),
+                totals.count
+            ));
+        }
+        self.lines.push(line);
+
+        let mut children = self.children.get(&pid).cloned().unwrap_or_default();
+        if children.is_empty() {
+            return;
+        }
+        if depth >= self.max_depth {
+            self.omitted += totals.count - 1;
+            return;
+        }
+        self.sort(&mut children);
+
+        let prefix = match (root, last) {
+            (true, _) => prefix.to_string(),
+            (false, true) => format!("{}   ", prefix),
+            (false, false) => format!("{}│  ", prefix),
+        };
+        let last_child = children.len() - 1;
+        for (i, child) in children.into_iter().enumerate() {
+            self.render(child, &prefix, i == last_child, depth + 1, false);
+        }
+    }
+}
+
+pub async fn list_processes(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let sys = state.system();
 
     let mut processes: Vec<_> = sys.processes().values().collect();
     processes.sort_by(|a, b| {
@@ -292,8 +591,9 @@ pub async fn list_processes() -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn get_network_stats() -> Result<CallToolResult, McpError> {
-    let networks = Networks::new_with_refreshed_list();
+pub async fn get_network_stats(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let (covered, rates) = interface_rates(state, DEFAULT_WINDOW);
+    let networks = state.networks();
 
     let mut output = String::from("Network Interface Statistics:\n\n");
 
@@ -302,6 +602,22 @@ pub async fn get_network_stats() -> Result<CallToolResult, McpError> {
     } else {
         for (name, data) in networks.iter() {
             output.push_str(&format!("{}:\n", name));
+            if let Some(rate) = rates.iter().find(|r| &r.name == name) {
+                if !covered.is_zero() {
+                    output.push_str(&format!(
+                        "  Receiving: {}/s ({:.1} packets/s, last {}s)\n",
+                        format_bytes(rate.received_bytes_per_sec as u64),
+                        rate.received_packets_per_sec,
+                        covered.as_secs()
+                    ));
+                    output.push_str(&format!(
+                        "  Sending: {}/s ({:.1} packets/s, last {}s)\n",
+                        format_bytes(rate.transmitted_bytes_per_sec as u64),
+                        rate.transmitted_packets_per_sec,
+                        covered.as_secs()
+                    ));
+                }
+            }
             output.push_str(&format!(
                 "  Received: {}\n",
                 format_bytes(data.total_received())
@@ -310,12 +626,18 @@ pub async fn get_network_stats() -> Result<CallToolResult, McpError> {
                 "  Transmitted: {}\n",
                 format_bytes(data.total_transmitted())
             ));
-            output.push_str(&format!("  Packets In: {}\n", data.total_packets_received()));
+            output.push_str(&format!(
+                "  Packets In: {}\n",
+                data.total_packets_received()
+            ));
             output.push_str(&format!(
                 "  Packets Out: {}\n",
                 data.total_packets_transmitted()
             ));
-            output.push_str(&format!("  Errors In: {}\n", data.total_errors_on_received()));
+            output.push_str(&format!(
+                "  Errors In: {}\n",
+                data.total_errors_on_received()
+            ));
             output.push_str(&format!(
                 "  Errors Out: {}\n",
                 data.total_errors_on_transmitted()
@@ -327,8 +649,8 @@ pub async fn get_network_stats() -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn get_component_temps() -> Result<CallToolResult, McpError> {
-    let components = Components::new_with_refreshed_list();
+pub async fn get_component_temps(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let components = state.components();
 
     let mut output = String::from("Component Temperatures:\n\n");
 
@@ -352,8 +674,8 @@ pub async fn get_component_temps() -> Result<CallToolResult, McpError> {
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
 
-pub async fn get_disk_info() -> Result<CallToolResult, McpError> {
-    let disks = Disks::new_with_refreshed_list();
+pub async fn get_disk_info(state: &SystemState) -> Result<CallToolResult, McpError> {
+    let disks = state.disks();
 
     let mut output = String::from("Disk Usage:\n\n");
 
@@ -405,3 +727,29 @@ pub async fn get_users() -> Result<CallToolResult, McpError> {
 
     Ok(CallToolResult::success(vec![Content::text(output)]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_env() {
+        assert_eq!(redact_env("GITHUB_TOKEN=abc123"), "GITHUB_TOKEN=[REDACTED]");
+        assert_eq!(redact_env("db_password=hunter2"), "db_password=[REDACTED]");
+        assert_eq!(redact_env("HOME=/home/me"), "HOME=/home/me");
+        assert_eq!(redact_env("API_KEY="), "API_KEY=");
+        assert_eq!(redact_env("PGPASSWORD=hunter2"), "PGPASSWORD=[REDACTED]");
+        assert_eq!(
+            redact_env("AWS_SECRET_ACCESS_KEY=abc"),
+            "AWS_SECRET_ACCESS_KEY=[REDACTED]"
+        );
+        for var in [
+            "XDG_SESSION_TYPE=wayland",
+            "XAUTHORITY=/run/user/1000/xauth",
+            "KEYMAP=us",
+            "SSH_AUTH_SOCK=/run/user/1000/ssh-agent.socket",
+        ] {
+            assert_eq!(redact_env(var), var);
+        }
+    }
+}
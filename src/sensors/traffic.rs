@@ -0,0 +1,253 @@
+//! Network throughput
+//!
+//! Interface rates add up the per-refresh deltas the shared state keeps, so they are
+//! available without waiting. On Linux the busiest processes are found by reading the
+//! TCP byte counters of every socket twice, a second apart, and adding the differences
+//! up per owning process.
+
+use super::state::{SystemState, NETWORK_WINDOW};
+use crate::shared::internal_error;
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Window used where callers don't choose one
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+
+const DEFAULT_TOP_PROCESSES: usize = 5;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NetworkRatesParams {
+    #[schemars(description = "Seconds to average interface rates over (default 10, at most 60)")]
+    #[serde(default)]
+    pub window_secs: Option<u64>,
+    #[schemars(
+        description = "Number of processes with the most TCP traffic to list, sampled over one second (default 5, 0 to skip; Linux only)"
+    )]
+    #[serde(default)]
+    pub top_processes: Option<usize>,
+}
+
+// === Rates ===
+
+#[derive(Debug, Serialize)]
+pub struct InterfaceRate {
+    pub name: String,
+    pub received_bytes_per_sec: f64,
+    pub transmitted_bytes_per_sec: f64,
+    pub received_packets_per_sec: f64,
+    pub transmitted_packets_per_sec: f64,
+}
+
+fn per_sec(count: u64, secs: f64) -> f64 {
+    if secs <= 0.0 {
+        return 0.0;
+    }
+    (count as f64 / secs * 10.0).round() / 10.0
+}
+
+/// Rates per interface over about `window`, busiest first, with the time actually
+/// covered. Nothing is covered until the state has refreshed twice.
+pub fn interface_rates(state: &SystemState, window: Duration) -> (Duration, Vec<InterfaceRate>) {
+    let (covered, traffic) = state.network_traffic(window);
+    let secs = covered.as_secs_f64();
+    let mut rates: Vec<InterfaceRate> = traffic
+        .into_iter()
+        .map(|(name, t)| InterfaceRate {
+            name,
+            received_bytes_per_sec: per_sec(t.received, secs),
+            transmitted_bytes_per_sec: per_sec(t.transmitted, secs),
+            received_packets_per_sec: per_sec(t.packets_received, secs),
+            transmitted_packets_per_sec: per_sec(t.packets_transmitted, secs),
+        })
+        .collect();
+    rates.sort_by(|a, b| {
+        let total = |r: &InterfaceRate| r.received_bytes_per_sec + r.transmitted_bytes_per_sec;
+        total(b)
+            .total_cmp(&total(a))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    (covered, rates)
+}
+
+#[cfg(target_os = "linux")]
+mod talkers {
+    use super::per_sec;
+    use crate::sensors::sockets::{socket_owners, tcp_bytes, TcpBytes};
+    use serde::Serialize;
+    use std::collections::HashMap;
+    use std::time::{Duration, Instant};
+
+    pub const SAMPLE: Duration = Duration::from_secs(1);
+
+    #[derive(Debug, Serialize)]
+    pub struct ProcessRate {
+        pub pid: u32,
+        pub name: String,
+        pub received_bytes_per_sec: f64,
+        pub transmitted_bytes_per_sec: f64,
+        /// TCP sockets that carried traffic during the sample
+        pub active_connections: usize,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct Talkers {
+        pub sample_secs: f64,
+        pub processes: Vec<ProcessRate>,
+        /// Traffic on sockets whose owner we may not inspect
+        pub unattributed_received_bytes_per_sec: f64,
+        pub unattributed_transmitted_bytes_per_sec: f64,
+    }
+
+    /// Sum per-socket differences between two dumps by owning process
+    fn attribute(
+        before: &HashMap<u64, TcpBytes>,
+        after: &HashMap<u64, TcpBytes>,
+        owners: &HashMap<u64, (u32, String)>,
+        secs: f64,
+        limit: usize,
+    ) -> Talkers {
+        let mut by_pid: HashMap<u32, (String, TcpBytes, usize)> = HashMap::new();
+        let mut unattributed = TcpBytes::default();
+
+        for (inode, now) in after {
+            // Sockets opened during the sample started from zero
+            let then = before.get(inode).copied().unwrap_or_default();
+            let sent = now.sent.saturating_sub(then.sent);
+            let received = now.received.saturating_sub(then.received);
+            if sent == 0 && received == 0 {
+                continue;
+            }
+            let total = match owners.get(inode) {
+                Some((pid, name)) => {
+                    let entry = by_pid
+                        .entry(*pid)
+                        .or_insert_with(|| (name.clone(), TcpBytes::default(), 0));
+                    entry.2 += 1;
+                    &mut entry.1
+                }
+                None => &mut unattributed,
+            };
+            total.sent += sent;
+            total.received += received;
+        }
+
+        let mut processes: Vec<ProcessRate> = by_pid
+            .into_iter()
+            .map(|(pid, (name, bytes, active_connections))| ProcessRate {
+                pid,
+                name,
+                received_bytes_per_sec: per_sec(bytes.received, secs),
+                transmitted_bytes_per_sec: per_sec(bytes.sent, secs),
+                active_connections,
+            })
+            .collect();
+        processes.sort_by(|a, b| {
+            let total = |p: &ProcessRate| p.received_bytes_per_sec + p.transmitted_bytes_per_sec;
+            total(b)
+                .total_cmp(&total(a))
+                .then_with(|| a.pid.cmp(&b.pid))
+        });
+        processes.truncate(limit);
+
+        Talkers {
+            sample_secs: secs,
+            processes,
+            unattributed_received_bytes_per_sec: per_sec(unattributed.received, secs),
+            unattributed_transmitted_bytes_per_sec: per_sec(unattributed.sent, secs),
+        }
+    }
+
+    pub async fn sample(limit: usize) -> Result<Talkers, String> {
+        let started = Instant::now();
+        let before = tokio::task::spawn_blocking(tcp_bytes)
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("sock_diag dump failed: {}", e))?;
+        tokio::time::sleep(SAMPLE).await;
+        let (after, owners) = tokio::task::spawn_blocking(|| (tcp_bytes(), socket_owners()))
+            .await
+            .map_err(|e| e.to_string())?;
+        let after = after.map_err(|e| format!("sock_diag dump failed: {}", e))?;
+        let secs = (started.elapsed().as_secs_f64() * 100.0).round() / 100.0;
+        Ok(attribute(&before, &after, &owners, secs, limit))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_attribute() {
+            let bytes = |sent, received| TcpBytes { sent, received };
+            let before = HashMap::from([(1, bytes(100, 100)), (2, bytes(0, 0))]);
+            let after = HashMap::from([
+                (1, bytes(300, 100)),
+                (2, bytes(0, 50)),
+                (3, bytes(40, 0)),
+                (4, bytes(0, 10)),
+            ]);
+            let owners = HashMap::from([
+                (1, (10, "curl".to_string())),
+                (2, (20, "ssh".to_string())),
+                (3, (10, "curl".to_string())),
+            ]);
+
+            let talkers = attribute(&before, &after, &owners, 2.0, 5);
+            assert_eq!(talkers.processes.len(), 2);
+            assert_eq!(talkers.processes[0].pid, 10);
+            assert_eq!(talkers.processes[0].transmitted_bytes_per_sec, 120.0);
+            assert_eq!(talkers.processes[0].active_connections, 2);
+            assert_eq!(talkers.processes[1].received_bytes_per_sec, 25.0);
+            assert_eq!(talkers.unattributed_received_bytes_per_sec, 5.0);
+        }
+    }
+}
+
+// === Tool Functions ===
+
+pub async fn get_network_rates(
+    state: &SystemState,
+    params: NetworkRatesParams,
+) -> Result<CallToolResult, McpError> {
+    let window = params
+        .window_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_WINDOW)
+        .clamp(Duration::from_secs(1), NETWORK_WINDOW);
+    let top = params.top_processes.unwrap_or(DEFAULT_TOP_PROCESSES);
+
+    let (covered, interfaces) = interface_rates(state, window);
+    let mut json = serde_json::json!({
+        "window_secs": (covered.as_secs_f64() * 10.0).round() / 10.0,
+        "interfaces": interfaces,
+    });
+    if covered.is_zero() {
+        json["note"] = serde_json::json!(
+            "No samples yet; interface rates are available a few seconds after startup"
+        );
+    }
+
+    if top > 0 {
+        #[cfg(target_os = "linux")]
+        {
+            json["top_processes"] = match talkers::sample(top).await {
+                Ok(talkers) => serde_json::to_value(talkers)
+                    .map_err(|e| internal_error(format!("Serialization error: {}", e)))?,
+                Err(e) => serde_json::json!({ "error": e }),
+            };
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            json["top_processes"] =
+                serde_json::json!({ "error": "Per-process traffic is only available on Linux" });
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&json)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
@@ -1,10 +1,20 @@
-//! Weather sensors via wttr.in API
+//! Weather sensors via wttr.in or Open-Meteo
+//!
+//! Both providers are read into the same `Report`, in metric units, which the tools
+//! convert to the configured units and return as JSON. Neither provider publishes
+//! severe-weather alerts, so those come from a separate, optional source.
 
-use crate::shared::internal_error;
+use crate::config::{AlertSource, Units, WeatherConfig, WeatherProvider};
+use crate::egress::Egress;
+use crate::shared::{internal_error, RequestBuilderExt};
+use chrono::{NaiveDate, NaiveTime};
 use rmcp::{model::*, ErrorData as McpError};
 use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+const DEFAULT_DAYS: usize = 3;
+
 // === Parameter Types ===
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -17,90 +27,713 @@ pub struct LocationParams {
 pub struct ForecastParams {
     #[schemars(description = "Location to get forecast for")]
     pub location: String,
-    #[schemars(description = "Number of days (1-3, default 3)")]
+    #[schemars(
+        description = "Number of days (default 3; at most 3 with wttr.in, 16 with Open-Meteo)"
+    )]
     #[serde(default)]
     pub days: Option<u8>,
+    #[schemars(
+        description = "Include hour-by-hour entries for each day (3-hourly with wttr.in, default false)"
+    )]
+    #[serde(default)]
+    pub hourly: Option<bool>,
+}
+
+// === Provider-Neutral Report ===
+
+#[derive(Debug, Serialize)]
+struct Location {
+    name: String,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Current {
+    description: String,
+    temperature: f64,
+    feels_like: f64,
+    /// Percent
+    humidity: f64,
+    wind_speed: f64,
+    /// 16-point compass direction the wind comes from
+    wind_direction: String,
+    wind_direction_degrees: Option<f64>,
+    precipitation: Option<f64>,
+    /// Percent
+    cloud_cover: Option<f64>,
+    pressure: Option<f64>,
+    visibility: Option<f64>,
+    uv_index: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Moon {
+    phase: String,
+    /// Percent of the disc lit
+    illumination: f64,
+    moonrise: Option<String>,
+    moonset: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Hour {
+    /// Local time, `HH:MM`
+    time: String,
+    description: String,
+    temperature: Option<f64>,
+    feels_like: Option<f64>,
+    /// Percent
+    precipitation_probability: Option<f64>,
+    precipitation: Option<f64>,
+    wind_speed: Option<f64>,
+    /// Percent
+    humidity: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Day {
+    date: String,
+    description: Option<String>,
+    temperature_max: f64,
+    temperature_min: f64,
+    /// Highest chance of precipitation during the day, percent
+    precipitation_probability: Option<f64>,
+    precipitation: Option<f64>,
+    uv_index_max: Option<f64>,
+    /// Local time, `HH:MM`
+    sunrise: Option<String>,
+    sunset: Option<String>,
+    moon: Moon,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hours: Vec<Hour>,
+}
+
+#[derive(Debug)]
+struct Report {
+    location: Location,
+    current: Current,
+    days: Vec<Day>,
+}
+
+#[derive(Debug, Serialize)]
+struct UnitLabels {
+    temperature: &'static str,
+    wind_speed: &'static str,
+    precipitation: &'static str,
+    pressure: &'static str,
+    visibility: &'static str,
+}
+
+fn unit_labels(units: Units) -> UnitLabels {
+    match units {
+        Units::Metric => UnitLabels {
+            temperature: "°C",
+            wind_speed: "km/h",
+            precipitation: "mm",
+            pressure: "hPa",
+            visibility: "km",
+        },
+        Units::Imperial => UnitLabels {
+            temperature: "°F",
+            wind_speed: "mph",
+            precipitation: "in",
+            pressure: "inHg",
+            visibility: "mi",
+        },
+    }
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
 }
 
-// === Weather API Response Structs ===
+impl Report {
+    /// Convert the metric values the providers are read in
+    fn into_units(mut self, units: Units) -> Self {
+        if units == Units::Metric {
+            return self;
+        }
+        let temp = |c: f64| round(c * 9.0 / 5.0 + 32.0, 1);
+        let speed = |kmh: f64| round(kmh / 1.609_344, 1);
+        let precip = |mm: f64| round(mm / 25.4, 2);
+
+        let current = &mut self.current;
+        current.temperature = temp(current.temperature);
+        current.feels_like = temp(current.feels_like);
+        current.wind_speed = speed(current.wind_speed);
+        current.precipitation = current.precipitation.map(precip);
+        current.pressure = current.pressure.map(|hpa| round(hpa * 0.029_53, 2));
+        current.visibility = current.visibility.map(speed);
+
+        for day in &mut self.days {
+            day.temperature_max = temp(day.temperature_max);
+            day.temperature_min = temp(day.temperature_min);
+            day.precipitation = day.precipitation.map(precip);
+            for hour in &mut day.hours {
+                hour.temperature = hour.temperature.map(temp);
+                hour.feels_like = hour.feels_like.map(temp);
+                hour.precipitation = hour.precipitation.map(precip);
+                hour.wind_speed = hour.wind_speed.map(speed);
+            }
+        }
+        self
+    }
+}
+
+/// `07:05 PM` to `19:05`; wttr.in writes `No moonrise` on days without one
+fn clock_24h(time: &str) -> Option<String> {
+    NaiveTime::parse_from_str(time.trim(), "%I:%M %p")
+        .ok()
+        .map(|t| t.format("%H:%M").to_string())
+}
+
+/// Phase name and lit percentage at noon UTC, counted from the new moon of
+/// 2000-01-06 18:14 UTC
+fn moon_phase(date: NaiveDate) -> (&'static str, f64) {
+    const SYNODIC_MONTH: f64 = 29.530_588_853;
+    let reference = NaiveDate::from_ymd_opt(2000, 1, 6)
+        .and_then(|d| d.and_hms_opt(18, 14, 0))
+        .expect("valid reference date");
+    let noon = date.and_hms_opt(12, 0, 0).expect("valid time");
+    let days = (noon - reference).num_seconds() as f64 / 86_400.0;
+    let fraction = days.rem_euclid(SYNODIC_MONTH) / SYNODIC_MONTH;
+
+    let illumination = (1.0 - (2.0 * std::f64::consts::PI * fraction).cos()) / 2.0 * 100.0;
+    let phase = match (fraction * 8.0).round() as u8 % 8 {
+        0 => "New Moon",
+        1 => "Waxing Crescent",
+        2 => "First Quarter",
+        3 => "Waxing Gibbous",
+        4 => "Full Moon",
+        5 => "Waning Gibbous",
+        6 => "Last Quarter",
+        _ => "Waning Crescent",
+    };
+    (phase, round(illumination, 0))
+}
+
+// === wttr.in ===
 
 #[derive(Debug, Deserialize)]
-pub struct WttrResponse {
-    pub current_condition: Vec<CurrentCondition>,
-    pub nearest_area: Vec<NearestArea>,
-    pub weather: Vec<WeatherDay>,
+struct WttrResponse {
+    current_condition: Vec<WttrCurrent>,
+    nearest_area: Vec<WttrArea>,
+    weather: Vec<WttrDay>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct CurrentCondition {
-    pub temp_F: String,
-    pub temp_C: String,
-    #[serde(rename = "FeelsLikeF")]
-    pub feels_like_f: String,
+struct WttrCurrent {
+    #[serde(rename = "temp_C")]
+    temp_c: String,
     #[serde(rename = "FeelsLikeC")]
-    pub feels_like_c: String,
-    pub humidity: String,
-    pub weatherDesc: Vec<WeatherDesc>,
-    pub windspeedMiles: String,
-    pub windspeedKmph: String,
-    pub winddir16Point: String,
-    #[allow(dead_code)]
-    pub precipMM: String,
-    pub visibility: String,
-    pub pressure: String,
-    pub uvIndex: String,
+    feels_like_c: String,
+    humidity: String,
+    #[serde(rename = "weatherDesc")]
+    weather_desc: Vec<WttrValue>,
+    #[serde(rename = "windspeedKmph")]
+    windspeed_kmph: String,
+    #[serde(rename = "winddir16Point")]
+    winddir_16_point: String,
+    #[serde(rename = "winddirDegree", default)]
+    winddir_degree: String,
+    #[serde(rename = "precipMM", default)]
+    precip_mm: String,
+    #[serde(default)]
+    cloudcover: String,
+    visibility: String,
+    pressure: String,
+    #[serde(rename = "uvIndex")]
+    uv_index: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct WeatherDesc {
-    pub value: String,
+struct WttrValue {
+    value: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct NearestArea {
-    pub areaName: Vec<AreaValue>,
-    pub region: Vec<AreaValue>,
-    #[allow(dead_code)]
-    pub country: Vec<AreaValue>,
+struct WttrArea {
+    #[serde(rename = "areaName")]
+    area_name: Vec<WttrValue>,
+    region: Vec<WttrValue>,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct AreaValue {
-    pub value: String,
+struct WttrDay {
+    date: String,
+    #[serde(rename = "maxtempC")]
+    maxtemp_c: String,
+    #[serde(rename = "mintempC")]
+    mintemp_c: String,
+    #[serde(rename = "uvIndex", default)]
+    uv_index: String,
+    #[serde(default)]
+    astronomy: Vec<WttrAstronomy>,
+    hourly: Vec<WttrHour>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct WeatherDay {
-    pub date: String,
-    pub maxtempF: String,
-    pub maxtempC: String,
-    pub mintempF: String,
-    pub mintempC: String,
-    pub hourly: Vec<HourlyForecast>,
+struct WttrAstronomy {
+    sunrise: String,
+    sunset: String,
+    moonrise: String,
+    moonset: String,
+    moon_phase: String,
+    moon_illumination: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct HourlyForecast {
-    pub time: String,
-    pub tempF: String,
-    #[allow(dead_code)]
-    pub tempC: String,
-    pub weatherDesc: Vec<WeatherDesc>,
-    pub chanceofrain: String,
+struct WttrHour {
+    /// `0`, `300`, ... `2100`
+    time: String,
+    #[serde(rename = "tempC")]
+    temp_c: String,
+    #[serde(rename = "FeelsLikeC", default)]
+    feels_like_c: String,
+    #[serde(rename = "weatherDesc")]
+    weather_desc: Vec<WttrValue>,
+    chanceofrain: String,
+    #[serde(default)]
+    chanceofsnow: String,
+    #[serde(rename = "precipMM", default)]
+    precip_mm: String,
+    #[serde(rename = "windspeedKmph", default)]
+    windspeed_kmph: String,
+    #[serde(default)]
+    humidity: String,
 }
 
-// === Helper Functions ===
+/// wttr.in sends every number as a string
+fn number(value: &str) -> Option<f64> {
+    value.trim().parse().ok()
+}
+
+fn first_value(values: &[WttrValue]) -> Option<&str> {
+    values.first().map(|v| v.value.as_str())
+}
+
+impl WttrHour {
+    fn into_hour(self) -> Hour {
+        let chance = match (number(&self.chanceofrain), number(&self.chanceofsnow)) {
+            (Some(rain), Some(snow)) => Some(rain.max(snow)),
+            (rain, snow) => rain.or(snow),
+        };
+        let hhmm = self.time.parse::<u32>().unwrap_or(0);
+        Hour {
+            time: format!("{:02}:{:02}", hhmm / 100, hhmm % 100),
+            description: first_value(&self.weather_desc).unwrap_or("?").to_string(),
+            temperature: number(&self.temp_c),
+            feels_like: number(&self.feels_like_c),
+            precipitation_probability: chance,
+            precipitation: number(&self.precip_mm),
+            wind_speed: number(&self.windspeed_kmph),
+            humidity: number(&self.humidity),
+        }
+    }
+}
+
+impl WttrDay {
+    fn into_day(self) -> Day {
+        let hours: Vec<Hour> = self.hourly.into_iter().map(WttrHour::into_hour).collect();
+        let description = hours
+            .iter()
+            .find(|h| h.time == "12:00")
+            .or(hours.first())
+            .map(|h| h.description.clone());
+        let precipitation_probability = hours
+            .iter()
+            .filter_map(|h| h.precipitation_probability)
+            .reduce(f64::max);
+
+        let astronomy = self.astronomy.first();
+        let moon = match astronomy {
+            Some(a) => Moon {
+                phase: a.moon_phase.clone(),
+                illumination: number(&a.moon_illumination).unwrap_or(0.0),
+                moonrise: clock_24h(&a.moonrise),
+                moonset: clock_24h(&a.moonset),
+            },
+            None => computed_moon(&self.date),
+        };
+
+        Day {
+            description,
+            temperature_max: number(&self.maxtemp_c).unwrap_or(0.0),
+            temperature_min: number(&self.mintemp_c).unwrap_or(0.0),
+            precipitation_probability,
+            precipitation: None,
+            uv_index_max: number(&self.uv_index),
+            sunrise: astronomy.and_then(|a| clock_24h(&a.sunrise)),
+            sunset: astronomy.and_then(|a| clock_24h(&a.sunset)),
+            moon,
+            hours,
+            date: self.date,
+        }
+    }
+}
+
+impl WttrResponse {
+    fn into_report(self, location: &str) -> Result<Report, McpError> {
+        let current = self
+            .current_condition
+            .first()
+            .ok_or_else(|| internal_error("No current conditions"))?;
+
+        let location = match self.nearest_area.first() {
+            Some(a) => Location {
+                name: format!(
+                    "{}, {}",
+                    first_value(&a.area_name).unwrap_or("Unknown"),
+                    first_value(&a.region).unwrap_or("")
+                ),
+                latitude: number(&a.latitude),
+                longitude: number(&a.longitude),
+            },
+            None => Location {
+                name: location.to_string(),
+                latitude: None,
+                longitude: None,
+            },
+        };
+
+        let current = Current {
+            description: first_value(&current.weather_desc)
+                .unwrap_or("Unknown")
+                .to_string(),
+            temperature: number(&current.temp_c).unwrap_or(0.0),
+            feels_like: number(&current.feels_like_c).unwrap_or(0.0),
+            humidity: number(&current.humidity).unwrap_or(0.0),
+            wind_speed: number(&current.windspeed_kmph).unwrap_or(0.0),
+            wind_direction: current.winddir_16_point.clone(),
+            wind_direction_degrees: number(&current.winddir_degree),
+            precipitation: number(&current.precip_mm),
+            cloud_cover: number(&current.cloudcover),
+            pressure: number(&current.pressure),
+            visibility: number(&current.visibility),
+            uv_index: number(&current.uv_index),
+        };
+
+        Ok(Report {
+            location,
+            current,
+            days: self.weather.into_iter().map(WttrDay::into_day).collect(),
+        })
+    }
+}
+
+// === Open-Meteo ===
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<Place>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Place {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    #[serde(default)]
+    admin1: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoResponse {
+    current: OpenMeteoCurrent,
+    hourly: OpenMeteoHourly,
+    daily: OpenMeteoDaily,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoCurrent {
+    temperature_2m: f64,
+    apparent_temperature: f64,
+    relative_humidity_2m: f64,
+    weather_code: u8,
+    wind_speed_10m: f64,
+    wind_direction_10m: f64,
+    #[serde(default)]
+    precipitation: Option<f64>,
+    #[serde(default)]
+    cloud_cover: Option<f64>,
+    #[serde(default)]
+    pressure_msl: Option<f64>,
+    /// Meters
+    #[serde(default)]
+    visibility: Option<f64>,
+    #[serde(default)]
+    uv_index: Option<f64>,
+}
+
+/// Parallel arrays, one entry per hour
+#[derive(Debug, Deserialize)]
+struct OpenMeteoHourly {
+    /// Local time, `2024-06-01T09:00`
+    time: Vec<String>,
+    #[serde(default)]
+    temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    apparent_temperature: Vec<Option<f64>>,
+    #[serde(default)]
+    relative_humidity_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    weather_code: Vec<Option<u8>>,
+    #[serde(default)]
+    precipitation_probability: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_speed_10m: Vec<Option<f64>>,
+}
+
+/// Parallel arrays, one entry per day
+#[derive(Debug, Deserialize)]
+struct OpenMeteoDaily {
+    time: Vec<String>,
+    #[serde(default)]
+    weather_code: Vec<Option<u8>>,
+    #[serde(default)]
+    temperature_2m_max: Vec<Option<f64>>,
+    #[serde(default)]
+    temperature_2m_min: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_sum: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability_max: Vec<Option<f64>>,
+    #[serde(default)]
+    uv_index_max: Vec<Option<f64>>,
+    /// Local time, `2024-06-01T04:53`
+    #[serde(default)]
+    sunrise: Vec<Option<String>>,
+    #[serde(default)]
+    sunset: Vec<Option<String>>,
+}
+
+const OPEN_METEO_CURRENT: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,\
+    weather_code,wind_speed_10m,wind_direction_10m,precipitation,cloud_cover,pressure_msl,\
+    visibility,uv_index";
+const OPEN_METEO_HOURLY: &str = "temperature_2m,apparent_temperature,relative_humidity_2m,\
+    weather_code,precipitation_probability,precipitation,wind_speed_10m";
+const OPEN_METEO_DAILY: &str = "weather_code,temperature_2m_max,temperature_2m_min,\
+    precipitation_sum,precipitation_probability_max,uv_index_max,sunrise,sunset";
+
+/// Entry `i` of a parallel array, if the array is long enough and the value present
+fn at<T: Copy>(values: &[Option<T>], i: usize) -> Option<T> {
+    values.get(i).copied().flatten()
+}
+
+/// `HH:MM` of an ISO 8601 local timestamp
+fn time_of(timestamp: &str) -> Option<String> {
+    timestamp.get(11..16).map(str::to_string)
+}
+
+fn time_at(timestamps: &[Option<String>], i: usize) -> Option<String> {
+    timestamps.get(i)?.as_deref().and_then(time_of)
+}
+
+fn computed_moon(date: &str) -> Moon {
+    let (phase, illumination) = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(moon_phase)
+        .unwrap_or(("Unknown", 0.0));
+    Moon {
+        phase: phase.to_string(),
+        illumination,
+        moonrise: None,
+        moonset: None,
+    }
+}
+
+/// WMO weather interpretation codes used by Open-Meteo
+fn wmo_description(code: u8) -> &'static str {
+    match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 => "Drizzle",
+        56 | 57 => "Freezing drizzle",
+        61 => "Light rain",
+        63 => "Rain",
+        65 => "Heavy rain",
+        66 | 67 => "Freezing rain",
+        71 => "Light snow",
+        73 => "Snow",
+        75 => "Heavy snow",
+        77 => "Snow grains",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95 => "Thunderstorm",
+        96 | 99 => "Thunderstorm with hail",
+        _ => "Unknown",
+    }
+}
+
+fn compass_point(degrees: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    let index = (degrees.rem_euclid(360.0) / 22.5).round() as usize % 16;
+    POINTS[index]
+}
+
+/// `'lat,lon'` locations need no geocoding
+fn parse_coordinates(location: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = location.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+impl OpenMeteoResponse {
+    fn into_report(self, location: Location) -> Report {
+        let current = Current {
+            description: wmo_description(self.current.weather_code).to_string(),
+            temperature: self.current.temperature_2m,
+            feels_like: self.current.apparent_temperature,
+            humidity: self.current.relative_humidity_2m,
+            wind_speed: self.current.wind_speed_10m,
+            wind_direction: compass_point(self.current.wind_direction_10m).to_string(),
+            wind_direction_degrees: Some(self.current.wind_direction_10m),
+            precipitation: self.current.precipitation,
+            cloud_cover: self.current.cloud_cover,
+            pressure: self.current.pressure_msl,
+            visibility: self.current.visibility.map(|m| round(m / 1000.0, 1)),
+            uv_index: self.current.uv_index,
+        };
+
+        let hourly = &self.hourly;
+        let daily = &self.daily;
+        let days = daily
+            .time
+            .iter()
+            .enumerate()
+            .map(|(i, date)| Day {
+                date: date.clone(),
+                description: at(&daily.weather_code, i).map(|c| wmo_description(c).to_string()),
+                temperature_max: at(&daily.temperature_2m_max, i).unwrap_or(0.0),
+                temperature_min: at(&daily.temperature_2m_min, i).unwrap_or(0.0),
+                precipitation_probability: at(&daily.precipitation_probability_max, i),
+                precipitation: at(&daily.precipitation_sum, i),
+                uv_index_max: at(&daily.uv_index_max, i),
+                sunrise: time_at(&daily.sunrise, i),
+                sunset: time_at(&daily.sunset, i),
+                moon: computed_moon(date),
+                hours: hourly
+                    .time
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, time)| time.starts_with(date.as_str()))
+                    .map(|(j, time)| Hour {
+                        time: time_of(time).unwrap_or_default(),
+                        description: at(&hourly.weather_code, j)
+                            .map_or("?", wmo_description)
+                            .to_string(),
+                        temperature: at(&hourly.temperature_2m, j),
+                        feels_like: at(&hourly.apparent_temperature, j),
+                        precipitation_probability: at(&hourly.precipitation_probability, j),
+                        precipitation: at(&hourly.precipitation, j),
+                        wind_speed: at(&hourly.wind_speed_10m, j),
+                        humidity: at(&hourly.relative_humidity_2m, j),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Report {
+            location,
+            current,
+            days,
+        }
+    }
+}
+
+// === Alerts ===
+
+#[derive(Debug, Deserialize)]
+struct NwsAlerts {
+    #[serde(default)]
+    features: Vec<NwsFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NwsFeature {
+    properties: Alert,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Alert {
+    event: String,
+    /// Extreme, Severe, Moderate, Minor or Unknown
+    #[serde(default)]
+    severity: Option<String>,
+    #[serde(default)]
+    urgency: Option<String>,
+    #[serde(default)]
+    headline: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    instruction: Option<String>,
+    #[serde(rename(deserialize = "areaDesc"), default)]
+    area: Option<String>,
+    #[serde(default)]
+    onset: Option<String>,
+    #[serde(default)]
+    expires: Option<String>,
+    #[serde(rename(deserialize = "senderName"), default)]
+    sender: Option<String>,
+}
 
-async fn fetch_weather(client: &reqwest::Client, location: &str) -> Result<WttrResponse, McpError> {
-    let url = format!(
-        "https://wttr.in/{}?format=j1",
-        urlencoding::encode(location)
-    );
+/// Active alerts for the report's location. `None` when no alert source is configured.
+async fn fetch_alerts(
+    config: &WeatherConfig,
+    egress: &Egress,
+    location: &Location,
+) -> Option<Result<Vec<Alert>, String>> {
+    match config.alerts {
+        AlertSource::None => None,
+        AlertSource::Nws => {
+            let (Some(lat), Some(lon)) = (location.latitude, location.longitude) else {
+                return Some(Err("Location has no coordinates".to_string()));
+            };
+            let url = format!(
+                "{}/alerts/active?point={:.4},{:.4}",
+                config.alerts_url(),
+                lat,
+                lon
+            );
+            if !egress.allows_url(&url).await {
+                return Some(Err("Blocked by network_egress".to_string()));
+            }
+            let alerts = fetch_json::<NwsAlerts>(egress, &url)
+                .await
+                .map(|a| a.features.into_iter().map(|f| f.properties).collect())
+                .map_err(|e| e.message.to_string());
+            Some(alerts)
+        }
+    }
+}
 
-    let response = client
-        .get(&url)
+// === Helper Functions ===
+
+async fn fetch_json<T: DeserializeOwned>(egress: &Egress, url: &str) -> Result<T, McpError> {
+    let response = egress
+        .client(url)
+        .await?
+        .get(url)
         .header("User-Agent", "rmcp-presence/0.1.0")
-        .send()
+        .traced_send()
         .await
         .map_err(|e| internal_error(format!("HTTP request failed: {}", e)))?;
 
@@ -112,117 +745,261 @@ async fn fetch_weather(client: &reqwest::Client, location: &str) -> Result<WttrR
     }
 
     response
-        .json::<WttrResponse>()
+        .json::<T>()
         .await
         .map_err(|e| internal_error(format!("Failed to parse weather data: {}", e)))
 }
 
+fn max_days(provider: WeatherProvider) -> usize {
+    match provider {
+        WeatherProvider::Wttr => 3,
+        WeatherProvider::OpenMeteo => 16,
+    }
+}
+
+async fn fetch_report(
+    config: &WeatherConfig,
+    egress: &Egress,
+    location: &str,
+    days: usize,
+) -> Result<Report, McpError> {
+    let report = match config.provider {
+        WeatherProvider::Wttr => {
+            let url = format!(
+                "{}/{}?format=j1",
+                config.url(),
+                urlencoding::encode(location)
+            );
+            let data: WttrResponse = fetch_json(egress, &url).await?;
+            data.into_report(location)?
+        }
+        WeatherProvider::OpenMeteo => {
+            let place = match parse_coordinates(location) {
+                Some((lat, lon)) => Location {
+                    name: location.to_string(),
+                    latitude: Some(lat),
+                    longitude: Some(lon),
+                },
+                None => {
+                    let url = format!(
+                        "{}/v1/search?name={}&count=1&format=json",
+                        config.geocoding_url(),
+                        urlencoding::encode(location)
+                    );
+                    let found: GeocodingResponse = fetch_json(egress, &url).await?;
+                    let place = found.results.into_iter().next().ok_or_else(|| {
+                        McpError::invalid_params(format!("Unknown location: {}", location), None)
+                    })?;
+                    let region = place.admin1.or(place.country).unwrap_or_default();
+                    Location {
+                        name: format!("{}, {}", place.name, region),
+                        latitude: Some(place.latitude),
+                        longitude: Some(place.longitude),
+                    }
+                }
+            };
+            let url = format!(
+                "{}/v1/forecast?latitude={}&longitude={}&current={}&hourly={}&daily={}\
+                 &timezone=auto&forecast_days={}",
+                config.url(),
+                place.latitude.unwrap_or_default(),
+                place.longitude.unwrap_or_default(),
+                OPEN_METEO_CURRENT,
+                OPEN_METEO_HOURLY,
+                OPEN_METEO_DAILY,
+                days.clamp(1, max_days(config.provider))
+            );
+            let data: OpenMeteoResponse = fetch_json(egress, &url).await?;
+            data.into_report(place)
+        }
+    };
+    Ok(report.into_units(config.units))
+}
+
+/// Location, units and, when an alert source is configured, alerts or why they're missing
+async fn report_json(
+    config: &WeatherConfig,
+    egress: &Egress,
+    location: &Location,
+) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "location": location,
+        "units": unit_labels(config.units),
+    });
+    match fetch_alerts(config, egress, location).await {
+        Some(Ok(alerts)) => json["alerts"] = serde_json::json!(alerts),
+        Some(Err(e)) => json["alerts_error"] = serde_json::json!(e),
+        None => {}
+    }
+    json
+}
+
+fn to_result(json: &serde_json::Value) -> Result<CallToolResult, McpError> {
+    let json = serde_json::to_string_pretty(json)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
 // === Tool Functions ===
 
 pub async fn get_weather(
-    client: &reqwest::Client,
+    config: &WeatherConfig,
+    egress: &Egress,
     params: LocationParams,
 ) -> Result<CallToolResult, McpError> {
-    let data = fetch_weather(client, &params.location).await?;
-
-    let current = data
-        .current_condition
-        .first()
-        .ok_or_else(|| internal_error("No current conditions"))?;
-
-    let area = data
-        .nearest_area
-        .first()
-        .map(|a| {
-            format!(
-                "{}, {}",
-                a.areaName
-                    .first()
-                    .map(|v| v.value.as_str())
-                    .unwrap_or("Unknown"),
-                a.region.first().map(|v| v.value.as_str()).unwrap_or("")
-            )
-        })
-        .unwrap_or_else(|| params.location.clone());
-
-    let desc = current
-        .weatherDesc
-        .first()
-        .map(|d| d.value.as_str())
-        .unwrap_or("Unknown");
-
-    let output = format!(
-        "Weather for {}:\n\
-         Conditions: {}\n\
-         Temperature: {}°F / {}°C\n\
-         Feels like: {}°F / {}°C\n\
-         Humidity: {}%\n\
-         Wind: {} mph {} ({})\n\
-         Visibility: {} miles\n\
-         Pressure: {} mb\n\
-         UV Index: {}",
-        area,
-        desc,
-        current.temp_F,
-        current.temp_C,
-        current.feels_like_f,
-        current.feels_like_c,
-        current.humidity,
-        current.windspeedMiles,
-        current.winddir16Point,
-        current.windspeedKmph,
-        current.visibility,
-        current.pressure,
-        current.uvIndex
-    );
-
-    Ok(CallToolResult::success(vec![Content::text(output)]))
+    let mut report = fetch_report(config, egress, &params.location, 1).await?;
+    let mut json = report_json(config, egress, &report.location).await;
+
+    json["current"] = serde_json::json!(report.current);
+    if !report.days.is_empty() {
+        let mut today = report.days.swap_remove(0);
+        today.hours.clear();
+        json["today"] = serde_json::json!(today);
+    }
+    to_result(&json)
 }
 
 pub async fn get_forecast(
-    client: &reqwest::Client,
+    config: &WeatherConfig,
+    egress: &Egress,
     params: ForecastParams,
 ) -> Result<CallToolResult, McpError> {
-    let data = fetch_weather(client, &params.location).await?;
-    let days = params.days.unwrap_or(3).min(3) as usize;
-
-    let area = data
-        .nearest_area
-        .first()
-        .map(|a| {
-            format!(
-                "{}, {}",
-                a.areaName
-                    .first()
-                    .map(|v| v.value.as_str())
-                    .unwrap_or("Unknown"),
-                a.region.first().map(|v| v.value.as_str()).unwrap_or("")
-            )
-        })
-        .unwrap_or_else(|| params.location.clone());
-
-    let mut output = format!("Forecast for {} ({} days):\n\n", area, days);
-
-    for day in data.weather.iter().take(days) {
-        output.push_str(&format!(
-            "{}:\n  High: {}°F / {}°C | Low: {}°F / {}°C\n",
-            day.date, day.maxtempF, day.maxtempC, day.mintempF, day.mintempC
-        ));
-
-        for hour in day.hourly.iter().step_by(3) {
-            let time_hr = hour.time.parse::<u32>().unwrap_or(0) / 100;
-            let desc = hour
-                .weatherDesc
-                .first()
-                .map(|d| d.value.as_str())
-                .unwrap_or("?");
-            output.push_str(&format!(
-                "  {:02}:00 - {}°F, {}, {}% rain\n",
-                time_hr, hour.tempF, desc, hour.chanceofrain
-            ));
+    let days = params
+        .days
+        .map_or(DEFAULT_DAYS, usize::from)
+        .clamp(1, max_days(config.provider));
+    let mut report = fetch_report(config, egress, &params.location, days).await?;
+    let mut json = report_json(config, egress, &report.location).await;
+
+    report.days.truncate(days);
+    if !params.hourly.unwrap_or(false) {
+        for day in &mut report.days {
+            day.hours.clear();
         }
-        output.push('\n');
     }
+    json["days"] = serde_json::json!(report.days);
+    to_result(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves canned JSON by path prefix, one request per connection
+    async fn mock_server(routes: Vec<(&'static str, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let len = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..len]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let body = routes
+                    .iter()
+                    .find(|(prefix, _)| path.starts_with(prefix))
+                    .map_or("{}", |(_, body)| body.as_str());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                     Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_open_meteo_mock_server() {
+        let geocoding = serde_json::json!({
+            "results": [{
+                "name": "Oslo", "latitude": 59.91, "longitude": 10.75,
+                "admin1": "Oslo County", "country": "Norway"
+            }]
+        });
+        let hours: Vec<String> = (0..24).map(|h| format!("2024-06-01T{:02}:00", h)).collect();
+        let forecast = serde_json::json!({
+            "current": {
+                "temperature_2m": 20.0, "apparent_temperature": 19.0,
+                "relative_humidity_2m": 55.0, "weather_code": 2,
+                "wind_speed_10m": 16.1, "wind_direction_10m": 270.0,
+                "pressure_msl": 1013.2, "visibility": 24000.0, "uv_index": 5.1
+            },
+            "hourly": {
+                "time": hours,
+                "temperature_2m": vec![15.0; 24],
+                "weather_code": vec![61; 24],
+                "precipitation_probability": vec![40; 24]
+            },
+            "daily": {
+                "time": ["2024-06-01"],
+                "temperature_2m_max": [22.0],
+                "temperature_2m_min": [12.0],
+                "sunrise": ["2024-06-01T03:58"],
+                "sunset": ["2024-06-01T22:28"]
+            }
+        });
+        let alerts = serde_json::json!({
+            "features": [{"properties": {
+                "event": "Flood Warning", "severity": "Severe", "senderName": "NWS Test"
+            }}]
+        });
+        let base = mock_server(vec![
+            ("/v1/search", geocoding.to_string()),
+            ("/v1/forecast", forecast.to_string()),
+            ("/alerts/active?point=59.9100,10.7500", alerts.to_string()),
+        ])
+        .await;
+
+        let config = WeatherConfig {
+            provider: WeatherProvider::OpenMeteo,
+            url: Some(base.clone()),
+            geocoding_url: Some(format!("{}/", base)),
+            units: Units::Imperial,
+            alerts: AlertSource::Nws,
+            alerts_url: Some(base),
+        };
+        let egress = Egress::new(&Config::default());
+        let report = fetch_report(&config, &egress, "Oslo", 1).await.unwrap();
 
-    Ok(CallToolResult::success(vec![Content::text(output)]))
+        assert_eq!(report.location.name, "Oslo, Oslo County");
+        assert_eq!(report.current.description, "Partly cloudy");
+        assert_eq!(report.current.wind_direction, "W");
+        assert_eq!(report.current.temperature, 68.0);
+        assert_eq!(report.current.wind_speed, 10.0);
+        assert_eq!(report.current.visibility, Some(14.9));
+        let day = &report.days[0];
+        assert_eq!(day.temperature_max, 71.6);
+        assert_eq!(day.sunset.as_deref(), Some("22:28"));
+        assert_eq!(day.moon.phase, "Waning Crescent");
+        assert_eq!(day.hours.len(), 24);
+        assert_eq!(day.hours[9].time, "09:00");
+        assert_eq!(day.hours[9].description, "Light rain");
+        assert_eq!(day.hours[9].precipitation_probability, Some(40.0));
+
+        let json = report_json(&config, &egress, &report.location).await;
+        assert_eq!(json["units"]["temperature"], "°F");
+        assert_eq!(json["alerts"][0]["event"], "Flood Warning");
+        assert_eq!(json["alerts"][0]["sender"], "NWS Test");
+    }
+
+    #[test]
+    fn test_moon_phase() {
+        // Total solar eclipse, and the full moon two weeks later
+        let (phase, lit) = moon_phase(NaiveDate::from_ymd_opt(2024, 4, 8).unwrap());
+        assert_eq!(phase, "New Moon");
+        assert!(lit < 2.0);
+        let (phase, lit) = moon_phase(NaiveDate::from_ymd_opt(2024, 4, 24).unwrap());
+        assert_eq!(phase, "Full Moon");
+        assert!(lit > 98.0);
+
+        assert_eq!(clock_24h("07:05 PM").as_deref(), Some("19:05"));
+        assert_eq!(clock_24h("No moonrise"), None);
+    }
 }
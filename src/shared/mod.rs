@@ -1,6 +1,7 @@
 //! Shared utilities for rmcp-presence
 
-use rmcp::ErrorData as McpError;
+use rmcp::{model::ErrorCode, ErrorData as McpError};
+use std::path::PathBuf;
 
 /// Format a duration in seconds to human readable string
 pub fn format_duration(seconds: u64) -> String {
@@ -46,3 +47,94 @@ pub fn format_bytes(bytes: u64) -> String {
 pub fn internal_error(msg: impl Into<String>) -> McpError {
     McpError::internal_error(msg.into(), None)
 }
+
+/// Create an error for a call refused by server policy, with structured details
+pub fn policy_error(msg: impl Into<String>, data: serde_json::Value) -> McpError {
+    McpError::new(ErrorCode::INVALID_REQUEST, msg.into(), Some(data))
+}
+
+/// Locate an executable on PATH (like `which`)
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Run a command to completion inside a `subprocess` span
+pub trait CommandExt {
+    fn traced_output(&mut self) -> std::io::Result<std::process::Output>;
+}
+
+impl CommandExt for std::process::Command {
+    fn traced_output(&mut self) -> std::io::Result<std::process::Output> {
+        let span = tracing::info_span!(
+            "subprocess",
+            program = %self.get_program().to_string_lossy(),
+            exit_code = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+
+        let result = self.output();
+        match &result {
+            Ok(output) => {
+                if let Some(code) = output.status.code() {
+                    span.record("exit_code", code);
+                }
+                if !output.status.success() {
+                    span.record("error", "non-zero exit status");
+                }
+            }
+            Err(e) => {
+                span.record("error", tracing::field::display(e));
+            }
+        }
+        result
+    }
+}
+
+/// Send an HTTP request inside an `http.request` span
+#[cfg(feature = "sensors")]
+pub trait RequestBuilderExt {
+    fn traced_send(
+        self,
+    ) -> impl std::future::Future<Output = reqwest::Result<reqwest::Response>> + Send;
+}
+
+#[cfg(feature = "sensors")]
+impl RequestBuilderExt for reqwest::RequestBuilder {
+    fn traced_send(
+        self,
+    ) -> impl std::future::Future<Output = reqwest::Result<reqwest::Response>> + Send {
+        use tracing::Instrument;
+
+        let (client, request) = self.build_split();
+        async move {
+            let request = request?;
+            // Host and path only: query strings may carry locations or keys
+            let span = tracing::info_span!(
+                "http.request",
+                method = %request.method(),
+                host = request.url().host_str().unwrap_or_default(),
+                path = request.url().path(),
+                status = tracing::field::Empty,
+                error = tracing::field::Empty,
+            );
+
+            let result = client.execute(request).instrument(span.clone()).await;
+            match &result {
+                Ok(response) => {
+                    span.record("status", response.status().as_u16());
+                    if !response.status().is_success() {
+                        span.record("error", "unsuccessful status");
+                    }
+                }
+                Err(e) => {
+                    span.record("error", tracing::field::display(e));
+                }
+            }
+            result
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Snapshot diffing for composite tools
+//!
+//! Every composite result is kept in memory under a fresh `snapshot_id`. Passing
+//! that id back as `since` returns only the fields that changed, which keeps
+//! long-running sessions from re-reading the whole state to spot a difference.
+
+use rmcp::{model::*, ErrorData as McpError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::shared::internal_error;
+
+/// Oldest snapshots are forgotten beyond this
+const MAX_SNAPSHOTS: usize = 64;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SinceParams {
+    #[schemars(
+        description = "snapshot_id from an earlier call: return only the fields that changed since then"
+    )]
+    #[serde(default)]
+    pub since: Option<String>,
+}
+
+// === Snapshot Store ===
+
+#[derive(Debug)]
+struct Snapshot {
+    token: String,
+    tool: String,
+    value: serde_json::Value,
+}
+
+/// Recent composite results, keyed by token
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    entries: Mutex<VecDeque<Snapshot>>,
+    next_id: AtomicU64,
+    hasher: RandomState,
+}
+
+impl Snapshots {
+    /// Store a composite's result and answer with either the full result or the changes
+    /// since `since`, tagged with the new token. Errors and non-JSON output pass through.
+    pub fn apply(
+        &self,
+        tool: &str,
+        since: Option<&str>,
+        result: Result<CallToolResult, McpError>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = result?;
+        if result.is_error == Some(true) {
+            return Ok(result);
+        }
+        let value = match result.content.as_slice() {
+            [content] => match &content.raw {
+                RawContent::Text(text) => {
+                    serde_json::from_str::<serde_json::Value>(&text.text).ok()
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(value) = value else {
+            return Ok(result);
+        };
+
+        let token = self.token();
+        let previous = since.map(|since| self.find(tool, since));
+        self.store(Snapshot {
+            token: token.clone(),
+            tool: tool.to_string(),
+            value: value.clone(),
+        });
+
+        let output = match previous {
+            Some(Some(old)) => serde_json::json!({
+                "snapshot_id": token,
+                "since": since,
+                "changed": diff(&old, &value).unwrap_or_else(|| serde_json::json!({})),
+            }),
+            Some(None) => full(
+                value,
+                &token,
+                Some(format!(
+                    "Snapshot {} not found (expired or from another tool), returning everything",
+                    since.unwrap_or_default()
+                )),
+            ),
+            None => full(value, &token, None),
+        };
+
+        let json = serde_json::to_string_pretty(&output)
+            .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    fn token(&self) -> String {
+        // Unguessable and never reused across restarts, so a stale token can't match
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("{:016x}", self.hasher.hash_one(id))
+    }
+
+    fn find(&self, tool: &str, token: &str) -> Option<serde_json::Value> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .find(|s| s.token == token && s.tool == tool)
+            .map(|s| s.value.clone())
+    }
+
+    fn store(&self, snapshot: Snapshot) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_SNAPSHOTS {
+            entries.pop_front();
+        }
+        entries.push_back(snapshot);
+    }
+}
+
+/// The full result with the token (and an optional note) added
+fn full(value: serde_json::Value, token: &str, note: Option<String>) -> serde_json::Value {
+    let mut object = match value {
+        serde_json::Value::Object(object) => object,
+        other => {
+            let mut object = serde_json::Map::new();
+            object.insert("result".to_string(), other);
+            object
+        }
+    };
+    object.insert("snapshot_id".to_string(), token.into());
+    if let Some(note) = note {
+        object.insert("note".to_string(), note.into());
+    }
+    object.into()
+}
+
+/// Changed fields of `new` relative to `old`, nested like the original. Removed keys
+/// become `null`; arrays and scalars are replaced whole. `None` if nothing changed.
+fn diff(old: &serde_json::Value, new: &serde_json::Value) -> Option<serde_json::Value> {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            let mut changed = serde_json::Map::new();
+            for (key, value) in new {
+                let entry = match old.get(key) {
+                    Some(previous) => diff(previous, value),
+                    None => Some(value.clone()),
+                };
+                if let Some(entry) = entry {
+                    changed.insert(key.clone(), entry);
+                }
+            }
+            for key in old.keys().filter(|key| !new.contains_key(*key)) {
+                changed.insert(key.clone(), serde_json::Value::Null);
+            }
+            (!changed.is_empty()).then(|| changed.into())
+        }
+        _ if old == new => None,
+        _ => Some(new.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RedactionConfig;
+    use crate::redact::Redactor;
+    use serde_json::json;
+
+    fn text(result: Result<CallToolResult, McpError>) -> serde_json::Value {
+        match &result.unwrap().content[0].raw {
+            RawContent::Text(text) => serde_json::from_str(&text.text).unwrap(),
+            _ => panic!("expected text"),
+        }
+    }
+
+    /// The result as the client sees it, after default redaction
+    fn redacted(result: Result<CallToolResult, McpError>) -> serde_json::Value {
+        let mut result = result.unwrap();
+        Redactor::new(&RedactionConfig::default()).redact_result(&mut result);
+        text(Ok(result))
+    }
+
+    fn call(value: serde_json::Value) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            value.to_string(),
+        )]))
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = json!({ "volume": 40, "muted": false, "sink": { "name": "hdmi", "volume": 40 }, "apps": ["a"] });
+        let new = json!({ "volume": 40, "muted": true, "sink": { "name": "hdmi", "volume": 55 }, "apps": ["a", "b"] });
+        assert_eq!(
+            diff(&old, &new),
+            Some(json!({ "muted": true, "sink": { "volume": 55 }, "apps": ["a", "b"] }))
+        );
+        assert_eq!(diff(&new, &new), None);
+        assert_eq!(
+            diff(&json!({ "battery": 80 }), &json!({})),
+            Some(json!({ "battery": null }))
+        );
+    }
+
+    #[test]
+    fn test_since() {
+        let snapshots = Snapshots::default();
+        let first = redacted(snapshots.apply(
+            "get_context",
+            None,
+            call(json!({ "idle": 1, "user": "me" })),
+        ));
+        let token = first["snapshot_id"].as_str().unwrap().to_string();
+        assert_eq!(first["user"], "me");
+
+        let second = redacted(snapshots.apply(
+            "get_context",
+            Some(&token),
+            call(json!({ "idle": 9, "user": "me" })),
+        ));
+        assert_eq!(second["changed"], json!({ "idle": 9 }));
+        assert_ne!(second["snapshot_id"], first["snapshot_id"]);
+
+        // Tokens are per tool
+        let other =
+            text(snapshots.apply("get_peripherals", Some(&token), call(json!({ "usb": [] }))));
+        assert!(other["note"].is_string());
+        assert_eq!(other["usb"], json!([]));
+    }
+}
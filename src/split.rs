@@ -0,0 +1,342 @@
+//! Privilege separation between sensors and actuators
+//!
+//! In split mode the MCP-facing process keeps only server and sensor tools and
+//! forwards every other call over a Unix socket to `rmcp-presence helper`. The helper
+//! serves the actuator and Linux tools with its own copy of the policy, and can run
+//! as a different user or inside a systemd sandbox.
+
+use rmcp::{
+    model::*,
+    service::{RequestContext, RunningService},
+    ClientHandler, ErrorData as McpError, Peer, RoleClient, RoleServer, ServiceError, ServiceExt,
+};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::audit;
+use crate::config::SplitConfig;
+use crate::shared::internal_error;
+use crate::PresenceServer;
+
+// === Helper Process ===
+
+/// Serve `server` on the helper socket, one MCP session per connection
+pub async fn run_helper(server: PresenceServer, config: &SplitConfig) -> anyhow::Result<()> {
+    let path = config.socket_path();
+    let shared = !config.allow_uids.is_empty();
+    let own_uid = own_uid();
+    prepare_socket_dir(config)?;
+
+    // Replace a socket left behind by a previous run, but never clobber other files
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    // Other users can only get past the UID check below if they can open the socket
+    let mode = if shared { 0o666 } else { 0o600 };
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+    tracing::info!("Helper listening on {}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+
+        let uid = match stream.peer_cred() {
+            Ok(cred) => cred.uid(),
+            Err(e) => {
+                tracing::warn!("Could not read helper peer credentials: {}", e);
+                continue;
+            }
+        };
+        let allowed = uid == own_uid || config.allow_uids.contains(&uid);
+        audit::helper_connection(uid, allowed);
+        if !allowed {
+            continue;
+        }
+
+        let server = server.for_session();
+        tokio::spawn(async move {
+            match server.serve(stream).await {
+                Ok(service) => {
+                    if let Err(e) = service.waiting().await {
+                        tracing::warn!("Helper session ended with error: {}", e);
+                    }
+                }
+                Err(e) => tracing::warn!("Helper handshake failed: {}", e),
+            }
+        });
+    }
+}
+
+fn own_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
+
+/// Create the helper's socket directory, or check an existing one. The sandbox calls
+/// this too, since Landlock can only open up paths that exist.
+pub fn prepare_socket_dir(config: &SplitConfig) -> anyhow::Result<()> {
+    match config.socket_path().parent() {
+        Some(dir) => prepare_dir(dir, !config.allow_uids.is_empty(), own_uid()),
+        None => Ok(()),
+    }
+}
+
+/// Create the socket directory, or make sure an existing one is safe to use: a real
+/// directory owned by us (or root) that nobody else can write to. Otherwise another
+/// user could swap the socket, e.g. in a shared temp dir.
+fn prepare_dir(dir: &Path, shared: bool, own_uid: u32) -> anyhow::Result<()> {
+    let mode = if shared { 0o711 } else { 0o700 };
+    if let Some(parent) = dir.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::DirBuilder::new().mode(mode).create(dir) {
+        // The umask may have dropped the execute bits other users need
+        Ok(()) => std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))?,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+    if metadata.uid() != own_uid && metadata.uid() != 0 {
+        anyhow::bail!("{} is owned by UID {}", dir.display(), metadata.uid());
+    }
+    if metadata.mode() & 0o022 != 0 {
+        anyhow::bail!("{} is writable by other users", dir.display());
+    }
+    Ok(())
+}
+
+// === Forwarding (MCP-facing process) ===
+
+/// Client side of the helper connection. Relays the helper's consent prompts
+/// (elicitation requests) to the real MCP client.
+#[derive(Clone, Default)]
+struct Relay {
+    upstream: Arc<Mutex<Option<Peer<RoleServer>>>>,
+}
+
+impl ClientHandler for Relay {
+    async fn create_elicitation(
+        &self,
+        request: CreateElicitationRequestParam,
+        _context: RequestContext<RoleClient>,
+    ) -> Result<CreateElicitationResult, McpError> {
+        let upstream = self
+            .upstream
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        match upstream {
+            Some(peer) if peer.supports_elicitation() => peer
+                .create_elicitation(request)
+                .await
+                .map_err(|e| internal_error(format!("Failed to relay prompt: {}", e))),
+            _ => Err(internal_error("MCP client does not support elicitation")),
+        }
+    }
+
+    fn get_info(&self) -> ClientInfo {
+        ClientInfo {
+            capabilities: ClientCapabilities {
+                elicitation: Some(ElicitationCapability::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Connection to the helper, re-established on demand if the helper restarts
+pub struct Forwarder {
+    socket: PathBuf,
+    allow_uids: Vec<u32>,
+    relay: Relay,
+    client: tokio::sync::Mutex<Option<RunningService<RoleClient, Relay>>>,
+}
+
+impl std::fmt::Debug for Forwarder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Forwarder")
+            .field("socket", &self.socket)
+            .finish()
+    }
+}
+
+impl Forwarder {
+    pub fn new(config: &SplitConfig) -> Self {
+        Self {
+            socket: config.socket_path(),
+            allow_uids: config.allow_uids.clone(),
+            relay: Relay::default(),
+            client: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Remember the MCP client so the helper's prompts can reach it
+    pub fn set_upstream(&self, peer: &Peer<RoleServer>) {
+        *self
+            .relay
+            .upstream
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(peer.clone());
+    }
+
+    async fn peer(&self) -> Result<Peer<RoleClient>, String> {
+        let mut client = self.client.lock().await;
+        if let Some(service) = client.as_ref() {
+            if !service.peer().is_transport_closed() {
+                return Ok(service.peer().clone());
+            }
+        }
+
+        let stream = UnixStream::connect(&self.socket).await.map_err(|e| {
+            format!("Cannot reach helper at {}: {}", self.socket.display(), e)
+        })?;
+
+        // Whoever serves the socket gets our tool calls and can prompt the user
+        let uid = stream
+            .peer_cred()
+            .map_err(|e| format!("Could not read helper credentials: {}", e))?
+            .uid();
+        if uid != own_uid() && !self.allow_uids.contains(&uid) {
+            return Err(format!(
+                "Helper at {} runs as UID {}, which is not allowed",
+                self.socket.display(),
+                uid
+            ));
+        }
+        let service = self
+            .relay
+            .clone()
+            .serve(stream)
+            .await
+            .map_err(|e| format!("Helper handshake failed: {}", e))?;
+
+        let peer = service.peer().clone();
+        *client = Some(service);
+        Ok(peer)
+    }
+
+    /// Tools served by the helper (empty while it is unreachable)
+    pub async fn list_tools(&self) -> Vec<Tool> {
+        let tools = match self.peer().await {
+            Ok(peer) => peer.list_all_tools().await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        tools.unwrap_or_else(|e| {
+            tracing::warn!("Listing helper tools failed: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Run a tool in the helper. `meta` is sent as the request's `_meta`
+    /// (e.g. trace context).
+    pub async fn call_tool(
+        &self,
+        params: CallToolRequestParam,
+        meta: Meta,
+    ) -> Result<CallToolResult, McpError> {
+        let peer = self.peer().await.map_err(internal_error)?;
+
+        let mut request = ClientRequest::CallToolRequest(CallToolRequest {
+            method: Default::default(),
+            params,
+            extensions: Default::default(),
+        });
+        *request.get_meta_mut() = meta;
+
+        match peer.send_request(request).await {
+            Ok(ServerResult::CallToolResult(result)) => Ok(result),
+            Ok(_) => Err(internal_error("Helper sent an unexpected response")),
+            Err(ServiceError::McpError(error)) => Err(error),
+            Err(other) => Err(internal_error(format!("Helper call failed: {}", other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::{EmptyParams, Role};
+    use rmcp::handler::server::wrapper::Parameters;
+
+    fn test_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rmcp-presence-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_prepare_dir() {
+        let dir = test_dir("dir-test");
+        let uid = own_uid();
+        prepare_dir(&dir, false, uid).unwrap();
+        assert_eq!(std::fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+        // An existing directory is checked, not trusted
+        prepare_dir(&dir, false, uid).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(prepare_dir(&dir, false, uid).is_err());
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        if uid != 0 {
+            // Directories owned by root are accepted
+            assert!(prepare_dir(&dir, false, uid + 1).is_err());
+        }
+        std::fs::remove_dir(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_forwarding() {
+        let mut config = Config::default();
+        config.split.socket = Some(test_dir("split-test").join("helper.sock"));
+
+        let helper = PresenceServer::new(config.clone(), Role::Helper).await;
+        let split = config.split.clone();
+        tokio::spawn(async move { run_helper(helper, &split).await });
+
+        // Each tool is served by exactly one side
+        let front = PresenceServer::new(config.clone(), Role::Front).await;
+        assert!(front.tool_router.has_route("get_server_stats"));
+        assert!(!front.tool_router.has_route("undo_action"));
+        let forwarder = Forwarder::new(&config.split);
+        let mut helper_tools = Vec::new();
+        for _ in 0..50 {
+            helper_tools = forwarder.list_tools().await;
+            if !helper_tools.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(helper_tools.iter().any(|tool| tool.name == "undo_action"));
+        assert!(!helper_tools
+            .iter()
+            .any(|tool| tool.name == "get_server_stats"));
+
+        let params = CallToolRequestParam {
+            name: "list_recent_actions".into(),
+            arguments: None,
+        };
+        let result = forwarder.call_tool(params, Meta::new()).await.unwrap();
+        assert_ne!(result.is_error, Some(true));
+
+        // The front reports the helper's backend probes
+        let result = front
+            .get_server_capabilities(Parameters(EmptyParams {}))
+            .await
+            .unwrap();
+        let text = &result.content[0].as_text().unwrap().text;
+        let report: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert!(!report["backends"].as_array().unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(test_dir("split-test"));
+    }
+}
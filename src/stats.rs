@@ -0,0 +1,330 @@
+//! Per-tool call statistics
+//!
+//! Counts calls, errors and latency for every tool, reported by `get_server_stats`
+//! and, when `[metrics] listen` is set, served in Prometheus text format.
+
+use rmcp::{model::*, ErrorData as McpError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::ratelimit::RateLimiter;
+use crate::shared::internal_error;
+
+/// Latency histogram bucket upper bounds, in seconds
+const BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Default, Clone)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    /// Non-cumulative counts per bucket; the last slot is +Inf
+    buckets: [u64; BUCKETS.len() + 1],
+    total_secs: f64,
+    max_secs: f64,
+}
+
+impl ToolStats {
+    /// Upper bound of the bucket holding the given quantile
+    fn quantile(&self, q: f64) -> Option<f64> {
+        if self.calls == 0 {
+            return None;
+        }
+        let target = (self.calls as f64 * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(BUCKETS.get(i).copied().unwrap_or(self.max_secs));
+            }
+        }
+        Some(self.max_secs)
+    }
+}
+
+/// Call statistics for one server process
+#[derive(Debug)]
+pub struct Stats {
+    started: Instant,
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            tools: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl Stats {
+    /// Record one finished call
+    pub fn record(&self, tool_name: &str, elapsed: Duration, success: bool) {
+        let secs = elapsed.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(BUCKETS.len());
+
+        let mut tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = tools.entry(tool_name.to_string()).or_default();
+        stats.calls += 1;
+        if !success {
+            stats.errors += 1;
+        }
+        stats.buckets[bucket] += 1;
+        stats.total_secs += secs;
+        stats.max_secs = stats.max_secs.max(secs);
+    }
+
+    /// Per-tool summary, busiest tools first
+    pub fn snapshot(&self) -> Vec<ToolSummary> {
+        let tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let ms = |secs: f64| (secs * 1000.0 * 10.0).round() / 10.0;
+
+        let mut summary: Vec<ToolSummary> = tools
+            .iter()
+            .map(|(name, stats)| ToolSummary {
+                tool: name.clone(),
+                calls: stats.calls,
+                errors: stats.errors,
+                mean_ms: ms(stats.total_secs / stats.calls.max(1) as f64),
+                p50_ms: stats.quantile(0.5).map(ms),
+                p95_ms: stats.quantile(0.95).map(ms),
+                max_ms: ms(stats.max_secs),
+            })
+            .collect();
+        summary.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool.cmp(&b.tool)));
+        summary
+    }
+
+    /// Render all counters in Prometheus text exposition format
+    pub fn prometheus(&self) -> String {
+        let tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP rmcp_presence_uptime_seconds Seconds since the server started"
+        );
+        let _ = writeln!(out, "# TYPE rmcp_presence_uptime_seconds gauge");
+        let _ = writeln!(
+            out,
+            "rmcp_presence_uptime_seconds {}",
+            self.started.elapsed().as_secs()
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP rmcp_presence_tool_calls_total Tool calls, including failed ones"
+        );
+        let _ = writeln!(out, "# TYPE rmcp_presence_tool_calls_total counter");
+        for (name, stats) in tools.iter() {
+            let name = escape_label(name);
+            let _ = writeln!(
+                out,
+                "rmcp_presence_tool_calls_total{{tool=\"{}\"}} {}",
+                name, stats.calls
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP rmcp_presence_tool_errors_total Tool calls that returned an error"
+        );
+        let _ = writeln!(out, "# TYPE rmcp_presence_tool_errors_total counter");
+        for (name, stats) in tools.iter() {
+            let name = escape_label(name);
+            let _ = writeln!(
+                out,
+                "rmcp_presence_tool_errors_total{{tool=\"{}\"}} {}",
+                name, stats.errors
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP rmcp_presence_tool_duration_seconds Tool call latency"
+        );
+        let _ = writeln!(out, "# TYPE rmcp_presence_tool_duration_seconds histogram");
+        for (name, stats) in tools.iter() {
+            let name = escape_label(name);
+            let mut cumulative = 0;
+            for (i, count) in stats.buckets.iter().enumerate() {
+                cumulative += count;
+                let le = BUCKETS
+                    .get(i)
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                let _ = writeln!(
+                    out,
+                    "rmcp_presence_tool_duration_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    name, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "rmcp_presence_tool_duration_seconds_sum{{tool=\"{}\"}} {}",
+                name, stats.total_secs
+            );
+            let _ = writeln!(
+                out,
+                "rmcp_presence_tool_duration_seconds_count{{tool=\"{}\"}} {}",
+                name, stats.calls
+            );
+        }
+
+        out
+    }
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// One tool's usage, as reported by `get_server_stats`
+#[derive(Debug, Serialize)]
+pub struct ToolSummary {
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub mean_ms: f64,
+    /// Upper bound of the histogram bucket holding the median
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_ms: f64,
+}
+
+// === Prometheus Endpoint ===
+
+/// Serve `GET /metrics` on `addr` until the process exits
+pub async fn serve_metrics(addr: String, stats: Arc<Stats>) {
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Could not start metrics endpoint on {}: {}", addr, e);
+            return;
+        }
+    };
+    tracing::info!("Prometheus metrics on http://{}/metrics", addr);
+
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let stats = stats.clone();
+
+        tokio::spawn(async move {
+            // Only the request line matters; anything past the first read is ignored
+            let mut buf = [0u8; 1024];
+            let n = match tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await
+            {
+                Ok(Ok(n)) => n,
+                _ => return,
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+
+            let (status, body) = if request.starts_with("GET ") && path == "/metrics" {
+                ("200 OK", stats.prometheus())
+            } else {
+                ("404 Not Found", "not found\n".to_string())
+            };
+
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+// === Tool Functions ===
+
+pub async fn get_server_stats(
+    stats: &Stats,
+    limiter: &RateLimiter,
+    enabled_tools: Vec<String>,
+) -> Result<CallToolResult, McpError> {
+    let tools = stats.snapshot();
+    let mut unused: Vec<String> = enabled_tools
+        .into_iter()
+        .filter(|name| !tools.iter().any(|t| &t.tool == name))
+        .collect();
+    unused.sort();
+
+    let report = serde_json::json!({
+        "uptime_secs": stats.started.elapsed().as_secs(),
+        "total_calls": tools.iter().map(|t| t.calls).sum::<u64>(),
+        "tools": tools,
+        "unused_tools": unused,
+        "rate_limits": limiter.snapshot(),
+    });
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_quantiles() {
+        let stats = Stats::default();
+        for ms in [1, 2, 3, 40, 2000] {
+            stats.record("get_context", Duration::from_millis(ms), ms != 2000);
+        }
+
+        let summary = &stats.snapshot()[0];
+        assert_eq!(summary.calls, 5);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.p50_ms, Some(5.0));
+        assert_eq!(summary.p95_ms, Some(2500.0));
+        assert_eq!(summary.max_ms, 2000.0);
+    }
+
+    #[test]
+    fn test_prometheus_histogram_is_cumulative() {
+        let stats = Stats::default();
+        stats.record("click_at", Duration::from_millis(3), true);
+        stats.record("click_at", Duration::from_millis(60), true);
+
+        let text = stats.prometheus();
+        assert!(text.contains("rmcp_presence_tool_calls_total{tool=\"click_at\"} 2"));
+        assert!(text.contains(
+            "rmcp_presence_tool_duration_seconds_bucket{tool=\"click_at\",le=\"0.005\"} 1"
+        ));
+        assert!(text.contains(
+            "rmcp_presence_tool_duration_seconds_bucket{tool=\"click_at\",le=\"0.1\"} 2"
+        ));
+        assert!(text.contains(
+            "rmcp_presence_tool_duration_seconds_bucket{tool=\"click_at\",le=\"+Inf\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_prometheus_escapes_labels() {
+        let stats = Stats::default();
+        stats.record("a\"b\\c\nd", Duration::from_millis(3), true);
+        let text = stats.prometheus();
+        assert!(text.contains("rmcp_presence_tool_calls_total{tool=\"a\\\"b\\\\c\\nd\"} 1"));
+    }
+}
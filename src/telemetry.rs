@@ -0,0 +1,444 @@
+//! OpenTelemetry trace export (`otel` feature)
+//!
+//! A tracing layer turns every `tool_call` span and the backend spans below it
+//! (`subprocess`, `http.request`, `dbus`) into OTLP spans, posted as JSON to
+//! `<endpoint>/v1/traces`. A W3C `traceparent` from the request's `_meta` makes the
+//! tool call a child of the client's span, so both sides end up in one trace.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::OtelConfig;
+
+/// Name of the span opened for every tool call
+pub const TOOL_CALL: &str = "tool_call";
+
+/// How long the exporter waits to collect a batch after the first span arrives
+const BATCH_DELAY: Duration = Duration::from_secs(2);
+
+const MAX_BATCH: usize = 512;
+
+/// Spans waiting for export; further spans are dropped while the queue is full
+const QUEUE_CAPACITY: usize = 4 * MAX_BATCH;
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// OTLP SpanKind and StatusCode values
+const KIND_INTERNAL: u8 = 1;
+const KIND_SERVER: u8 = 2;
+const KIND_CLIENT: u8 = 3;
+const STATUS_OK: u8 = 1;
+const STATUS_ERROR: u8 = 2;
+
+/// Set once an exporter is running; until then the layer records nothing
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Receiving end of the layer's channel, claimed by `start`
+static PENDING: Mutex<Option<mpsc::Receiver<SpanData>>> = Mutex::new(None);
+
+/// Spans dropped because the export queue was full, since the last report
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+enum AttrValue {
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+}
+
+/// A span being recorded, stored in the tracing span's extensions until it closes
+#[derive(Debug, Clone)]
+struct SpanData {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_id: Option<[u8; 8]>,
+    name: &'static str,
+    kind: u8,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, AttrValue)>,
+}
+
+impl SpanData {
+    fn attribute(&self, key: &str) -> Option<&AttrValue> {
+        self.attributes
+            .iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    fn failed(&self) -> bool {
+        match self.attribute("outcome") {
+            Some(AttrValue::Str(outcome)) => outcome != "ok",
+            _ => self.attribute("error").is_some(),
+        }
+    }
+}
+
+/// Collects span fields; `traceparent` is kept apart since it only links the trace
+#[derive(Default)]
+struct FieldVisitor {
+    attributes: Vec<(&'static str, AttrValue)>,
+    traceparent: Option<String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "traceparent" {
+            self.traceparent = Some(value.to_string());
+        } else {
+            self.attributes
+                .push((field.name(), AttrValue::Str(value.to_string())));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.attributes.push((field.name(), AttrValue::Int(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.attributes.push((
+            field.name(),
+            AttrValue::Int(value.min(i64::MAX as u64) as i64),
+        ));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.attributes
+            .push((field.name(), AttrValue::Double(value)));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.attributes.push((field.name(), AttrValue::Bool(value)));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// Tracing layer feeding the OTLP exporter
+pub struct OtelLayer {
+    sender: mpsc::Sender<SpanData>,
+}
+
+/// Create the layer; spans are only recorded once `start` finds an endpoint
+pub fn layer() -> OtelLayer {
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    *PENDING.lock().unwrap_or_else(|e| e.into_inner()) = Some(receiver);
+    OtelLayer { sender }
+}
+
+impl<S> Layer<S> for OtelLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let name = attrs.metadata().name();
+
+        let (trace_id, parent_id) = if name == TOOL_CALL {
+            match visitor.traceparent.as_deref().and_then(parse_traceparent) {
+                Some((trace_id, parent_id)) => (trace_id, Some(parent_id)),
+                None => (random_id(), None),
+            }
+        } else {
+            // Backend spans are exported only below a tool call
+            let parent = span.scope().skip(1).find_map(|ancestor| {
+                ancestor
+                    .extensions()
+                    .get::<SpanData>()
+                    .map(|data| (data.trace_id, data.span_id))
+            });
+            match parent {
+                Some((trace_id, parent_id)) => (trace_id, Some(parent_id)),
+                None => return,
+            }
+        };
+
+        let kind = match name {
+            TOOL_CALL => KIND_SERVER,
+            "http.request" | "dbus" => KIND_CLIENT,
+            _ => KIND_INTERNAL,
+        };
+
+        let now = SystemTime::now();
+        span.extensions_mut().insert(SpanData {
+            trace_id,
+            span_id: random_id(),
+            parent_id,
+            name,
+            kind,
+            start: now,
+            end: now,
+            attributes: visitor.attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<SpanData>() {
+            let mut visitor = FieldVisitor::default();
+            values.record(&mut visitor);
+            data.attributes.extend(visitor.attributes);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let data = span.extensions_mut().remove::<SpanData>();
+        if let Some(mut data) = data {
+            data.end = SystemTime::now();
+            // Never block the traced code on a slow or unreachable collector
+            if self.sender.try_send(data).is_err() {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Start exporting if an endpoint is configured. Needs a Tokio runtime.
+pub fn start(config: &OtelConfig) {
+    let Some(receiver) = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    let Some(endpoint) = config.endpoint() else {
+        return;
+    };
+
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let service_name = config
+        .service_name
+        .clone()
+        .unwrap_or_else(|| "rmcp-presence".to_string());
+    tracing::info!("Exporting traces to {}", url);
+
+    ENABLED.store(true, Ordering::Relaxed);
+    tokio::spawn(export(receiver, url, service_name));
+}
+
+async fn export(mut receiver: mpsc::Receiver<SpanData>, url: String, service_name: String) {
+    let client = reqwest::Client::new();
+
+    while let Some(first) = receiver.recv().await {
+        let mut batch = vec![first];
+        tokio::time::sleep(BATCH_DELAY).await;
+        while batch.len() < MAX_BATCH {
+            match receiver.try_recv() {
+                Ok(span) => batch.push(span),
+                Err(_) => break,
+            }
+        }
+
+        let body = encode(&service_name, &batch);
+        match client
+            .post(&url)
+            .json(&body)
+            .timeout(EXPORT_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => tracing::warn!(
+                "Trace export to {} failed with status {}",
+                url,
+                response.status()
+            ),
+            Err(e) => tracing::warn!("Trace export to {} failed: {}", url, e),
+        }
+
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::warn!(
+                "Dropped {} spans because the export queue was full",
+                dropped
+            );
+        }
+    }
+}
+
+/// OTLP/HTTP JSON body for a batch of spans
+fn encode(service_name: &str, spans: &[SpanData]) -> serde_json::Value {
+    let spans: Vec<serde_json::Value> = spans
+        .iter()
+        .map(|span| {
+            let mut value = serde_json::json!({
+                "traceId": hex(&span.trace_id),
+                "spanId": hex(&span.span_id),
+                "name": span.name,
+                "kind": span.kind,
+                "startTimeUnixNano": unix_nanos(span.start).to_string(),
+                "endTimeUnixNano": unix_nanos(span.end).to_string(),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+                "status": {
+                    "code": if span.failed() { STATUS_ERROR } else { STATUS_OK },
+                },
+            });
+            if let Some(parent_id) = span.parent_id {
+                value["parentSpanId"] = hex(&parent_id).into();
+            }
+            value
+        })
+        .collect();
+
+    serde_json::json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &AttrValue::Str(service_name.to_string()))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "rmcp-presence", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: &AttrValue) -> serde_json::Value {
+    let value = match value {
+        AttrValue::Str(s) => serde_json::json!({ "stringValue": s }),
+        // OTLP JSON encodes 64-bit integers as strings
+        AttrValue::Int(i) => serde_json::json!({ "intValue": i.to_string() }),
+        AttrValue::Double(d) => serde_json::json!({ "doubleValue": d }),
+        AttrValue::Bool(b) => serde_json::json!({ "boolValue": b }),
+    };
+    serde_json::json!({ "key": key, "value": value })
+}
+
+/// Parse a W3C `traceparent` header into trace ID and parent span ID
+fn parse_traceparent(value: &str) -> Option<([u8; 16], [u8; 8])> {
+    let mut parts = value.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let parent_id = parts.next()?;
+    let flags = parts.next()?;
+
+    if version.len() != 2 || version == "ff" || flags.len() != 2 {
+        return None;
+    }
+    // Version 00 has exactly four fields; later versions may append more
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+
+    let trace_id: [u8; 16] = unhex(trace_id)?;
+    let parent_id: [u8; 8] = unhex(parent_id)?;
+    unhex::<1>(flags)?;
+
+    if trace_id == [0; 16] || parent_id == [0; 8] {
+        return None;
+    }
+    Some((trace_id, parent_id))
+}
+
+/// W3C `traceparent` for the current span, for passing the trace on to the helper
+pub fn current_traceparent() -> Option<String> {
+    let mut traceparent = None;
+    tracing::Span::current().with_subscriber(|(id, dispatch)| {
+        let registry = dispatch.downcast_ref::<tracing_subscriber::Registry>()?;
+        let span = registry.span(id)?;
+        let extensions = span.extensions();
+        let data = extensions.get::<SpanData>()?;
+        traceparent = Some(format!(
+            "00-{}-{}-01",
+            hex(&data.trace_id),
+            hex(&data.span_id)
+        ));
+        Some(())
+    });
+    traceparent
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    let mut out = [0u8; N];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(out)
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+/// Random non-zero ID. `RandomState` is seeded from the OS, which is enough
+/// for trace IDs without pulling in an RNG crate.
+fn random_id<const N: usize>() -> [u8; N] {
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+
+    let mut out = [0u8; N];
+    for chunk in out.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u128(unix_nanos(SystemTime::now()));
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes()[..chunk.len()]);
+    }
+    if out.iter().all(|b| *b == 0) {
+        out[0] = 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let (trace_id, parent_id) =
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(hex(&trace_id), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(hex(&parent_id), "00f067aa0ba902b7");
+
+        // Invalid IDs, uppercase hex and the forbidden version are rejected
+        assert!(
+            parse_traceparent("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").is_none()
+        );
+        assert!(
+            parse_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7").is_none()
+        );
+    }
+}
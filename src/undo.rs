@@ -0,0 +1,501 @@
+//! Undo journal for reversible actuator changes
+//!
+//! Before a reversible actuator (volume, brightness, default devices, per-app volume,
+//! clipboard, player volume, focused workspace, trash) changes something, it records
+//! the previous state here. `list_recent_actions` shows the journal and `undo_action`
+//! puts the recorded state back. The journal lives in memory and is lost on restart.
+//!
+//! Only calls that report success are journaled, and an entry is marked undone only
+//! once its restore succeeded. A restore is subject to the policies, rate limits and
+//! privacy safeguards of the tool that made the original change.
+
+use rmcp::{model::*, service::Peer, ErrorData as McpError, RoleServer};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+#[cfg(feature = "actuators")]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::shared::internal_error;
+use crate::PresenceServer;
+
+/// Oldest entries are dropped beyond this
+const MAX_ENTRIES: usize = 100;
+
+// === Parameter Types ===
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListActionsParams {
+    #[schemars(description = "Maximum number of actions to list, newest first (default: 20)")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UndoParams {
+    #[schemars(description = "Action ID from list_recent_actions")]
+    pub id: u64,
+    #[schemars(
+        description = "Undo even if a later action changed the same thing (default: false)"
+    )]
+    #[serde(default)]
+    pub force: bool,
+}
+
+// === Journal ===
+
+/// State as it was before a reversible change
+#[derive(Debug, Clone)]
+pub enum Prior {
+    #[cfg(feature = "actuators")]
+    SystemVolume { volume: u8 },
+    #[cfg(feature = "actuators")]
+    SystemMute { muted: bool },
+    #[cfg(feature = "actuators")]
+    Clipboard { text: Option<String> },
+    #[cfg(feature = "actuators")]
+    Trashed { paths: Vec<PathBuf> },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    Brightness { device: String, level: u32 },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    DefaultSink { name: String },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    DefaultSource { name: String },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    AppVolume { index: u32, volumes: Vec<u32> },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    AppMute { index: u32, muted: bool },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    PlayerVolume { player: String, volume: f64 },
+    #[cfg(all(feature = "linux", target_os = "linux"))]
+    Workspace { name: String },
+}
+
+impl Prior {
+    /// What the change touched; a later action on the same target makes undo unsafe
+    fn target(&self) -> String {
+        match self {
+            #[cfg(feature = "actuators")]
+            Prior::SystemVolume { .. } => "system_volume".to_string(),
+            #[cfg(feature = "actuators")]
+            Prior::SystemMute { .. } => "system_mute".to_string(),
+            #[cfg(feature = "actuators")]
+            Prior::Clipboard { .. } => "clipboard".to_string(),
+            #[cfg(feature = "actuators")]
+            Prior::Trashed { paths } => format!("trash:{:?}", paths),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Brightness { device, .. } => format!("brightness:{}", device),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSink { .. } => "default_sink".to_string(),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSource { .. } => "default_source".to_string(),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppVolume { index, .. } => format!("app_volume:{}", index),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppMute { index, .. } => format!("app_mute:{}", index),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::PlayerVolume { player, .. } => format!("player_volume:{}", player),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Workspace { .. } => "workspace".to_string(),
+        }
+    }
+
+    /// The recorded state in words. Clipboard contents are never shown.
+    fn describe(&self) -> String {
+        match self {
+            #[cfg(feature = "actuators")]
+            Prior::SystemVolume { volume } => format!("system volume was {}%", volume),
+            #[cfg(feature = "actuators")]
+            Prior::SystemMute { muted } => {
+                format!(
+                    "system audio was {}",
+                    if *muted { "muted" } else { "unmuted" }
+                )
+            }
+            #[cfg(feature = "actuators")]
+            Prior::Clipboard { text: Some(text) } => {
+                format!("clipboard held {} chars of text", text.chars().count())
+            }
+            #[cfg(feature = "actuators")]
+            Prior::Clipboard { text: None } => "clipboard was empty".to_string(),
+            #[cfg(feature = "actuators")]
+            Prior::Trashed { paths } => format!(
+                "{} was in place",
+                paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Brightness { device, level } => format!("{} brightness was {}%", device, level),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSink { name } => format!("default sink was {}", name),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSource { name } => format!("default source was {}", name),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppVolume { index, volumes } => format!(
+                "app {} volume was {:.0}%",
+                index,
+                volumes.first().copied().unwrap_or(0) as f64 / 65536.0 * 100.0
+            ),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppMute { index, muted } => format!(
+                "app {} was {}",
+                index,
+                if *muted { "muted" } else { "unmuted" }
+            ),
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::PlayerVolume { player, volume } => {
+                format!("{} volume was {:.0}%", player, volume * 100.0)
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Workspace { name } => format!("workspace '{}' was focused", name),
+        }
+    }
+
+    /// Put the recorded state back
+    async fn restore(&self) -> Result<CallToolResult, McpError> {
+        match self.clone() {
+            #[cfg(feature = "actuators")]
+            Prior::SystemVolume { volume } => {
+                crate::actuators::audio::set_volume(crate::actuators::audio::SetVolumeParams {
+                    volume,
+                })
+                .await
+            }
+            #[cfg(feature = "actuators")]
+            Prior::SystemMute { muted } => {
+                crate::actuators::audio::set_mute(crate::actuators::audio::SetMuteParams { muted })
+                    .await
+            }
+            #[cfg(feature = "actuators")]
+            Prior::Clipboard { text: Some(text) } => {
+                crate::actuators::clipboard::write_clipboard(
+                    crate::actuators::clipboard::WriteClipboardParams { text },
+                )
+                .await
+            }
+            #[cfg(feature = "actuators")]
+            Prior::Clipboard { text: None } => crate::actuators::clipboard::clear_clipboard().await,
+            #[cfg(feature = "actuators")]
+            Prior::Trashed { paths } => crate::actuators::trash::restore_paths(&paths).await,
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Brightness { device, level } => {
+                crate::linux::brightness::set_brightness(
+                    crate::linux::brightness::SetBrightnessParams {
+                        brightness: level,
+                        device: Some(device),
+                    },
+                )
+                .await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSink { name } => {
+                crate::linux::pulseaudio::set_default_sink(crate::linux::pulseaudio::NameParams {
+                    name,
+                })
+                .await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::DefaultSource { name } => {
+                crate::linux::pulseaudio::set_default_source(crate::linux::pulseaudio::NameParams {
+                    name,
+                })
+                .await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppVolume { index, volumes } => {
+                crate::linux::pulseaudio::restore_sink_input_volume(index, volumes).await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::AppMute { index, muted } => {
+                crate::linux::pulseaudio::set_sink_input_mute(
+                    crate::linux::pulseaudio::MuteParams { index, mute: muted },
+                )
+                .await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::PlayerVolume { player, volume } => {
+                crate::linux::mpris::set_player_volume(crate::linux::mpris::SetVolumeParams {
+                    volume,
+                    player: Some(player),
+                })
+                .await
+            }
+            #[cfg(all(feature = "linux", target_os = "linux"))]
+            Prior::Workspace { name } => {
+                crate::linux::i3::switch_workspace(crate::linux::i3::SwitchWorkspaceParams {
+                    workspace: format!("\"{}\"", name.replace('"', "\\\"")),
+                })
+                .await
+            }
+        }
+    }
+}
+
+/// Prior state for a trash call: the absolute paths that are about to be trashed
+#[cfg(feature = "actuators")]
+pub fn trashed(paths: &[String]) -> Result<Prior, String> {
+    let paths: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(|p| std::fs::canonicalize(p).ok())
+        .collect();
+    if paths.is_empty() {
+        return Err("No existing paths to trash".to_string());
+    }
+    Ok(Prior::Trashed { paths })
+}
+
+#[derive(Debug, Clone)]
+struct Action {
+    id: u64,
+    tool: String,
+    at: chrono::DateTime<chrono::Local>,
+    prior: Prior,
+    undone: bool,
+}
+
+/// One journal entry, as listed by `list_recent_actions`
+#[derive(Debug, Serialize)]
+pub struct ActionSummary {
+    pub id: u64,
+    pub tool: String,
+    pub at: String,
+    pub previous: String,
+    pub undone: bool,
+}
+
+/// Recent reversible changes made by this server process
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Mutex<VecDeque<Action>>,
+    next_id: AtomicU64,
+}
+
+impl Journal {
+    /// Record the prior state of a change once the tool has succeeded
+    pub fn record(
+        &self,
+        tool: &str,
+        prior: Result<Prior, String>,
+        result: &Result<CallToolResult, McpError>,
+    ) {
+        if !succeeded(result) {
+            return;
+        }
+        let prior = match prior {
+            Ok(prior) => prior,
+            Err(e) => {
+                tracing::warn!("{} cannot be undone, previous state unknown: {}", tool, e);
+                return;
+            }
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.push_back(Action {
+            id,
+            tool: tool.to_string(),
+            at: chrono::Local::now(),
+            prior,
+            undone: false,
+        });
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    /// Newest entries first
+    pub fn recent(&self, limit: usize) -> Vec<ActionSummary> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .iter()
+            .rev()
+            .take(limit)
+            .map(|action| ActionSummary {
+                id: action.id,
+                tool: action.tool.clone(),
+                at: action.at.to_rfc3339(),
+                previous: action.prior.describe(),
+                undone: action.undone,
+            })
+            .collect()
+    }
+
+    /// Find an entry that can be undone, refusing if a later action touched the same target
+    fn undoable(&self, id: u64, force: bool) -> Result<(String, Prior), McpError> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let position = entries
+            .iter()
+            .position(|action| action.id == id)
+            .ok_or_else(|| {
+                McpError::invalid_params(format!("No action with id {} in the journal", id), None)
+            })?;
+
+        let action = &entries[position];
+        if action.undone {
+            return Err(McpError::invalid_params(
+                format!("Action {} was already undone", id),
+                None,
+            ));
+        }
+
+        if !force {
+            let target = action.prior.target();
+            if let Some(later) = entries
+                .iter()
+                .skip(position + 1)
+                .find(|later| !later.undone && later.prior.target() == target)
+            {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "Action {} ({}) changed the same thing later; undo it first or pass force",
+                        later.id, later.tool
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        Ok((action.tool.clone(), action.prior.clone()))
+    }
+
+    fn mark_undone(&self, id: u64) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(action) = entries.iter_mut().find(|action| action.id == id) {
+            action.undone = true;
+        }
+    }
+}
+
+// === Tool Functions ===
+
+pub async fn list_recent_actions(
+    journal: &Journal,
+    params: ListActionsParams,
+) -> Result<CallToolResult, McpError> {
+    let actions = journal.recent(params.limit.unwrap_or(20));
+    if actions.is_empty() {
+        return Ok(CallToolResult::success(vec![Content::text(
+            "No reversible actions recorded yet",
+        )]));
+    }
+
+    let json = serde_json::to_string_pretty(&actions)
+        .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+pub async fn undo_action(
+    server: &PresenceServer,
+    peer: &Peer<RoleServer>,
+    params: UndoParams,
+) -> Result<CallToolResult, McpError> {
+    let (tool, prior) = server.journal.undoable(params.id, params.force)?;
+    let capture = server.check_policy(&tool, peer).await?;
+
+    let result = prior.restore().await;
+    if let Some(capture) = capture {
+        capture.finish(succeeded(&result));
+    }
+    finish(&server.journal, params.id, &prior, result)
+}
+
+/// Mark the entry undone if the restore worked and report the outcome
+fn finish(
+    journal: &Journal,
+    id: u64,
+    prior: &Prior,
+    result: Result<CallToolResult, McpError>,
+) -> Result<CallToolResult, McpError> {
+    let ok = succeeded(&result);
+    let result = result?;
+    let summary = if ok {
+        journal.mark_undone(id);
+        format!("Undid action {}: {}", id, prior.describe())
+    } else {
+        format!("Could not undo action {} ({})", id, prior.describe())
+    };
+
+    let mut content = vec![Content::text(summary)];
+    content.extend(result.content);
+    Ok(CallToolResult { content, ..result })
+}
+
+/// Backends undo relies on report failures with `is_error`
+fn succeeded(result: &Result<CallToolResult, McpError>) -> bool {
+    matches!(result, Ok(r) if r.is_error != Some(true))
+}
+
+#[cfg(all(test, feature = "actuators"))]
+mod tests {
+    use super::*;
+
+    fn ok() -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text("done")]))
+    }
+
+    #[test]
+    fn test_journal_conflicts() {
+        let journal = Journal::default();
+        journal.record("set_volume", Ok(Prior::SystemVolume { volume: 30 }), &ok());
+        journal.record("set_volume", Ok(Prior::SystemVolume { volume: 50 }), &ok());
+        journal.record("write_clipboard", Err("no clipboard".to_string()), &ok());
+        journal.record(
+            "set_mute",
+            Ok(Prior::SystemMute { muted: false }),
+            &Err(internal_error("failed")),
+        );
+
+        let recent = journal.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].id, 2);
+        assert_eq!(recent[0].previous, "system volume was 50%");
+
+        // The older volume change is shadowed by the newer one
+        assert!(journal.undoable(1, false).is_err());
+        assert!(journal.undoable(1, true).is_ok());
+
+        assert!(journal.undoable(2, false).is_ok());
+        journal.mark_undone(2);
+        assert!(journal.undoable(2, false).is_err());
+        assert!(journal.undoable(1, false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failed_action_not_recorded() {
+        let journal = Journal::default();
+        let params = crate::actuators::trash::TrashFileParams {
+            path: "/nonexistent/rmcp-presence-undo-test".to_string(),
+        };
+        let prior = Ok(Prior::Trashed {
+            paths: vec![PathBuf::from(&params.path)],
+        });
+        let result = crate::actuators::trash::trash_file(params).await;
+        assert_eq!(result.as_ref().unwrap().is_error, Some(true));
+
+        journal.record("trash_file", prior, &result);
+        assert!(journal.recent(10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_stays_undoable() {
+        let journal = Journal::default();
+        let prior = Prior::Trashed {
+            paths: vec![PathBuf::from("/nonexistent/rmcp-presence-undo-test")],
+        };
+        journal.record("trash_file", Ok(prior.clone()), &ok());
+
+        // Nothing by that name is in the trash, so the restore fails
+        let result = finish(&journal, 1, &prior, prior.restore().await).unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(!journal.recent(1)[0].undone);
+        assert!(journal.undoable(1, false).is_ok());
+
+        finish(&journal, 1, &prior, ok()).unwrap();
+        assert!(journal.recent(1)[0].undone);
+    }
+}
@@ -0,0 +1,354 @@
+//! Config-defined workflows (`[workflows.<name>]` in tools.toml)
+//!
+//! A workflow is a single tool that runs its steps in order. A step can be made
+//! conditional on an earlier step's result, has its own timeout, and by default stops
+//! the workflow when it fails. Steps go through the same dispatch as direct calls and
+//! are audited one by one.
+//!
+//! A step fails when the call errors or the tool flags its result as an error. Some
+//! built-in tools report failures as ordinary text, so a step can set `fail_if` to a
+//! pattern that marks such output as failed.
+
+use futures::future::FutureExt;
+use regex::Regex;
+use rmcp::{
+    handler::server::tool::{schema_for_type, ToolCallContext, ToolRoute},
+    model::*,
+    service::RequestContext,
+    ErrorData as McpError, RoleServer,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
+
+use crate::audit;
+use crate::composites;
+use crate::config::{self, Config, StepCondition, WorkflowConfig, WorkflowStep};
+use crate::shared::internal_error;
+use crate::{EmptyParams, PresenceServer};
+
+const DEFAULT_STEP_TIMEOUT_SECS: u64 = 30;
+
+/// Router entries for the configured workflows. Invalid ones are skipped with a warning.
+pub fn routes(config: &Config) -> Vec<ToolRoute<PresenceServer>> {
+    // Steps may call composites, but not other workflows
+    let mut known = config::all_tool_names();
+    known.extend(config.custom_tools.keys().map(String::as_str));
+    known.extend(config.composites.keys().map(String::as_str));
+    let mut routes = Vec::new();
+
+    for (name, workflow) in &config.workflows {
+        if let Err(e) = validate(name, workflow, &known) {
+            tracing::warn!("Skipping workflow {}: {}", name, e);
+            continue;
+        }
+
+        let description = workflow.description.clone().unwrap_or_else(|| {
+            let steps: Vec<&str> = workflow.steps.iter().map(|s| s.tool.as_str()).collect();
+            format!("Workflow: runs {} in order", steps.join(", "))
+        });
+        let tool = Tool::new(name.clone(), description, schema_for_type::<EmptyParams>());
+        tracing::info!("Registered workflow tool: {}", name);
+
+        let name = Arc::new(name.clone());
+        let workflow = Arc::new(workflow.clone());
+        routes.push(ToolRoute::new_dyn(
+            tool,
+            move |context: ToolCallContext<'_, PresenceServer>| {
+                let name = name.clone();
+                let workflow = workflow.clone();
+                async move { run(context.service, &name, &workflow, context.request_context).await }
+                    .boxed()
+            },
+        ));
+    }
+
+    routes
+}
+
+fn validate(name: &str, workflow: &WorkflowConfig, known: &[&str]) -> Result<(), String> {
+    if known.contains(&name) {
+        return Err("name clashes with another tool".to_string());
+    }
+    if workflow.steps.is_empty() {
+        return Err("no steps listed".to_string());
+    }
+
+    let mut earlier: Vec<&str> = Vec::new();
+    for step in &workflow.steps {
+        if !known.contains(&step.tool.as_str()) {
+            return Err(format!("unknown tool '{}'", step.tool));
+        }
+        if let Some(condition) = &step.when {
+            if !earlier.contains(&condition.step.as_str()) {
+                return Err(format!(
+                    "step '{}' depends on '{}', which is not an earlier step",
+                    step.id(),
+                    condition.step
+                ));
+            }
+            if let Some(pattern) = &condition.matches {
+                Regex::new(pattern).map_err(|e| format!("invalid regex '{}': {}", pattern, e))?;
+            }
+        }
+        if let Some(pattern) = &step.fail_if {
+            Regex::new(pattern).map_err(|e| format!("invalid regex '{}': {}", pattern, e))?;
+        }
+        if earlier.contains(&step.id()) {
+            return Err(format!(
+                "duplicate step id '{}' (set `id` on repeated tools)",
+                step.id()
+            ));
+        }
+        earlier.push(step.id());
+    }
+    Ok(())
+}
+
+/// How a step ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Error,
+    Timeout,
+    Skipped,
+    NotRun,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Error => "error",
+            Status::Timeout => "timeout",
+            Status::Skipped => "skipped",
+            Status::NotRun => "not_run",
+        }
+    }
+}
+
+/// A finished step, as seen by later conditions
+struct Outcome {
+    status: Status,
+    text: String,
+}
+
+async fn run(
+    server: &PresenceServer,
+    name: &str,
+    workflow: &WorkflowConfig,
+    context: RequestContext<RoleServer>,
+) -> Result<CallToolResult, McpError> {
+    let mut outcomes: HashMap<&str, Outcome> = HashMap::new();
+    let mut report = Vec::new();
+    let mut stopped = false;
+
+    for step in &workflow.steps {
+        let mut output = serde_json::Value::Null;
+        let status = if stopped {
+            Status::NotRun
+        } else if !step
+            .when
+            .as_ref()
+            .is_none_or(|condition| holds(condition, &outcomes))
+        {
+            Status::Skipped
+        } else {
+            let request = CallToolRequestParam {
+                name: step.tool.clone().into(),
+                arguments: Some(step.arguments.clone()),
+            };
+            let timeout = step.timeout_secs.unwrap_or(DEFAULT_STEP_TIMEOUT_SECS);
+            let call = server
+                .dispatch(&step.tool, request, context.clone())
+                .instrument(
+                    tracing::info_span!("workflow_step", step = step.id(), tool = %step.tool),
+                );
+
+            let (status, text) =
+                match tokio::time::timeout(Duration::from_secs(timeout), call).await {
+                    Ok(Ok(result)) if !failed(step, &result) => {
+                        let text = composites::text_of(&result);
+                        output = composites::output(Ok(result));
+                        (Status::Ok, text)
+                    }
+                    Ok(result) => {
+                        let text = match &result {
+                            Ok(result) => composites::text_of(result),
+                            Err(e) => e.message.to_string(),
+                        };
+                        output = composites::output(result);
+                        (Status::Error, text)
+                    }
+                    Err(_) => {
+                        let text = format!("Timed out after {}s", timeout);
+                        output = serde_json::json!({ "error": text });
+                        (Status::Timeout, text)
+                    }
+                };
+            if status != Status::Ok && !step.continue_on_error {
+                stopped = true;
+            }
+            outcomes.insert(step.id(), Outcome { status, text });
+            status
+        };
+
+        audit::workflow_step(name, step.id(), &step.tool, status.as_str());
+        report.push(serde_json::json!({
+            "step": step.id(),
+            "tool": step.tool,
+            "status": status.as_str(),
+            "output": output,
+        }));
+    }
+
+    let json = serde_json::to_string_pretty(&serde_json::json!({
+        "workflow": name,
+        "completed": !stopped,
+        "steps": report,
+    }))
+    .map_err(|e| internal_error(format!("Serialization error: {}", e)))?;
+
+    if stopped {
+        Ok(CallToolResult::error(vec![Content::text(json)]))
+    } else {
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+}
+
+/// Whether the tool flagged its result as an error or the output matches `fail_if`
+fn failed(step: &WorkflowStep, result: &CallToolResult) -> bool {
+    if result.is_error == Some(true) {
+        return true;
+    }
+    // Validated at startup
+    step.fail_if.as_ref().is_some_and(|pattern| {
+        Regex::new(pattern).is_ok_and(|re| re.is_match(&composites::text_of(result)))
+    })
+}
+
+/// Whether a condition holds for the earlier steps' outcomes
+fn holds(condition: &StepCondition, outcomes: &HashMap<&str, Outcome>) -> bool {
+    // Skipped steps have no outcome and satisfy nothing
+    let Some(outcome) = outcomes.get(condition.step.as_str()) else {
+        return false;
+    };
+
+    if let Some(ok) = condition.ok {
+        if (outcome.status == Status::Ok) != ok {
+            return false;
+        }
+    }
+    if let Some(text) = &condition.contains {
+        if !outcome.text.contains(text.as_str()) {
+            return false;
+        }
+    }
+    if let Some(text) = &condition.not_contains {
+        if outcome.text.contains(text.as_str()) {
+            return false;
+        }
+    }
+    if let Some(pattern) = &condition.matches {
+        // Validated at startup
+        if !Regex::new(pattern).is_ok_and(|re| re.is_match(&outcome.text)) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(tool: &str, when: Option<StepCondition>) -> WorkflowStep {
+        WorkflowStep {
+            tool: tool.to_string(),
+            when,
+            ..WorkflowStep::default()
+        }
+    }
+
+    fn after(step: &str) -> Option<StepCondition> {
+        Some(StepCondition {
+            step: step.to_string(),
+            ..StepCondition::default()
+        })
+    }
+
+    #[test]
+    fn test_validate() {
+        let known = ["is_idle_for", "lock_session", "media_pause"];
+        let away = WorkflowConfig {
+            description: None,
+            steps: vec![
+                step("is_idle_for", None),
+                step("lock_session", after("is_idle_for")),
+                step("media_pause", after("is_idle_for")),
+            ],
+        };
+        assert!(validate("away", &away, &known).is_ok());
+        assert!(validate("lock_session", &away, &known).is_err());
+
+        // Conditions only look back
+        let forward = WorkflowConfig {
+            description: None,
+            steps: vec![
+                step("lock_session", after("is_idle_for")),
+                step("is_idle_for", None),
+            ],
+        };
+        assert!(validate("away", &forward, &known).is_err());
+
+        let unknown = WorkflowConfig {
+            description: None,
+            steps: vec![step("away", None)],
+        };
+        assert!(validate("again", &unknown, &known).is_err());
+    }
+
+    #[test]
+    fn test_conditions() {
+        let mut outcomes = HashMap::new();
+        outcomes.insert(
+            "idle",
+            Outcome {
+                status: Status::Ok,
+                text: "Idle Check:\n\n  Is idle: YES\n".to_string(),
+            },
+        );
+
+        let idle = StepCondition {
+            step: "idle".to_string(),
+            ok: Some(true),
+            contains: Some("Is idle: YES".to_string()),
+            ..StepCondition::default()
+        };
+        assert!(holds(&idle, &outcomes));
+
+        let not_idle = StepCondition {
+            step: "idle".to_string(),
+            matches: Some(r"Is idle: NO".to_string()),
+            ..StepCondition::default()
+        };
+        assert!(!holds(&not_idle, &outcomes));
+
+        // A step that did not run satisfies nothing
+        assert!(!holds(&after("skipped").unwrap(), &outcomes));
+    }
+
+    #[test]
+    fn test_failed() {
+        let focus = WorkflowStep {
+            fail_if: Some("^Failed".to_string()),
+            ..step("focus_window", None)
+        };
+        let text = |text: &str| CallToolResult::success(vec![Content::text(text)]);
+        assert!(failed(&focus, &text("Failed to focus window: no match")));
+        assert!(!failed(&focus, &text("Focused window")));
+        assert!(failed(&focus, &CallToolResult::error(vec![])));
+        assert!(!failed(&step("focus_window", None), &text("Failed")));
+    }
+}